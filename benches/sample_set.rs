@@ -0,0 +1,75 @@
+//! Compares the linear `Vec` sample set against the hash-indexed one.
+//!
+//! Run with `cargo bench --bench sample_set`. The hashed estimator is run on a stream of 10^8
+//! elements; the linear one is only run on short prefixes since it is `O(m * thresh)`. Set
+//! `DISTINCTION_BENCH_LEN` to change the length of the long stream.
+use std::time::{Duration, Instant};
+
+use distinction::{CvmEstimator, Gen};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+const EPS: f64 = 0.01;
+const DELTA: f64 = 0.005;
+const N_VALUES: u64 = 10_000_000;
+
+fn stream(len: usize) -> impl Iterator<Item = u64> {
+    let mut rng = SmallRng::seed_from_u64(42);
+    (0..len).map(move |_| rng.gen_range(0..N_VALUES))
+}
+
+fn time<F: FnOnce() -> usize>(f: F) -> (usize, Duration) {
+    let start = Instant::now();
+    let estimate = f();
+    (estimate, start.elapsed())
+}
+
+fn report(name: &str, len: usize, (estimate, elapsed): (usize, Duration)) {
+    println!(
+        "{:<8} m = {:>11} estimate = {:>10} time = {:>10.3?} ({:.1} ns/element)",
+        name,
+        len,
+        estimate,
+        elapsed,
+        elapsed.as_nanos() as f64 / len as f64
+    );
+}
+
+fn main() {
+    let long_len = std::env::var("DISTINCTION_BENCH_LEN")
+        .ok()
+        .and_then(|len| len.parse().ok())
+        .unwrap_or(100_000_000);
+
+    for len in [10_000, 100_000] {
+        report(
+            "linear",
+            len,
+            time(|| {
+                let mut estimator = CvmEstimator::new(EPS, DELTA, len, Some(Gen::new(Some(1))));
+                estimator.extend(stream(len));
+                estimator.estimate()
+            }),
+        );
+        report(
+            "hashed",
+            len,
+            time(|| {
+                let mut estimator =
+                    CvmEstimator::new_hashed(EPS, DELTA, len, Some(Gen::new(Some(1))));
+                estimator.extend(stream(len));
+                estimator.estimate()
+            }),
+        );
+    }
+
+    report(
+        "hashed",
+        long_len,
+        time(|| {
+            let mut estimator =
+                CvmEstimator::new_hashed(EPS, DELTA, long_len, Some(Gen::new(Some(1))));
+            estimator.extend(stream(long_len));
+            estimator.estimate()
+        }),
+    );
+}
@@ -0,0 +1,142 @@
+//! Regenerates `src/hyperloglog/bias.rs`, the empirical bias correction tables used by
+//! `HyperLogLogPlusPlus`.
+//!
+//! For every precision the raw HyperLogLog estimate is recorded at evenly spaced cardinalities
+//! between 0 and `5 * 2^precision`, averaged over many independent runs, and stored next to its
+//! bias (mean raw estimate minus the true cardinality), following the procedure of Heule, Nunkesser
+//! and Hall. Run with:
+//!
+//! ```text
+//! cargo run --release --example hll_bias_tables > src/hyperloglog/bias.rs
+//! ```
+use distinction::Gen;
+use rand::RngCore;
+
+const MIN_PRECISION: u8 = 4;
+const MAX_PRECISION: u8 = 18;
+const POINTS: usize = 100;
+const SEED: u64 = 0x6869_6c6c;
+
+/// Number of runs averaged for a precision. Large sketches vary less from run to run.
+fn runs(precision: u8) -> usize {
+    if precision <= 12 {
+        500
+    } else {
+        100
+    }
+}
+
+fn alpha(m: usize) -> f64 {
+    match m {
+        16 => 0.673,
+        32 => 0.697,
+        64 => 0.709,
+        _ => 0.7213 / (1.0 + 1.079 / m as f64),
+    }
+}
+
+/// Returns the `POINTS + 1` evenly spaced cardinalities the raw estimate is sampled at.
+fn cardinalities(precision: u8) -> Vec<usize> {
+    let max = 5 * (1usize << precision);
+    (0..=POINTS).map(|i| i * max / POINTS).collect()
+}
+
+/// Returns the mean raw estimate at each of the sampled cardinalities.
+fn mean_raw_estimates(precision: u8, gen: &mut Gen) -> Vec<f64> {
+    let m = 1usize << precision;
+    let cardinalities = cardinalities(precision);
+    let mut totals = vec![0.0; cardinalities.len()];
+
+    for _ in 0..runs(precision) {
+        let mut registers = vec![0u8; m];
+        // Sum of 2^-register, kept up to date as the registers change.
+        let mut sum = m as f64;
+
+        let mut inserted = 0;
+
+        for (total, &n) in totals.iter_mut().zip(&cardinalities) {
+            // Random 64-bit values stand in for the hashes of distinct elements.
+            while inserted < n {
+                let hash = gen.next_u64();
+                let index = (hash >> (64 - precision)) as usize;
+                let rest = (hash << precision) | (1 << (precision - 1));
+                let rank = rest.leading_zeros() as u8 + 1;
+
+                if rank > registers[index] {
+                    sum += 2f64.powi(-(rank as i32)) - 2f64.powi(-(registers[index] as i32));
+                    registers[index] = rank;
+                }
+                inserted += 1;
+            }
+
+            *total += alpha(m) * (m * m) as f64 / sum;
+        }
+    }
+
+    let runs = runs(precision) as f64;
+    totals.into_iter().map(|total| total / runs).collect()
+}
+
+fn main() {
+    let mut gen = Gen::new(Some(SEED));
+    let mut raw_tables = Vec::new();
+    let mut bias_tables = Vec::new();
+
+    for precision in MIN_PRECISION..=MAX_PRECISION {
+        let raw = mean_raw_estimates(precision, &mut gen);
+        let bias: Vec<f64> = raw
+            .iter()
+            .zip(cardinalities(precision))
+            .map(|(raw, n)| raw - n as f64)
+            .collect();
+
+        raw_tables.push(raw);
+        bias_tables.push(bias);
+    }
+
+    println!("//! Empirical bias correction tables for `HyperLogLogPlusPlus`.");
+    println!("//!");
+    println!(
+        "//! Generated by `cargo run --release --example hll_bias_tables`, do not edit by hand."
+    );
+    println!(
+        "//! Row `i` of each table is for precision `{}` + i.",
+        MIN_PRECISION
+    );
+    println!();
+    print_tables(
+        "RAW_ESTIMATES",
+        "Mean raw estimate at each sampled cardinality.",
+        &raw_tables,
+    );
+    println!();
+    print_tables(
+        "BIAS",
+        "Mean raw estimate minus the true cardinality.",
+        &bias_tables,
+    );
+}
+
+fn print_tables(name: &str, doc: &str, tables: &[Vec<f64>]) {
+    println!("/// {}", doc);
+    println!("#[rustfmt::skip]");
+    println!(
+        "pub(super) const {}: [[f64; {}]; {}] = [",
+        name,
+        POINTS + 1,
+        tables.len()
+    );
+    for table in tables {
+        println!("    [");
+        for chunk in table.chunks(6) {
+            let row: Vec<String> = chunk
+                .iter()
+                // Avoid printing `-0.0` for biases which round to zero.
+                .map(|value| format!("{:.1}", if value.abs() < 0.05 { 0.0 } else { *value }))
+                .collect();
+            println!("        {},", row.join(", "));
+        }
+        println!("    ],");
+    }
+    println!("];");
+}
@@ -0,0 +1,448 @@
+//! Command line parsing.
+use std::{ffi::OsString, fmt, path::PathBuf, str::FromStr};
+
+use distinction::{
+    csv::{CsvFormat, Key},
+    jsonl::{Arrays, JsonlOptions, Missing, Path},
+};
+
+pub const USAGE: &str = "\
+Usage: distinction [OPTIONS] [FILE]...
+
+Prints the approximate number of distinct lines in the FILEs, or in the standard input if there
+are none or FILE is `-`. The lines of all the FILEs are counted as a single stream, in bounded
+memory.
+
+With --csv, --tsv or --columns the FILEs are read as delimited records instead, and the distinct
+values of every column, or of the given columns and composite keys, are reported together. With
+--jsonl or --path every line is read as a JSON document, and the values at the given paths are
+reported the same way.
+
+Options:
+  -a, --algorithm <NAME>  cvm, hll, hll++, kmv or theta [default: cvm]
+  -e, --eps <EPS>         Relative error: the (eps, delta) guarantee of cvm, the standard error
+                          the sketches are sized for [default: 0.05]
+  -d, --delta <DELTA>     Failure probability of cvm [default: 0.01]
+  -s, --seed <SEED>       Seed of the cvm sampling, drawn from the OS by default
+      --exact             Also count exactly, in memory proportional to the distinct lines, and
+                          report the error of the estimate
+      --csv               Count the columns of comma separated values
+      --tsv               Count the columns of tab separated values
+      --delimiter <CHAR>  Delimiter of the columns, in place of the one of --csv or --tsv
+                          [default: `,`]
+  -c, --columns <KEYS>    Comma separated columns to count, by name or 1-based position, where
+                          `a+b` counts the composite key of columns a and b [default: all]
+      --no-header         The first record holds values rather than the names of the columns
+      --jsonl             Count the values of JSON Lines documents [default path: `.`]
+  -p, --path <PATH>       Path of the values to count, such as `.user.id` or `.items[0]`, may be
+                          repeated
+      --missing <POLICY>  Lines without a value at a path: skip, null (count them as one more
+                          value, distinct from JSON null) or error [default: skip]
+      --arrays <MODE>     Arrays as a single value (whole) or as each of their elements
+                          (elements) [default: whole]
+  -f, --format <FORMAT>   Report of the columns and paths, table or json [default: table]
+  -h, --help              Print this help
+  -V, --version           Print the version";
+
+/// The counting algorithms the tool can run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Cvm,
+    HyperLogLog,
+    HyperLogLogPlusPlus,
+    Kmv,
+    Theta,
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "cvm" => Ok(Self::Cvm),
+            "hll" => Ok(Self::HyperLogLog),
+            "hll++" | "hllpp" => Ok(Self::HyperLogLogPlusPlus),
+            "kmv" => Ok(Self::Kmv),
+            "theta" => Ok(Self::Theta),
+            _ => Err(format!("unknown algorithm `{}`", name)),
+        }
+    }
+}
+
+/// How the counts of the columns are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Table,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown report format `{}`", name)),
+        }
+    }
+}
+
+/// What the tool was asked to do.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Count(Options),
+    Help,
+    Version,
+}
+
+/// The options of a count.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub algorithm: Algorithm,
+    pub eps: f64,
+    pub delta: f64,
+    pub seed: Option<u64>,
+    pub exact: bool,
+    /// The format of delimited input, if the files are read as records rather than lines.
+    pub csv: Option<CsvFormat>,
+    /// The columns and keys of the records to count, all of them if empty.
+    pub columns: Vec<Key>,
+    /// How JSON Lines are read, if the files are read as documents rather than lines.
+    pub jsonl: Option<JsonlOptions>,
+    /// The paths of the values to count in the documents, the whole document if empty.
+    pub paths: Vec<Path>,
+    pub report: ReportFormat,
+    /// The files to read, where `-` stands for the standard input.
+    pub files: Vec<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Cvm,
+            eps: 0.05,
+            delta: 0.01,
+            seed: None,
+            exact: false,
+            csv: None,
+            columns: Vec::new(),
+            jsonl: None,
+            paths: Vec::new(),
+            report: ReportFormat::Table,
+            files: Vec::new(),
+        }
+    }
+}
+
+/// A command line which could not be parsed.
+#[derive(Debug, PartialEq)]
+pub struct ArgsError(String);
+
+impl fmt::Display for ArgsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Parses the arguments following the program name.
+pub fn parse<I>(args: I) -> Result<Command, ArgsError>
+where
+    I: IntoIterator<Item = OsString>,
+{
+    let mut options = Options::default();
+    // Applied once every option has been read, so that it wins over `--csv` and `--tsv` wherever
+    // they appear.
+    let mut delimiter = None;
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let Some(arg) = arg
+            .to_str()
+            .filter(|arg| arg.starts_with('-') && *arg != "-")
+        else {
+            options.files.push(arg.into());
+            continue;
+        };
+        // Accept both `--eps 0.1` and `--eps=0.1`.
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name, Some(value.to_owned())),
+            _ => (arg, None),
+        };
+        let mut value = || -> Result<String, ArgsError> {
+            inline
+                .clone()
+                .or_else(|| args.next().and_then(|value| value.into_string().ok()))
+                .ok_or_else(|| ArgsError(format!("missing value for `{}`", name)))
+        };
+
+        match name {
+            "-a" | "--algorithm" => options.algorithm = value()?.parse().map_err(ArgsError)?,
+            "-e" | "--eps" => options.eps = number(name, &value()?)?,
+            "-d" | "--delta" => options.delta = number(name, &value()?)?,
+            "-s" | "--seed" => options.seed = Some(number(name, &value()?)?),
+            "--exact" => options.exact = true,
+            "--csv" => {
+                let format = csv_format(&mut options);
+                format.delimiter = b',';
+                format.quote = Some(b'"');
+            }
+            "--tsv" => {
+                let format = csv_format(&mut options);
+                format.delimiter = b'\t';
+                format.quote = None;
+            }
+            "--delimiter" => {
+                let value = value()?;
+                let [byte] = value.as_bytes()[..] else {
+                    return Err(invalid(name, &value));
+                };
+                delimiter = Some(byte);
+                csv_format(&mut options);
+            }
+            "-c" | "--columns" => {
+                for key in value()?.split(',') {
+                    let key = key
+                        .parse()
+                        .map_err(|error| ArgsError(format!("invalid key `{}`: {}", key, error)))?;
+                    options.columns.push(key);
+                }
+                csv_format(&mut options);
+            }
+            "--no-header" => csv_format(&mut options).has_header = false,
+            "--jsonl" => {
+                options.jsonl.get_or_insert_with(JsonlOptions::default);
+            }
+            "-p" | "--path" => {
+                let path = value()?;
+                let path = path
+                    .parse()
+                    .map_err(|error| ArgsError(format!("{}", error)))?;
+                options.paths.push(path);
+                options.jsonl.get_or_insert_with(JsonlOptions::default);
+            }
+            "--missing" => {
+                let missing = match value()?.as_str() {
+                    "skip" => Missing::Skip,
+                    "null" => Missing::Null,
+                    "error" => Missing::Error,
+                    other => return Err(invalid(name, other)),
+                };
+                options
+                    .jsonl
+                    .get_or_insert_with(JsonlOptions::default)
+                    .missing = missing;
+            }
+            "--arrays" => {
+                let arrays = match value()?.as_str() {
+                    "whole" => Arrays::Whole,
+                    "elements" => Arrays::Elements,
+                    other => return Err(invalid(name, other)),
+                };
+                options
+                    .jsonl
+                    .get_or_insert_with(JsonlOptions::default)
+                    .arrays = arrays;
+            }
+            "-f" | "--format" => options.report = value()?.parse().map_err(ArgsError)?,
+            "-h" | "--help" => return Ok(Command::Help),
+            "-V" | "--version" => return Ok(Command::Version),
+            "--" => {
+                options.files.extend(args.by_ref().map(PathBuf::from));
+            }
+            _ => return Err(ArgsError(format!("unknown option `{}`", arg))),
+        }
+    }
+
+    if let (Some(format), Some(delimiter)) = (&mut options.csv, delimiter) {
+        format.delimiter = delimiter;
+    }
+    if options.csv.is_some() && options.jsonl.is_some() {
+        return Err(ArgsError(
+            "delimited and JSON Lines options cannot be combined".into(),
+        ));
+    }
+    Ok(Command::Count(options))
+}
+
+/// Returns the delimited format chosen so far, switching to CSV if the files were to be read as
+/// lines.
+fn csv_format(options: &mut Options) -> &mut CsvFormat {
+    options.csv.get_or_insert_with(CsvFormat::csv)
+}
+
+fn number<T: FromStr>(name: &str, value: &str) -> Result<T, ArgsError> {
+    value.parse().map_err(|_| invalid(name, value))
+}
+
+fn invalid(name: &str, value: &str) -> ArgsError {
+    ArgsError(format!("invalid value `{}` for `{}`", value, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_args(args: &[&str]) -> Result<Command, ArgsError> {
+        parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn parses_options_and_files() {
+        let command = parse_args(&[
+            "-a",
+            "hll",
+            "--eps=0.01",
+            "--delta",
+            "0.1",
+            "-s",
+            "7",
+            "--exact",
+            "a.txt",
+            "-",
+            "--",
+            "--b.txt",
+        ]);
+        assert_eq!(
+            command,
+            Ok(Command::Count(Options {
+                algorithm: Algorithm::HyperLogLog,
+                eps: 0.01,
+                delta: 0.1,
+                seed: Some(7),
+                exact: true,
+                files: vec!["a.txt".into(), "-".into(), "--b.txt".into()],
+                ..Options::default()
+            }))
+        );
+        assert_eq!(parse_args(&[]), Ok(Command::Count(Options::default())));
+        assert_eq!(parse_args(&["a", "--help"]), Ok(Command::Help));
+    }
+
+    #[test]
+    fn parses_column_options() {
+        let Ok(Command::Count(options)) =
+            parse_args(&["--no-header", "--tsv", "-c", "1,2+3", "--format=json", "-"])
+        else {
+            panic!("options should parse");
+        };
+        assert_eq!(
+            options.csv,
+            Some(CsvFormat {
+                has_header: false,
+                ..CsvFormat::tsv()
+            })
+        );
+        assert_eq!(options.columns, [Key::new(["1"]), Key::new(["2", "3"])]);
+        assert_eq!(options.report, ReportFormat::Json);
+
+        let Ok(Command::Count(options)) = parse_args(&["--columns", "id", "--delimiter", ";"])
+        else {
+            panic!("options should parse");
+        };
+        assert_eq!(
+            options.csv,
+            Some(CsvFormat {
+                delimiter: b';',
+                ..CsvFormat::csv()
+            })
+        );
+        // An explicit delimiter wins over the one of `--csv` or `--tsv`, whichever comes first.
+        for args in [["--delimiter", ";", "--csv"], ["--csv", "--delimiter", ";"]] {
+            let Ok(Command::Count(options)) = parse_args(&args) else {
+                panic!("options should parse");
+            };
+            assert_eq!(
+                options.csv,
+                Some(CsvFormat {
+                    delimiter: b';',
+                    ..CsvFormat::csv()
+                })
+            );
+        }
+        let Ok(Command::Count(options)) = parse_args(&["--delimiter", "|", "--tsv"]) else {
+            panic!("options should parse");
+        };
+        assert_eq!(
+            options.csv,
+            Some(CsvFormat {
+                delimiter: b'|',
+                ..CsvFormat::tsv()
+            })
+        );
+        assert_eq!(
+            parse_args(&["--delimiter", "::"]),
+            Err(ArgsError("invalid value `::` for `--delimiter`".into()))
+        );
+        assert_eq!(
+            parse_args(&["-c", "a,,b"]),
+            Err(ArgsError("invalid key ``: unknown column ``".into()))
+        );
+    }
+
+    #[test]
+    fn parses_json_lines_options() {
+        let Ok(Command::Count(options)) = parse_args(&[
+            "-p",
+            ".user.id",
+            "--path=.tags",
+            "--missing",
+            "null",
+            "--arrays=elements",
+        ]) else {
+            panic!("options should parse");
+        };
+        assert_eq!(
+            options.jsonl,
+            Some(JsonlOptions {
+                missing: Missing::Null,
+                arrays: Arrays::Elements,
+            })
+        );
+        assert_eq!(
+            options.paths,
+            [".user.id".parse().unwrap(), ".tags".parse().unwrap()]
+        );
+        assert_eq!(
+            parse_args(&["--jsonl"]),
+            Ok(Command::Count(Options {
+                jsonl: Some(JsonlOptions::default()),
+                ..Options::default()
+            }))
+        );
+        assert_eq!(
+            parse_args(&["-p", "user"]),
+            Err(ArgsError("invalid path `user`".into()))
+        );
+        assert_eq!(
+            parse_args(&["--missing", "ignore"]),
+            Err(ArgsError("invalid value `ignore` for `--missing`".into()))
+        );
+        assert_eq!(
+            parse_args(&["--csv", "--jsonl"]),
+            Err(ArgsError(
+                "delimited and JSON Lines options cannot be combined".into()
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_arguments() {
+        assert_eq!(
+            parse_args(&["--eps"]),
+            Err(ArgsError("missing value for `--eps`".into()))
+        );
+        assert_eq!(
+            parse_args(&["-s", "-1"]),
+            Err(ArgsError("invalid value `-1` for `-s`".into()))
+        );
+        assert_eq!(
+            parse_args(&["-a", "bloom"]),
+            Err(ArgsError("unknown algorithm `bloom`".into()))
+        );
+        assert_eq!(
+            parse_args(&["--fast"]),
+            Err(ArgsError("unknown option `--fast`".into()))
+        );
+    }
+}
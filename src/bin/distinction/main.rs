@@ -0,0 +1,291 @@
+//! Counts the distinct lines of files or of the standard input, or the distinct values of their
+//! columns or of fields of their JSON documents, see `distinction --help`.
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use distinction::{
+    csv::{CsvCounter, CsvFormat},
+    hyperloglog,
+    jsonl::{JsonlCounter, JsonlOptions},
+    report::ColumnCounters,
+    theta, CvmSketch, DistinctCounter, DistinctError, Estimate, ExactCounter, Gen, HyperLogLog,
+    HyperLogLogPlusPlus, KmvSketch, ThetaSketch,
+};
+
+mod args;
+
+use args::{Algorithm, Command, Options, ReportFormat};
+
+/// A counter of lines or of the values of a column, whichever the algorithm.
+type LineCounter = Box<dyn for<'a> DistinctCounter<&'a [u8]>>;
+
+/// The counter of a column, along with an exact count of its values with `--exact`.
+struct ColumnCounter {
+    counter: LineCounter,
+    exact: Option<ExactCounter<Vec<u8>>>,
+}
+
+impl DistinctCounter<&[u8]> for ColumnCounter {
+    fn insert(&mut self, item: &[u8]) {
+        self.counter.insert(item);
+        if let Some(exact) = &mut self.exact {
+            DistinctCounter::insert(exact, item);
+        }
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.counter.estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        let exact = self.exact.as_ref();
+        self.counter.memory_bytes() + exact.map_or(0, DistinctCounter::<&[u8]>::memory_bytes)
+    }
+
+    fn reset(&mut self) {
+        self.counter.reset();
+        if let Some(exact) = &mut self.exact {
+            *exact = ExactCounter::new();
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counter.clear();
+        if let Some(exact) = &mut self.exact {
+            DistinctCounter::<&[u8]>::clear(exact);
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    #[cfg(feature = "use_logging")]
+    let _ = env_logger::try_init();
+
+    let options = match args::parse(env::args_os().skip(1)) {
+        Ok(Command::Count(options)) => options,
+        Ok(Command::Help) => {
+            println!("{}", args::USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::Version) => {
+            println!("distinction {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::SUCCESS;
+        }
+        Err(error) => {
+            eprintln!("distinction: {}\n\n{}", error, args::USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(&options) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("distinction: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    if let Some(format) = &options.csv {
+        count_columns(options, format)
+    } else if let Some(jsonl) = &options.jsonl {
+        count_paths(options, jsonl)
+    } else {
+        count_lines(options)
+    }
+}
+
+fn count_lines(options: &Options) -> Result<(), String> {
+    let mut counter = counter(options).map_err(|error| error.to_string())?;
+    let mut exact = options.exact.then(ExactCounter::<Vec<u8>>::new);
+
+    for path in &files(options) {
+        for_each_line(open(path)?, |line| {
+            counter.insert(line);
+            if let Some(exact) = &mut exact {
+                DistinctCounter::insert(exact, line);
+            }
+        })
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+    }
+
+    let estimate = counter.estimate().map_err(|error| error.to_string())?;
+    let mut stdout = io::stdout().lock();
+    let result = match exact {
+        None => writeln!(stdout, "{}", estimate.count()),
+        Some(exact) => {
+            let exact = exact.estimate();
+            let error = (estimate.value - exact as f64) / (exact as f64).max(1.0);
+            writeln!(
+                stdout,
+                "estimate {}\nexact    {}\nerror    {:+.2}%",
+                estimate.count(),
+                exact,
+                error * 100.0
+            )
+        }
+    };
+    result.map_err(|error| error.to_string())
+}
+
+fn count_columns(options: &Options, format: &CsvFormat) -> Result<(), String> {
+    // Every column gets a counter built from the same options, so checking them once is enough.
+    counter(options).map_err(|error| error.to_string())?;
+    let mut counter = CsvCounter::new(format.clone(), options.columns.clone(), || {
+        column_counter(options)
+    });
+    for path in &files(options) {
+        counter
+            .read(open(path)?)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+    }
+    print_report(options, counter.finish())
+}
+
+fn count_paths(options: &Options, jsonl: &JsonlOptions) -> Result<(), String> {
+    counter(options).map_err(|error| error.to_string())?;
+    let paths = if options.paths.is_empty() {
+        vec![".".parse().expect("`.` is a path")]
+    } else {
+        options.paths.clone()
+    };
+    let mut counter = JsonlCounter::new(*jsonl, paths, || column_counter(options));
+    for path in &files(options) {
+        counter
+            .read(open(path)?)
+            .map_err(|error| format!("{}: {}", path.display(), error))?;
+    }
+    print_report(options, counter.finish())
+}
+
+/// Builds the counter of a column or path, from options already checked by [`counter`].
+fn column_counter(options: &Options) -> ColumnCounter {
+    ColumnCounter {
+        counter: counter(options).expect("options were checked"),
+        exact: options.exact.then(ExactCounter::new),
+    }
+}
+
+fn print_report(options: &Options, counters: ColumnCounters<ColumnCounter>) -> Result<(), String> {
+    let mut report = counters.report().map_err(|error| error.to_string())?;
+    for (column, (_, counter)) in report.columns.iter_mut().zip(&counters.columns) {
+        column.exact = counter.exact.as_ref().map(ExactCounter::estimate);
+    }
+    match options.report {
+        ReportFormat::Table => println!("{}", report),
+        ReportFormat::Json => println!("{}", report.to_json()),
+    }
+    Ok(())
+}
+
+/// Returns the files to read, the standard input if none were given.
+fn files(options: &Options) -> Vec<PathBuf> {
+    if options.files.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        options.files.clone()
+    }
+}
+
+/// Opens `path` for reading, or the standard input if it is `-`.
+fn open(path: &Path) -> Result<Box<dyn BufRead>, String> {
+    if path == Path::new("-") {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let file = File::open(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Builds the counter for the chosen algorithm. The sketches are sized so that their standard
+/// error is about `eps`.
+fn counter(options: &Options) -> Result<LineCounter, DistinctError> {
+    let eps = options.eps;
+    if !(eps > 0.0 && eps < 1.0) {
+        return Err(DistinctError::InvalidEpsilon(eps));
+    }
+    // HyperLogLog sketches have a standard error of `1.04 / sqrt(2^precision)`, KMV and theta
+    // sketches of about `1 / sqrt(k)`.
+    let hll_precision = (2.0 * (1.04 / eps).log2()).ceil().clamp(
+        hyperloglog::MIN_PRECISION.into(),
+        hyperloglog::MAX_PRECISION.into(),
+    ) as u8;
+    let k = (1.0 / (eps * eps)).ceil();
+
+    Ok(match options.algorithm {
+        Algorithm::Cvm => Box::new(CvmSketch::try_unbounded(
+            eps,
+            options.delta,
+            Some(Gen::new(options.seed)),
+        )?),
+        Algorithm::HyperLogLog => Box::new(HyperLogLog::try_new(hll_precision)?),
+        Algorithm::HyperLogLogPlusPlus => Box::new(HyperLogLogPlusPlus::try_new(hll_precision)?),
+        Algorithm::Kmv => Box::new(KmvSketch::try_new(k as usize + 2)?),
+        Algorithm::Theta => Box::new(ThetaSketch::try_new(
+            k.log2()
+                .ceil()
+                .clamp(theta::MIN_LG_K.into(), theta::MAX_LG_K.into()) as u8,
+        )?),
+    })
+}
+
+/// Calls `f` with every line of `reader`, without its line terminator, reusing a single buffer so
+/// that memory does not grow with the input.
+fn for_each_line<R, F>(mut reader: R, mut f: F) -> io::Result<()>
+where
+    R: BufRead,
+    F: FnMut(&[u8]),
+{
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        let mut end = line.len();
+        if line[..end].ends_with(b"\n") {
+            end -= 1;
+        }
+        if line[..end].ends_with(b"\r") {
+            end -= 1;
+        }
+        f(&line[..end]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_lose_their_terminators() {
+        let mut lines = Vec::new();
+        for_each_line(&b"a\nb\r\n\nc"[..], |line| lines.push(line.to_vec())).unwrap();
+        assert_eq!(lines, [&b"a"[..], b"b", b"", b"c"]);
+    }
+
+    #[test]
+    fn every_algorithm_counts_lines() {
+        let input: Vec<u8> = (0..20_000)
+            .flat_map(|i: u32| format!("line {}\n", i % 5000).into_bytes())
+            .collect();
+        for algorithm in ["cvm", "hll", "hll++", "kmv", "theta"] {
+            let options = Options {
+                algorithm: algorithm.parse().unwrap(),
+                eps: 0.02,
+                seed: Some(1),
+                ..Options::default()
+            };
+            let mut counter = counter(&options).unwrap();
+            for_each_line(&input[..], |line| counter.insert(line)).unwrap();
+
+            let count = counter.estimate().unwrap().count();
+            assert!(count.abs_diff(5000) < 500, "{}: {}", algorithm, count);
+        }
+    }
+}
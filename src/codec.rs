@@ -0,0 +1,345 @@
+//! The binary format the sketches are persisted in, through their `to_bytes` and `from_bytes`
+//! methods.
+//!
+//! Every sketch is written as a frame, all integers little endian:
+//!
+//! | Offset    | Size | Content                                              |
+//! |-----------|------|------------------------------------------------------|
+//! | 0         | 4    | Magic bytes `DSTN`                                   |
+//! | 4         | 1    | Format version, currently [`FORMAT_VERSION`]         |
+//! | 5         | 1    | Kind of sketch, see [`Kind`]                         |
+//! | 6         | 4    | Length `n` of the payload in bytes                   |
+//! | 10        | n    | Payload, specific to the kind of sketch              |
+//! | 10 + n    | 4    | CRC-32 (IEEE) of the `10 + n` bytes before it        |
+//!
+//! Payloads are a fixed sequence of fields, with `usize` values widened to 64 bits and `f64`
+//! values stored as their IEEE 754 bits, followed by the variable length part of the sketch,
+//! sorted so that equal sketches produce equal bytes.
+//!
+//! The format evolves under these rules:
+//!
+//! - Compatible additions append new fields to the end of a payload. Readers ignore the payload
+//!   bytes past the fields they know, so older versions of the crate keep reading newer frames of
+//!   the same format version, and newer versions fill in defaults for fields missing from older
+//!   frames.
+//! - Any other change bumps the format version. Readers reject frames with a version newer than
+//!   their own, and keep reading all older versions.
+//! - Kind numbers are never reused.
+use std::fmt;
+
+/// Version of the format written by this version of the crate.
+pub const FORMAT_VERSION: u8 = 1;
+
+const MAGIC: &[u8; 4] = b"DSTN";
+const HEADER_LEN: usize = 10;
+const CHECKSUM_LEN: usize = 4;
+
+/// The kind of sketch stored in a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum Kind {
+    /// A [`CvmSketch`](crate::CvmSketch).
+    Cvm = 1,
+    /// A [`HyperLogLog`](crate::HyperLogLog).
+    HyperLogLog = 2,
+    /// A [`HyperLogLogPlusPlus`](crate::HyperLogLogPlusPlus).
+    HyperLogLogPlusPlus = 3,
+    /// A [`KmvSketch`](crate::KmvSketch).
+    Kmv = 4,
+    /// A [`ThetaSketch`](crate::ThetaSketch).
+    Theta = 5,
+    /// A [`CompactThetaSketch`](crate::CompactThetaSketch).
+    CompactTheta = 6,
+}
+
+/// The reasons a frame can fail to decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The bytes end before the frame or one of its fields does.
+    Truncated,
+    /// The bytes do not start with the magic bytes of the format.
+    BadMagic,
+    /// The frame was written by a newer, incompatible version of the format.
+    UnsupportedVersion(u8),
+    /// The frame holds a different kind of sketch than the one being decoded, as the raw kind
+    /// numbers `(expected, found)`.
+    KindMismatch(u8, u8),
+    /// The checksum does not match the contents of the frame.
+    ChecksumMismatch,
+    /// The frame is well formed but describes a sketch which cannot exist.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Truncated => write!(f, "sketch bytes are truncated"),
+            Self::BadMagic => write!(f, "not a serialized sketch"),
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported sketch format version {}", version)
+            }
+            Self::KindMismatch(expected, found) => write!(
+                f,
+                "expected a sketch of kind {}, found kind {}",
+                expected, found
+            ),
+            Self::ChecksumMismatch => write!(f, "sketch checksum mismatch"),
+            Self::Invalid(reason) => write!(f, "invalid sketch: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+/// Writes a frame of the given kind, with the payload written by `payload`.
+pub(crate) fn encode(kind: Kind, payload: impl FnOnce(&mut Writer)) -> Vec<u8> {
+    let mut writer = Writer::new();
+    writer.0.extend_from_slice(MAGIC);
+    writer.u8(FORMAT_VERSION);
+    writer.u8(kind as u8);
+    writer.u32(0);
+
+    payload(&mut writer);
+
+    let mut bytes = writer.0;
+    let len = (bytes.len() - HEADER_LEN) as u32;
+    bytes[6..HEADER_LEN].copy_from_slice(&len.to_le_bytes());
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Checks the frame in `bytes` and reads its payload with `payload`, which is given the format
+/// version the frame was written with.
+pub(crate) fn decode<T>(
+    bytes: &[u8],
+    kind: Kind,
+    payload: impl FnOnce(&mut Reader, u8) -> Result<T, DecodeError>,
+) -> Result<T, DecodeError> {
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(if MAGIC.starts_with(bytes) {
+            DecodeError::Truncated
+        } else {
+            DecodeError::BadMagic
+        });
+    }
+    let mut header = Reader(&bytes[MAGIC.len()..]);
+    let version = header.u8()?;
+    let found = header.u8()?;
+    let len = header.u32()? as usize;
+
+    if version > FORMAT_VERSION || version == 0 {
+        return Err(DecodeError::UnsupportedVersion(version));
+    }
+    let frame_len = HEADER_LEN.checked_add(len).ok_or(DecodeError::Truncated)?;
+    if bytes.len() < frame_len + CHECKSUM_LEN {
+        return Err(DecodeError::Truncated);
+    }
+    let (frame, checksum) = bytes.split_at(frame_len);
+    if crc32(frame).to_le_bytes() != checksum[..CHECKSUM_LEN] {
+        return Err(DecodeError::ChecksumMismatch);
+    }
+    if found != kind as u8 {
+        return Err(DecodeError::KindMismatch(kind as u8, found));
+    }
+
+    // Trailing payload bytes hold fields appended by newer versions and are ignored.
+    payload(&mut Reader(&frame[HEADER_LEN..]), version)
+}
+
+/// Appends little endian fields to a payload.
+pub(crate) struct Writer(Vec<u8>);
+
+impl Writer {
+    pub(crate) fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub(crate) fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub(crate) fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub(crate) fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    pub(crate) fn f64(&mut self, value: f64) {
+        self.u64(value.to_bits());
+    }
+
+    pub(crate) fn bytes(&mut self, value: &[u8]) {
+        self.0.extend_from_slice(value);
+    }
+}
+
+/// Reads little endian fields from a payload.
+pub(crate) struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Self {
+        Self(bytes)
+    }
+
+    /// Returns the number of bytes left to read.
+    pub(crate) fn remaining(&self) -> usize {
+        self.0.len()
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+        let (head, rest) = self.0.split_first_chunk().ok_or(DecodeError::Truncated)?;
+        self.0 = rest;
+        Ok(*head)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, DecodeError> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, DecodeError> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, DecodeError> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    pub(crate) fn usize(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.u64()?).map_err(|_| DecodeError::Invalid("length overflows usize"))
+    }
+
+    pub(crate) fn f64(&mut self) -> Result<f64, DecodeError> {
+        self.u64().map(f64::from_bits)
+    }
+
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.0.len() < len {
+            return Err(DecodeError::Truncated);
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    /// Reads a length prefixed list of `u64` values, which must be strictly increasing.
+    pub(crate) fn sorted_u64s(&mut self) -> Result<Vec<u64>, DecodeError> {
+        let len = self.usize()?;
+        // Do not trust the length for the allocation before knowing the bytes are there.
+        if self.0.len() / 8 < len {
+            return Err(DecodeError::Truncated);
+        }
+
+        let values = (0..len)
+            .map(|_| self.u64())
+            .collect::<Result<Vec<_>, _>>()?;
+        if values.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(DecodeError::Invalid("hashes are not sorted"));
+        }
+        Ok(values)
+    }
+}
+
+/// Computes the CRC-32 checksum (IEEE polynomial, as used by zlib and PNG) of `bytes`.
+fn crc32(bytes: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut crc = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xedb8_8320
+                } else {
+                    crc >> 1
+                };
+                bit += 1;
+            }
+            table[i] = crc;
+            i += 1;
+        }
+        table
+    };
+
+    !bytes.iter().fold(!0, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Vec<u8> {
+        encode(Kind::Kmv, |writer| {
+            writer.u64(7);
+            writer.u8(1);
+        })
+    }
+
+    fn read(bytes: &[u8]) -> Result<(u64, u8), DecodeError> {
+        decode(bytes, Kind::Kmv, |reader, _| {
+            Ok((reader.u64()?, reader.u8()?))
+        })
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn corrupted_frames_are_rejected() {
+        let bytes = frame();
+        assert_eq!(read(&bytes), Ok((7, 1)));
+
+        for i in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[i] ^= 0x10;
+            assert!(read(&corrupted).is_err());
+        }
+        for len in 0..bytes.len() {
+            assert_eq!(read(&bytes[..len]), Err(DecodeError::Truncated));
+        }
+
+        assert_eq!(read(b"PNG\r\n"), Err(DecodeError::BadMagic));
+        assert_eq!(
+            decode(&bytes, Kind::Theta, |_, _| Ok(())),
+            Err(DecodeError::KindMismatch(5, 4))
+        );
+
+        let mut newer = bytes.clone();
+        newer[4] = FORMAT_VERSION + 1;
+        assert_eq!(read(&newer), Err(DecodeError::UnsupportedVersion(2)));
+    }
+
+    #[test]
+    fn appended_fields_are_ignored() {
+        let bytes = encode(Kind::Kmv, |writer| {
+            writer.u64(7);
+            writer.u8(1);
+            writer.bytes(b"a field from the future");
+        });
+        assert_eq!(read(&bytes), Ok((7, 1)));
+    }
+}
@@ -0,0 +1,142 @@
+//! A common interface over all distinct counters in the crate.
+use crate::{DistinctError, Estimate};
+
+/// Something which counts, exactly or approximately, the distinct elements of a stream of `T`.
+///
+/// Every counter in the crate implements this trait, so pipeline code can be written once and the
+/// algorithm picked through configuration. The trait is object safe, apart from
+/// [`insert_batch`](DistinctCounter::insert_batch) which is also available on boxed counters.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{CvmSketch, DistinctCounter, ExactCounter, HyperLogLog};
+/// fn counter(algorithm: &str) -> Box<dyn DistinctCounter<u64>> {
+///     match algorithm {
+///         "cvm" => Box::new(CvmSketch::unbounded(0.1, 0.005, None)),
+///         "hll" => Box::new(HyperLogLog::new(14)),
+///         _ => Box::new(ExactCounter::new()),
+///     }
+/// }
+///
+/// for algorithm in ["cvm", "hll", "exact"] {
+///     let mut counter = counter(algorithm);
+///     counter.insert_batch([1, 10, 20, 10, 10, 30, 20, 10, 20, 20, 1, 1, 1]);
+///     assert_eq!(counter.estimate().unwrap().count(), 4);
+/// }
+/// ```
+pub trait DistinctCounter<T> {
+    /// Feeds a single element of the stream into the counter.
+    fn insert(&mut self, item: T);
+
+    /// Feeds every element of `items` into the counter.
+    fn insert_batch<I>(&mut self, items: I)
+    where
+        I: IntoIterator<Item = T>,
+        Self: Sized,
+    {
+        for item in items {
+            self.insert(item);
+        }
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far.
+    fn estimate(&self) -> Result<Estimate, DistinctError>;
+
+    /// Returns the approximate number of bytes the counter occupies, including its heap
+    /// allocations.
+    fn memory_bytes(&self) -> usize;
+
+    /// Returns the counter to the state it was created in, releasing the memory it grew into.
+    /// Randomized counters keep drawing from their generator rather than rewinding it.
+    fn reset(&mut self);
+
+    /// Forgets every element seen so far, keeping the counter's configuration and its allocated
+    /// memory so that it can be refilled without reallocating.
+    fn clear(&mut self);
+}
+
+impl<T, C> DistinctCounter<T> for Box<C>
+where
+    C: DistinctCounter<T> + ?Sized,
+{
+    fn insert(&mut self, item: T) {
+        (**self).insert(item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        (**self).estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        (**self).memory_bytes()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset();
+    }
+
+    fn clear(&mut self) {
+        (**self).clear();
+    }
+}
+
+/// A counter whose state can be folded into another one's, so that it counts the distinct elements
+/// of both streams without going back to them.
+///
+/// The merged counter counts exactly what a single counter fed both streams would, whether the
+/// streams overlap or not, so counters of small buckets of a stream can be rolled up into counters
+/// of any range of them, as a [`WindowedCounter`](crate::WindowedCounter) does.
+/// [`CvmSketch`](crate::CvmSketch) does not implement it, since its merge overcounts the elements
+/// present in both streams.
+pub trait Mergeable {
+    /// Folds the state of `other` into this counter.
+    ///
+    /// Fails if the two counters were configured in ways that cannot be combined.
+    fn merge(&mut self, other: &Self) -> Result<(), DistinctError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CvmEstimator, CvmSketch, ExactCounter, Gen, HashSample, HyperLogLog, HyperLogLogPlusPlus,
+        L0Sketch, ThetaSketch,
+    };
+
+    fn check_clear_and_reset<C: DistinctCounter<u64>>(mut counter: C) {
+        let empty = counter.memory_bytes();
+        counter.insert_batch((0..5000).map(|i| i % 1000));
+        assert!(counter.estimate().unwrap().count().abs_diff(1000) <= 10);
+
+        let filled = counter.memory_bytes();
+        assert!(filled >= empty);
+
+        counter.clear();
+        let estimate = counter.estimate().unwrap();
+        assert_eq!((estimate.count(), estimate.processed), (0, 0));
+        assert_eq!(counter.memory_bytes(), filled);
+
+        counter.insert_batch(0..10);
+        counter.reset();
+        assert_eq!(counter.estimate().unwrap().count(), 0);
+        assert_eq!(counter.memory_bytes(), empty);
+
+        counter.insert_batch(0..10);
+        assert_eq!(counter.estimate().unwrap().count(), 10);
+    }
+
+    #[test]
+    fn counters_clear_and_reset() {
+        check_clear_and_reset(ExactCounter::new());
+        check_clear_and_reset(HyperLogLog::new(16));
+        check_clear_and_reset(HyperLogLogPlusPlus::new(16));
+        check_clear_and_reset(ThetaSketch::new(12));
+        check_clear_and_reset(L0Sketch::new(2048));
+        check_clear_and_reset(CvmSketch::unbounded(0.1, 0.005, Some(Gen::new(Some(1)))));
+        check_clear_and_reset(CvmEstimator::<u64, HashSample<u64>>::new_hashed(
+            0.1, 0.005, 5000, None,
+        ));
+        check_clear_and_reset(CvmEstimator::<u64>::new(0.1, 0.005, 5000, None));
+        check_clear_and_reset::<Box<dyn DistinctCounter<u64>>>(Box::new(ExactCounter::new()));
+    }
+}
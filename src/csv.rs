@@ -0,0 +1,520 @@
+//! Counting the distinct values of the columns of CSV and TSV files.
+//!
+//! A [`CsvCounter`] reads delimited records from any [`BufRead`] and feeds the value of every
+//! selected column, or composite key, to a counter of its own, so that all of them are counted in
+//! a single pass over the input and in memory bounded by the counters. The records are parsed by a
+//! small [`CsvReader`] which handles quoted fields, quotes escaped by doubling them, delimiters and
+//! line breaks within quotes, and both `\n` and `\r\n` line endings.
+//!
+//! # Examples
+//! ```rust
+//! use distinction::{
+//!     csv::{CsvCounter, CsvFormat},
+//!     HyperLogLog,
+//! };
+//! let input = "\
+//! user_id,country,page
+//! 1,fr,/
+//! 2,de,\"/search?q=a,b\"
+//! 1,fr,/about
+//! 3,fr,/
+//! ";
+//! let keys = ["user_id".parse().unwrap(), "country+page".parse().unwrap()];
+//! let mut counter = CsvCounter::new(CsvFormat::csv(), keys, || HyperLogLog::new(12));
+//! counter.read(input.as_bytes()).unwrap();
+//!
+//! let report = counter.finish().report().unwrap();
+//! assert_eq!(report.rows, 4);
+//! assert_eq!(report.columns[0].name, "user_id");
+//! assert_eq!(report.columns[0].estimate.count(), 3);
+//! assert_eq!(report.columns[1].name, "country+page");
+//! assert_eq!(report.columns[1].estimate.count(), 3);
+//! ```
+use std::{fmt, io, io::BufRead, str::FromStr};
+
+use crate::{report::ColumnCounters, DistinctCounter};
+
+/// The dialect of a delimited file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvFormat {
+    /// The byte separating the fields of a record.
+    pub delimiter: u8,
+    /// The byte quoting fields, or `None` if quotes have no special meaning.
+    pub quote: Option<u8>,
+    /// Whether the first record of every input names the columns.
+    pub has_header: bool,
+}
+
+impl CsvFormat {
+    /// Comma separated values quoted with `"`, with a header.
+    pub fn csv() -> Self {
+        Self {
+            delimiter: b',',
+            quote: Some(b'"'),
+            has_header: true,
+        }
+    }
+
+    /// Tab separated values without quoting, with a header.
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: b'\t',
+            quote: None,
+            has_header: true,
+        }
+    }
+}
+
+impl Default for CsvFormat {
+    fn default() -> Self {
+        Self::csv()
+    }
+}
+
+/// The columns whose values are counted together: a single column, or a composite key made of
+/// several.
+///
+/// A column is named by its header, or by its 1-based position in the record. Keys parse from the
+/// names of their columns joined with `+`, as in `user_id+country`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key(Vec<String>);
+
+impl Key {
+    /// Creates a key made of the given columns.
+    ///
+    /// # Panics
+    /// Panics if `columns` is empty.
+    pub fn new<I, S>(columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let columns: Vec<String> = columns.into_iter().map(Into::into).collect();
+        assert!(!columns.is_empty(), "a key needs at least one column");
+        Self(columns)
+    }
+
+    /// Returns the names of the columns of the key.
+    pub fn columns(&self) -> &[String] {
+        &self.0
+    }
+
+    /// Returns the index in `header` of every column of the key.
+    fn resolve(&self, header: Option<&[String]>) -> Result<Vec<usize>, CsvError> {
+        self.0
+            .iter()
+            .map(|column| {
+                header
+                    .and_then(|header| header.iter().position(|name| name == column))
+                    .or_else(|| column.parse::<usize>().ok()?.checked_sub(1))
+                    .ok_or_else(|| CsvError::UnknownColumn(column.clone()))
+            })
+            .collect()
+    }
+}
+
+impl FromStr for Key {
+    type Err = CsvError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split('+').find(|column| column.is_empty()) {
+            Some(column) => Err(CsvError::UnknownColumn(column.to_owned())),
+            None => Ok(Self(s.split('+').map(str::to_owned).collect())),
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join("+"))
+    }
+}
+
+/// The reasons delimited input can fail to be counted.
+#[derive(Debug)]
+pub enum CsvError {
+    /// Reading the input failed.
+    Io(io::Error),
+    /// A key names a column which is neither in the header nor a valid position.
+    UnknownColumn(String),
+    /// The input ends within the quoted field of the record starting on this line.
+    UnterminatedQuote(usize),
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => error.fmt(f),
+            Self::UnknownColumn(column) => write!(f, "unknown column `{}`", column),
+            Self::UnterminatedQuote(line) => {
+                write!(f, "unterminated quoted field in record on line {}", line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Reads the records of a delimited input one at a time, reusing its buffers so that memory does
+/// not grow with the input.
+///
+/// Blank lines are skipped. A quote only opens a quoted field at the start of the field, and is
+/// taken literally anywhere else.
+#[derive(Debug)]
+pub struct CsvReader<R> {
+    reader: R,
+    format: CsvFormat,
+    line: Vec<u8>,
+    line_number: usize,
+    fields: Vec<u8>,
+    ends: Vec<usize>,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    /// Creates a reader of records in `format` from `reader`.
+    pub fn new(reader: R, format: CsvFormat) -> Self {
+        Self {
+            reader,
+            format,
+            line: Vec::new(),
+            line_number: 0,
+            fields: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+
+    /// Reads the next record, or returns `None` at the end of the input.
+    pub fn read_record(&mut self) -> Result<Option<Record<'_>>, CsvError> {
+        self.fields.clear();
+        self.ends.clear();
+        loop {
+            if !self.read_line()? {
+                return Ok(None);
+            }
+            if !self.line.is_empty() {
+                break;
+            }
+        }
+
+        let first_line = self.line_number;
+        let mut quoted = false;
+        let mut field_start = true;
+        loop {
+            let mut bytes = self.line.iter().copied().peekable();
+            while let Some(byte) = bytes.next() {
+                if quoted {
+                    if Some(byte) == self.format.quote {
+                        if bytes.peek() == Some(&byte) {
+                            bytes.next();
+                            self.fields.push(byte);
+                        } else {
+                            quoted = false;
+                        }
+                    } else {
+                        self.fields.push(byte);
+                    }
+                } else if byte == self.format.delimiter {
+                    self.ends.push(self.fields.len());
+                    field_start = true;
+                    continue;
+                } else if field_start && Some(byte) == self.format.quote {
+                    quoted = true;
+                } else {
+                    self.fields.push(byte);
+                }
+                field_start = false;
+            }
+            if !quoted {
+                break;
+            }
+            // The line break belongs to the quoted field.
+            self.fields.push(b'\n');
+            if !self.read_line()? {
+                return Err(CsvError::UnterminatedQuote(first_line));
+            }
+        }
+        self.ends.push(self.fields.len());
+
+        Ok(Some(Record {
+            fields: &self.fields,
+            ends: &self.ends,
+        }))
+    }
+
+    /// Reads the next line into `self.line` without its terminator, returning `false` at the end
+    /// of the input.
+    fn read_line(&mut self) -> io::Result<bool> {
+        self.line.clear();
+        if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+            return Ok(false);
+        }
+        self.line_number += 1;
+        if self.line.ends_with(b"\n") {
+            self.line.pop();
+        }
+        if self.line.ends_with(b"\r") {
+            self.line.pop();
+        }
+        Ok(true)
+    }
+}
+
+/// A record read by a [`CsvReader`], with quotes removed from its fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Record<'a> {
+    fields: &'a [u8],
+    ends: &'a [usize],
+}
+
+impl<'a> Record<'a> {
+    /// Returns the number of fields of the record.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Returns whether the record has no fields, which a record read from input never does.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Returns the field at `index`, or `None` if the record is shorter.
+    pub fn get(&self, index: usize) -> Option<&'a [u8]> {
+        let end = *self.ends.get(index)?;
+        let start = index.checked_sub(1).map_or(0, |i| self.ends[i]);
+        Some(&self.fields[start..end])
+    }
+
+    /// Returns an iterator over the fields of the record.
+    pub fn iter(&self) -> impl Iterator<Item = &'a [u8]> + '_ {
+        (0..self.len()).filter_map(|i| self.get(i))
+    }
+}
+
+/// Counts the distinct values of columns of delimited inputs, with a counter per column.
+///
+/// Every input given to [`read`](CsvCounter::read) is counted as part of the same stream, and
+/// starts with its own header if the format has one, so that files whose columns are in different
+/// orders can be counted together. Without explicit keys every column of the first input is
+/// counted, named by the header or by its position.
+///
+/// Fields missing from short records count as empty values. The values of composite keys are
+/// counted as the sequence of their fields, so that `a,bc` and `ab,c` are different keys.
+#[derive(Debug)]
+pub struct CsvCounter<C, F> {
+    format: CsvFormat,
+    keys: Vec<Key>,
+    new_counter: F,
+    counters: ColumnCounters<C>,
+    key: Vec<u8>,
+}
+
+impl<C, F> CsvCounter<C, F>
+where
+    C: for<'a> DistinctCounter<&'a [u8]>,
+    F: FnMut() -> C,
+{
+    /// Creates a counter of the columns or composite keys `keys` of inputs in `format`, or of
+    /// every column if `keys` is empty. `new_counter` creates the counter of every key.
+    pub fn new<K>(format: CsvFormat, keys: K, new_counter: F) -> Self
+    where
+        K: IntoIterator<Item = Key>,
+    {
+        Self {
+            format,
+            keys: keys.into_iter().collect(),
+            new_counter,
+            counters: ColumnCounters {
+                rows: 0,
+                columns: Vec::new(),
+            },
+            key: Vec::new(),
+        }
+    }
+
+    /// Counts the records of `reader`.
+    pub fn read<R: BufRead>(&mut self, reader: R) -> Result<(), CsvError> {
+        let mut reader = CsvReader::new(reader, self.format.clone());
+        let mut indices = None;
+        if self.format.has_header {
+            let Some(header) = reader.read_record()? else {
+                return Ok(());
+            };
+            let header: Vec<String> = header
+                .iter()
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .collect();
+            indices = Some(self.resolve(Some(&header), header.len())?);
+        }
+
+        while let Some(record) = reader.read_record()? {
+            let indices = match &indices {
+                Some(indices) => indices,
+                None => indices.insert(self.resolve(None, record.len())?),
+            };
+            self.counters.rows += 1;
+            for (columns, (_, counter)) in indices.iter().zip(&mut self.counters.columns) {
+                if let [column] = columns[..] {
+                    counter.insert(record.get(column).unwrap_or_default());
+                    continue;
+                }
+                self.key.clear();
+                for &column in columns {
+                    let field = record.get(column).unwrap_or_default();
+                    self.key
+                        .extend_from_slice(&(field.len() as u64).to_le_bytes());
+                    self.key.extend_from_slice(field);
+                }
+                counter.insert(&self.key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the counter of every key.
+    pub fn finish(self) -> ColumnCounters<C> {
+        self.counters
+    }
+
+    /// Returns the indices of the columns of every key in the records of an input, creating the
+    /// counters on the first input.
+    fn resolve(
+        &mut self,
+        header: Option<&[String]>,
+        fields: usize,
+    ) -> Result<Vec<Vec<usize>>, CsvError> {
+        if self.counters.columns.is_empty() {
+            if self.keys.is_empty() {
+                self.keys = match header {
+                    Some(header) => header.iter().map(|name| Key(vec![name.clone()])).collect(),
+                    None => (1..=fields).map(|i| Key(vec![i.to_string()])).collect(),
+                };
+            }
+            let new_counter = &mut self.new_counter;
+            self.counters.columns = self
+                .keys
+                .iter()
+                .map(|key| (key.to_string(), new_counter()))
+                .collect();
+        }
+
+        self.keys.iter().map(|key| key.resolve(header)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExactCounter;
+
+    fn records(input: &str, format: CsvFormat) -> Result<Vec<Vec<String>>, CsvError> {
+        let mut reader = CsvReader::new(input.as_bytes(), format);
+        let mut records = Vec::new();
+        while let Some(record) = reader.read_record()? {
+            records.push(
+                record
+                    .iter()
+                    .map(|field| String::from_utf8(field.to_vec()).unwrap())
+                    .collect(),
+            );
+        }
+        Ok(records)
+    }
+
+    type Exact = ExactCounter<Vec<u8>>;
+
+    fn counts(counter: CsvCounter<Exact, fn() -> Exact>) -> Vec<(String, usize)> {
+        let counters = counter.finish();
+        counters
+            .columns
+            .iter()
+            .map(|(name, counter)| (name.clone(), counter.estimate()))
+            .collect()
+    }
+
+    #[test]
+    fn parses_quotes_and_line_endings() {
+        let input = "a,\"b,c\",\"say \"\"hi\"\"\"\r\n\n\"multi\r\nline\",,x\"y\"\nlast";
+        assert_eq!(
+            records(input, CsvFormat::csv()).unwrap(),
+            [
+                vec!["a", "b,c", "say \"hi\""],
+                vec!["multi\nline", "", "x\"y\""],
+                vec!["last"],
+            ]
+        );
+        assert_eq!(
+            records("a\t\"b\"\tc\n", CsvFormat::tsv()).unwrap(),
+            [["a", "\"b\"", "c"]]
+        );
+        assert!(matches!(
+            records("a\nb,\"c\nd", CsvFormat::csv()),
+            Err(CsvError::UnterminatedQuote(2))
+        ));
+    }
+
+    #[test]
+    fn counts_columns_and_composite_keys() {
+        let keys = [
+            "b".parse().unwrap(),
+            "a+b".parse().unwrap(),
+            "3".parse().unwrap(),
+        ];
+        let mut counter = CsvCounter::new(CsvFormat::csv(), keys, Exact::new as fn() -> Exact);
+        counter
+            .read("a,b,c\nx,yz,1\nxy,z,1\nx,yz,2\n".as_bytes())
+            .unwrap();
+        // A second file, with its columns in another order and a short record.
+        counter.read("c,b,a\n3,w,x\n1\n".as_bytes()).unwrap();
+        assert_eq!(counter.counters.rows, 5);
+        assert_eq!(
+            counts(counter),
+            [("b".into(), 4), ("a+b".into(), 4), ("3".into(), 4)]
+        );
+    }
+
+    #[test]
+    fn counts_every_column_by_default() {
+        let mut counter = CsvCounter::new(CsvFormat::tsv(), [], Exact::new as fn() -> Exact);
+        counter.read("id\tname\n1\ta\n2\ta\n".as_bytes()).unwrap();
+        assert_eq!(counts(counter), [("id".into(), 2), ("name".into(), 1)]);
+
+        let format = CsvFormat {
+            has_header: false,
+            ..CsvFormat::csv()
+        };
+        let mut counter = CsvCounter::new(format, [], Exact::new as fn() -> Exact);
+        counter.read("1,a\n2,a\n3,b\n".as_bytes()).unwrap();
+        assert_eq!(counts(counter), [("1".into(), 3), ("2".into(), 2)]);
+    }
+
+    #[test]
+    fn rejects_unknown_columns() {
+        assert!(matches!("a++b".parse::<Key>(), Err(CsvError::UnknownColumn(c)) if c.is_empty()));
+
+        let keys = [Key::new(["id", "missing"])];
+        let mut counter = CsvCounter::new(CsvFormat::csv(), keys, Exact::new as fn() -> Exact);
+        assert!(matches!(
+            counter.read("id,name\n1,a\n".as_bytes()),
+            Err(CsvError::UnknownColumn(c)) if c == "missing"
+        ));
+        let mut counter = CsvCounter::new(
+            CsvFormat::csv(),
+            [Key::new(["0"])],
+            Exact::new as fn() -> Exact,
+        );
+        assert!(counter.read("id\n1\n".as_bytes()).is_err());
+    }
+}
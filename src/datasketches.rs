@@ -0,0 +1,662 @@
+//! The binary formats of [Apache DataSketches](https://datasketches.apache.org), so that sketches
+//! serialized by its libraries can be read, merged and written back.
+//!
+//! The formats are implemented from the DataSketches specification and tested against sketches
+//! serialized by the DataSketches Rust library, version 0.5.0. They have not been tested against
+//! sketches written by the Java, C++ or Python libraries.
+//!
+//! Two families of formats are supported:
+//!
+//! - HyperLogLog sketches, in all of their modes (`LIST`, `SET` and the dense `HLL_4`, `HLL_6` and
+//!   `HLL_8` arrays), are read into and written from a [`HyperLogLog`] with
+//!   [`HyperLogLog::from_datasketches`] and [`HyperLogLog::to_datasketches`].
+//! - Compact theta sketches, in the uncompressed serial version 3, are read into and written from a
+//!   [`CompactThetaSketch`] with [`CompactThetaSketch::from_datasketches`] and
+//!   [`CompactThetaSketch::to_datasketches`]. The compressed serial version 4 is not supported.
+//!
+//! Merging only makes sense between sketches whose elements were hashed the same way. DataSketches
+//! hashes elements with [`murmur3_x64_128`] under [`DEFAULT_SEED`], so elements are fed to this
+//! crate's sketches through [`hll_hash`] and [`theta_hash`] rather than `insert`. Both take the
+//! bytes DataSketches hashes: the UTF-8 bytes of a string, or the 8 little endian bytes of an
+//! integer widened to `i64`.
+//!
+//! The DataSketches formats do not record the number of elements processed, which reads back as
+//! `0`.
+//!
+//! # Examples
+//! ```rust
+//! use distinction::datasketches::{self, HllType};
+//! use distinction::HyperLogLog;
+//!
+//! // Bytes received from another service, here an empty HLL_4 sketch with lg_k = 12.
+//! let remote = [2, 1, 7, 12, 3, 12, 0, 0];
+//! let mut hll = HyperLogLog::from_datasketches(&remote).unwrap();
+//!
+//! for user_id in 0..1000i64 {
+//!     hll.insert_hash(datasketches::hll_hash(&user_id.to_le_bytes(), hll.precision()));
+//! }
+//! assert!(hll.estimate().abs_diff(1000) < 50);
+//!
+//! // Bytes to send back.
+//! let bytes = hll.to_datasketches(HllType::Hll4);
+//! let read = HyperLogLog::from_datasketches(&bytes).unwrap();
+//! assert_eq!(read.estimate(), hll.estimate());
+//! ```
+//!
+//! [`HyperLogLog`]: crate::HyperLogLog
+//! [`HyperLogLog::from_datasketches`]: crate::HyperLogLog::from_datasketches
+//! [`HyperLogLog::to_datasketches`]: crate::HyperLogLog::to_datasketches
+//! [`CompactThetaSketch`]: crate::CompactThetaSketch
+//! [`CompactThetaSketch::from_datasketches`]: crate::CompactThetaSketch::from_datasketches
+//! [`CompactThetaSketch::to_datasketches`]: crate::CompactThetaSketch::to_datasketches
+use crate::{
+    codec::{DecodeError, Reader, Writer},
+    hash::murmur3_x64_128,
+    hyperloglog::{MAX_PRECISION, MIN_PRECISION},
+    theta::MAX_THETA,
+};
+
+/// The seed DataSketches hashes elements with, unless configured otherwise.
+pub const DEFAULT_SEED: u64 = 9001;
+
+/// The 16-bit digest of [`DEFAULT_SEED`] stored in theta sketches, the low bits of its hash
+/// under seed `0`.
+const SEED_HASH: u16 = 0x93cc;
+
+const HLL_FAMILY: u8 = 7;
+const HLL_SERIAL_VERSION: u8 = 1;
+const HLL_LIST_PREAMBLE_INTS: u8 = 2;
+const HLL_SET_PREAMBLE_INTS: u8 = 3;
+const HLL_ARRAY_PREAMBLE_INTS: u8 = 10;
+const HLL_EMPTY: u8 = 1 << 2;
+const HLL_COMPACT: u8 = 1 << 3;
+const HLL_OUT_OF_ORDER: u8 = 1 << 4;
+const MODE_LIST: u8 = 0;
+const MODE_SET: u8 = 1;
+const MODE_ARRAY: u8 = 2;
+/// Coupons pack a register value in their top 6 bits over a 26-bit register index.
+const COUPON_INDEX_BITS: u32 = 26;
+/// Largest register value DataSketches produces.
+const MAX_REGISTER: u8 = 63;
+/// The 4-bit register value marking a register whose value is in the auxiliary map.
+const AUX_TOKEN: u8 = 15;
+
+const COMPACT_THETA_FAMILY: u8 = 3;
+const THETA_SERIAL_VERSION: u8 = 3;
+const THETA_READ_ONLY: u8 = 1 << 1;
+const THETA_EMPTY: u8 = 1 << 2;
+const THETA_COMPACT: u8 = 1 << 3;
+const THETA_ORDERED: u8 = 1 << 4;
+
+/// The layouts of the dense register array of a DataSketches HyperLogLog sketch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HllType {
+    /// 4 bits per register, as offsets from the smallest register, with the registers too large
+    /// for 4 bits in an auxiliary map. The smallest of the three.
+    Hll4 = 0,
+    /// 6 bits per register.
+    Hll6 = 1,
+    /// 8 bits per register. The fastest to update for DataSketches.
+    Hll8 = 2,
+}
+
+/// Hashes an element the way DataSketches HyperLogLog sketches do, for
+/// [`HyperLogLog::insert_hash`](crate::HyperLogLog::insert_hash) on a sketch of the given
+/// `precision`.
+///
+/// DataSketches picks the register from the low bits of the first half of the 128-bit hash, and
+/// the value from the leading zeros of the second half. The result lays out those bits the way
+/// [`HyperLogLog`](crate::HyperLogLog) expects them, which depends on the precision.
+///
+/// # Panics
+/// Panics if `precision` is outside of [`MIN_PRECISION`]`..=`[`MAX_PRECISION`].
+pub fn hll_hash(item: &[u8], precision: u8) -> u64 {
+    assert!(
+        (MIN_PRECISION..=MAX_PRECISION).contains(&precision),
+        "invalid HyperLogLog precision"
+    );
+    let (low, high) = murmur3_x64_128(item, DEFAULT_SEED);
+    let index = low & ((1 << precision) - 1);
+    (index << (64 - precision)) | (high >> precision)
+}
+
+/// Hashes an element the way DataSketches theta sketches do, for
+/// [`ThetaSketch::insert_hash`](crate::ThetaSketch::insert_hash).
+pub fn theta_hash(item: &[u8]) -> u64 {
+    murmur3_x64_128(item, DEFAULT_SEED).0
+}
+
+/// Writes `registers` as a DataSketches HyperLogLog sketch in the `HLL` mode of the given type, or
+/// as an empty `LIST` mode sketch if every register is empty, as DataSketches does.
+pub(crate) fn write_hll(precision: u8, registers: &[u8], hll_type: HllType) -> Vec<u8> {
+    let mut writer = Writer::new();
+    let mode = |mode: u8| mode | (hll_type as u8) << 2;
+
+    if registers.iter().all(|&register| register == 0) {
+        writer.u8(HLL_LIST_PREAMBLE_INTS);
+        writer.u8(HLL_SERIAL_VERSION);
+        writer.u8(HLL_FAMILY);
+        writer.u8(precision);
+        writer.u8(3);
+        writer.u8(HLL_EMPTY | HLL_COMPACT);
+        writer.u8(0);
+        writer.u8(mode(MODE_LIST));
+        return writer.into_bytes();
+    }
+
+    // Registers are offsets from `cur_min` in HLL_4 only.
+    let cur_min = match hll_type {
+        HllType::Hll4 => registers.iter().copied().min().unwrap_or(0),
+        HllType::Hll6 | HllType::Hll8 => 0,
+    };
+    let exceptions: Vec<(usize, u8)> = registers
+        .iter()
+        .enumerate()
+        .filter(|&(_, &register)| hll_type == HllType::Hll4 && register - cur_min >= AUX_TOKEN)
+        .map(|(index, &register)| (index, register))
+        .collect();
+    // The sums of `2^-register` which DataSketches keeps for its estimator, split to keep the
+    // precision of the smallest terms.
+    let (kxq0, kxq1) = registers
+        .iter()
+        .fold((0.0, 0.0), |(kxq0, kxq1), &register| {
+            let term = 0.5f64.powi(register.into());
+            if register < 32 {
+                (kxq0 + term, kxq1)
+            } else {
+                (kxq0, kxq1 + term)
+            }
+        });
+
+    writer.u8(HLL_ARRAY_PREAMBLE_INTS);
+    writer.u8(HLL_SERIAL_VERSION);
+    writer.u8(HLL_FAMILY);
+    writer.u8(precision);
+    writer.u8(0);
+    // The running HIP estimate is not tracked here, so readers are told to estimate from the
+    // registers instead, as they do for merged sketches.
+    writer.u8(HLL_COMPACT | HLL_OUT_OF_ORDER);
+    writer.u8(cur_min);
+    writer.u8(mode(MODE_ARRAY));
+    writer.f64(0.0);
+    writer.f64(kxq0);
+    writer.f64(kxq1);
+    writer.u32(
+        registers
+            .iter()
+            .filter(|&&register| register == cur_min)
+            .count() as u32,
+    );
+    writer.u32(exceptions.len() as u32);
+
+    match hll_type {
+        HllType::Hll4 => {
+            for pair in registers.chunks_exact(2) {
+                let [low, high] =
+                    [pair[0], pair[1]].map(|register| (register - cur_min).min(AUX_TOKEN));
+                writer.u8(low | high << 4);
+            }
+            for (index, register) in exceptions {
+                writer.u32(coupon(index, register));
+            }
+        }
+        HllType::Hll6 => {
+            let mut packed = vec![0; hll6_len(registers.len())];
+            for (index, &register) in registers.iter().enumerate() {
+                let (byte, shift) = (index * 6 / 8, index * 6 % 8);
+                let window = u16::from(register) << shift;
+                packed[byte] |= window as u8;
+                packed[byte + 1] |= (window >> 8) as u8;
+            }
+            writer.bytes(&packed);
+        }
+        HllType::Hll8 => writer.bytes(registers),
+    }
+
+    writer.into_bytes()
+}
+
+/// Reads a DataSketches HyperLogLog sketch in any mode, as its precision and dense registers.
+///
+/// Register values are capped to the largest value a [`HyperLogLog`](crate::HyperLogLog) of the
+/// precision can hold. DataSketches only exceeds it when a hash has more than `64 - precision`
+/// leading zeros.
+pub(crate) fn read_hll(bytes: &[u8]) -> Result<(u8, Vec<u8>), DecodeError> {
+    let mut reader = Reader::new(bytes);
+    let preamble_ints = reader.u8()?;
+    let serial_version = reader.u8()?;
+    let family = reader.u8()?;
+    let precision = reader.u8()?;
+    let lg_array = reader.u8()?;
+    let flags = reader.u8()?;
+    let state = reader.u8()?;
+    let mode = reader.u8()?;
+
+    if family != HLL_FAMILY {
+        return Err(DecodeError::Invalid(
+            "not a DataSketches HyperLogLog sketch",
+        ));
+    }
+    if serial_version != HLL_SERIAL_VERSION {
+        return Err(DecodeError::UnsupportedVersion(serial_version));
+    }
+    if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+        return Err(DecodeError::Invalid("precision out of range"));
+    }
+    let hll_type = match mode >> 2 & 3 {
+        0 => HllType::Hll4,
+        1 => HllType::Hll6,
+        2 => HllType::Hll8,
+        _ => return Err(DecodeError::Invalid("unknown HyperLogLog type")),
+    };
+    let compact = flags & HLL_COMPACT != 0;
+    let m = 1 << precision;
+    let mut registers = vec![0; m];
+
+    match (mode & 3, preamble_ints) {
+        (MODE_LIST, HLL_LIST_PREAMBLE_INTS) => {
+            if flags & HLL_EMPTY == 0 {
+                let len = if compact {
+                    state.into()
+                } else {
+                    table_len(lg_array)?
+                };
+                read_coupons(&mut reader, len, &mut registers)?;
+            }
+        }
+        (MODE_SET, HLL_SET_PREAMBLE_INTS) => {
+            let count = reader.u32()? as usize;
+            let len = if compact { count } else { table_len(lg_array)? };
+            read_coupons(&mut reader, len, &mut registers)?;
+        }
+        (MODE_ARRAY, HLL_ARRAY_PREAMBLE_INTS) => {
+            // Skip the estimator state and the count of registers at `cur_min`, which the
+            // registers determine.
+            reader.bytes(3 * 8 + 4)?;
+            let exceptions = reader.u32()? as usize;
+
+            match hll_type {
+                HllType::Hll4 => {
+                    for (pair, &byte) in registers.chunks_exact_mut(2).zip(reader.bytes(m / 2)?) {
+                        pair[0] = byte & 0xf;
+                        pair[1] = byte >> 4;
+                    }
+                    let len = match (compact, exceptions) {
+                        (true, _) => exceptions,
+                        (false, 0) => 0,
+                        (false, _) => table_len(lg_array)?,
+                    };
+                    let tokens: Vec<usize> =
+                        (0..m).filter(|&i| registers[i] == AUX_TOKEN).collect();
+                    for register in &mut registers {
+                        *register = if *register == AUX_TOKEN {
+                            0
+                        } else {
+                            register.saturating_add(state)
+                        };
+                    }
+                    read_coupons(&mut reader, len, &mut registers)?;
+                    if tokens.len() != exceptions || tokens.iter().any(|&i| registers[i] == 0) {
+                        return Err(DecodeError::Invalid(
+                            "auxiliary map does not match registers",
+                        ));
+                    }
+                }
+                HllType::Hll6 => {
+                    let packed = reader.bytes(hll6_len(m))?;
+                    for (index, register) in registers.iter_mut().enumerate() {
+                        let (byte, shift) = (index * 6 / 8, index * 6 % 8);
+                        let window = u16::from_le_bytes([packed[byte], packed[byte + 1]]);
+                        *register = (window >> shift) as u8 & 0x3f;
+                    }
+                }
+                HllType::Hll8 => registers.copy_from_slice(reader.bytes(m)?),
+            }
+        }
+        _ => return Err(DecodeError::Invalid("unknown HyperLogLog mode")),
+    }
+
+    if registers.iter().any(|&register| register > MAX_REGISTER) {
+        return Err(DecodeError::Invalid("register holds an impossible rank"));
+    }
+    for register in &mut registers {
+        *register = (*register).min(64 - precision + 1);
+    }
+    Ok((precision, registers))
+}
+
+/// Reads `len` coupons, skipping the empty slots of hash tables, into `registers`.
+fn read_coupons(reader: &mut Reader, len: usize, registers: &mut [u8]) -> Result<(), DecodeError> {
+    // Do not trust the length for the loop before knowing the bytes are there.
+    if reader.remaining() / 4 < len {
+        return Err(DecodeError::Truncated);
+    }
+
+    for _ in 0..len {
+        let coupon = reader.u32()?;
+        if coupon == 0 {
+            continue;
+        }
+        let index = (coupon & ((1 << COUPON_INDEX_BITS) - 1)) as usize % registers.len();
+        let value = (coupon >> COUPON_INDEX_BITS) as u8;
+        if value == 0 {
+            return Err(DecodeError::Invalid("coupon holds an empty register"));
+        }
+        registers[index] = registers[index].max(value);
+    }
+    Ok(())
+}
+
+/// The number of slots of a hash table of coupons, from its base 2 logarithm.
+fn table_len(lg_len: u8) -> Result<usize, DecodeError> {
+    if lg_len > COUPON_INDEX_BITS as u8 {
+        return Err(DecodeError::Invalid("coupon table too large"));
+    }
+    Ok(1 << lg_len)
+}
+
+/// Packs a register index and value into a coupon.
+fn coupon(index: usize, register: u8) -> u32 {
+    u32::from(register) << COUPON_INDEX_BITS | index as u32
+}
+
+/// The number of bytes of `m` 6-bit registers, padded so that any register can be read as a 16-bit
+/// window.
+fn hll6_len(m: usize) -> usize {
+    m * 3 / 4 + 1
+}
+
+/// Writes a theta sketch with threshold `theta` and sorted `hashes` as an ordered DataSketches
+/// compact theta sketch.
+pub(crate) fn write_compact_theta(theta: u64, hashes: &[u64]) -> Vec<u8> {
+    let estimating = theta < MAX_THETA;
+    let preamble_longs = match hashes.len() {
+        _ if estimating => 3,
+        0 | 1 => 1,
+        _ => 2,
+    };
+    let mut flags = THETA_READ_ONLY | THETA_COMPACT | THETA_ORDERED;
+    if !estimating && hashes.is_empty() {
+        flags |= THETA_EMPTY;
+    }
+
+    let mut writer = Writer::new();
+    writer.u8(preamble_longs);
+    writer.u8(THETA_SERIAL_VERSION);
+    writer.u8(COMPACT_THETA_FAMILY);
+    writer.u16(0);
+    writer.u8(flags);
+    writer.u16(SEED_HASH);
+    if preamble_longs > 1 {
+        writer.u32(hashes.len() as u32);
+        writer.u32(0);
+    }
+    if estimating {
+        writer.u64(theta);
+    }
+    for &hash in hashes {
+        writer.u64(hash);
+    }
+    writer.into_bytes()
+}
+
+/// Reads a DataSketches compact theta sketch as its threshold and sorted hashes.
+pub(crate) fn read_compact_theta(bytes: &[u8]) -> Result<(u64, Vec<u64>), DecodeError> {
+    let mut reader = Reader::new(bytes);
+    let preamble_longs = reader.u8()?;
+    let serial_version = reader.u8()?;
+    let family = reader.u8()?;
+    reader.u16()?;
+    let flags = reader.u8()?;
+    let seed_hash = reader.u16()?;
+
+    if family != COMPACT_THETA_FAMILY {
+        return Err(DecodeError::Invalid(
+            "not a DataSketches compact theta sketch",
+        ));
+    }
+    if serial_version != THETA_SERIAL_VERSION {
+        return Err(DecodeError::UnsupportedVersion(serial_version));
+    }
+    if flags & THETA_EMPTY != 0 {
+        return Ok((MAX_THETA, Vec::new()));
+    }
+    if seed_hash != SEED_HASH {
+        return Err(DecodeError::Invalid(
+            "sketch was hashed with a different seed",
+        ));
+    }
+
+    let (len, theta) = match preamble_longs {
+        1 => (1, MAX_THETA),
+        2 | 3 => {
+            let len = reader.u32()? as usize;
+            reader.u32()?;
+            let theta = if preamble_longs == 3 {
+                reader.u64()?
+            } else {
+                MAX_THETA
+            };
+            (len, theta)
+        }
+        _ => return Err(DecodeError::Invalid("unexpected preamble length")),
+    };
+    if theta == 0 || theta > MAX_THETA {
+        return Err(DecodeError::Invalid("theta out of range"));
+    }
+    if reader.remaining() / 8 < len {
+        return Err(DecodeError::Truncated);
+    }
+
+    let mut hashes = (0..len)
+        .map(|_| reader.u64())
+        .collect::<Result<Vec<_>, _>>()?;
+    if flags & THETA_ORDERED == 0 {
+        hashes.sort_unstable();
+    }
+    if hashes.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err(DecodeError::Invalid("hashes are not distinct"));
+    }
+    if hashes.first() == Some(&0) || hashes.last().is_some_and(|&hash| hash >= theta) {
+        return Err(DecodeError::Invalid("hash outside of (0, theta)"));
+    }
+    Ok((theta, hashes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CompactThetaSketch, HyperLogLog, ThetaSketch};
+
+    /// Sketches of the integers `0..n` written by Apache DataSketches, see the README next to them.
+    macro_rules! fixture {
+        ($name:literal) => {
+            (
+                $name,
+                include_bytes!(concat!("../tests/fixtures/datasketches/", $name, ".sk")) as &[u8],
+            )
+        };
+    }
+
+    const FIXTURES: [(&str, &[u8]); 16] = [
+        fixture!("hll4_lgk10_n0"),
+        fixture!("hll4_lgk10_n5"),
+        fixture!("hll4_lgk10_n50"),
+        fixture!("hll4_lgk10_n30000"),
+        fixture!("hll6_lgk10_n0"),
+        fixture!("hll6_lgk10_n5"),
+        fixture!("hll6_lgk10_n50"),
+        fixture!("hll6_lgk10_n30000"),
+        fixture!("hll8_lgk10_n0"),
+        fixture!("hll8_lgk10_n5"),
+        fixture!("hll8_lgk10_n50"),
+        fixture!("hll8_lgk10_n30000"),
+        fixture!("theta_compact_lgk10_n0"),
+        fixture!("theta_compact_lgk10_n1"),
+        fixture!("theta_compact_lgk10_n100"),
+        fixture!("theta_compact_lgk10_n3000"),
+    ];
+
+    fn fixture(name: &str) -> (u64, &'static [u8]) {
+        let &(file, bytes) = FIXTURES
+            .iter()
+            .find(|(file, _)| *file == name)
+            .expect("missing fixture");
+        let n = file.rsplit_once("_n").unwrap().1.parse().unwrap();
+        (n, bytes)
+    }
+
+    fn hll_type(name: &str) -> HllType {
+        match &name[..4] {
+            "hll4" => HllType::Hll4,
+            "hll6" => HllType::Hll6,
+            _ => HllType::Hll8,
+        }
+    }
+
+    /// The sketch DataSketches builds from the integers `0..n`.
+    fn hll(n: u64) -> HyperLogLog {
+        let mut hll = HyperLogLog::new(10);
+        for i in 0..n as i64 {
+            hll.insert_hash(hll_hash(&i.to_le_bytes(), 10));
+        }
+        hll
+    }
+
+    fn theta(n: u64) -> CompactThetaSketch {
+        let mut sketch = ThetaSketch::new(10);
+        for i in 0..n as i64 {
+            sketch.insert_hash(theta_hash(&i.to_le_bytes()));
+        }
+        sketch.compact()
+    }
+
+    #[test]
+    fn seed_hash_of_default_seed() {
+        let (low, _) = murmur3_x64_128(&DEFAULT_SEED.to_le_bytes(), 0);
+        assert_eq!(low as u16, SEED_HASH);
+    }
+
+    /// Checks that the HLL fixture `name` holds the registers of the integers `0..n`.
+    fn check_hll(name: &str, n: u64, bytes: &[u8]) {
+        let read = HyperLogLog::from_datasketches(bytes).unwrap();
+        let expected = hll(n);
+
+        assert_eq!(read.precision(), 10, "{}", name);
+        assert_eq!(
+            read.to_datasketches(HllType::Hll8),
+            expected.to_datasketches(HllType::Hll8),
+            "{}",
+            name
+        );
+        let estimate = read.estimate() as f64;
+        assert!((estimate - n as f64).abs() <= 0.1 * n as f64, "{}", name);
+    }
+
+    /// Checks that the theta fixture `name` round-trips and holds the hashes of the integers
+    /// `0..n`.
+    fn check_theta(name: &str, n: u64, bytes: &[u8]) {
+        let read = CompactThetaSketch::from_datasketches(bytes).unwrap();
+        assert_eq!(read.to_datasketches(), bytes, "{}", name);
+
+        // DataSketches rebuilds its sketches at a different size, so thresholds may differ, but
+        // both keep every hash below them.
+        let built = theta(n);
+        let limit = read.theta().min(built.theta()) * MAX_THETA as f64 * (1.0 - 1e-9);
+        let below = |sketch: &CompactThetaSketch| -> Vec<u64> {
+            let limit = limit as u64;
+            sketch
+                .hashes()
+                .iter()
+                .copied()
+                .filter(|&hash| hash < limit)
+                .collect()
+        };
+        assert_eq!(below(&read), below(&built), "{}", name);
+        if n <= 100 {
+            assert_eq!(built.to_datasketches(), bytes, "{}", name);
+        }
+    }
+
+    #[test]
+    fn hll_fixtures_hold_the_same_registers() {
+        for &(name, bytes) in FIXTURES.iter().filter(|(name, _)| name.starts_with("hll")) {
+            let (n, _) = fixture(name);
+            check_hll(name, n, bytes);
+        }
+    }
+
+    #[test]
+    fn hll_writes_match_fixtures() {
+        for &(name, bytes) in FIXTURES.iter().filter(|(name, _)| name.starts_with("hll")) {
+            let (n, _) = fixture(name);
+            let written = hll(n).to_datasketches(hll_type(name));
+            if n == 0 {
+                assert_eq!(written, bytes, "{}", name);
+                continue;
+            }
+            if bytes[7] & 3 != MODE_ARRAY {
+                // Small sketches are written in HLL mode, where DataSketches keeps coupons.
+                let read = HyperLogLog::from_datasketches(&written).unwrap();
+                assert_eq!(read, HyperLogLog::from_datasketches(bytes).unwrap());
+                continue;
+            }
+
+            // Everything but the flags and the estimator state must be identical.
+            assert_eq!(written.len(), bytes.len(), "{}", name);
+            assert_eq!(written[..5], bytes[..5], "{}", name);
+            assert_eq!(written[6..8], bytes[6..8], "{}", name);
+            assert_eq!(written[32..], bytes[32..], "{}", name);
+            for offset in [16, 24] {
+                let kxq = |bytes: &[u8]| {
+                    f64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+                };
+                assert!((kxq(&written) - kxq(bytes)).abs() < 1e-9, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn hll_round_trip_all_types() {
+        let hll = hll(30_000);
+        for hll_type in [HllType::Hll4, HllType::Hll6, HllType::Hll8] {
+            let bytes = hll.to_datasketches(hll_type);
+            let read = HyperLogLog::from_datasketches(&bytes).unwrap();
+            assert_eq!(read.to_datasketches(hll_type), bytes);
+            for len in 0..bytes.len() {
+                assert!(HyperLogLog::from_datasketches(&bytes[..len]).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn theta_fixtures_round_trip_byte_for_byte() {
+        for &(name, bytes) in FIXTURES
+            .iter()
+            .filter(|(name, _)| name.starts_with("theta"))
+        {
+            let (n, _) = fixture(name);
+            check_theta(name, n, bytes);
+        }
+    }
+
+    #[test]
+    fn foreign_bytes_are_rejected() {
+        let (_, theta) = fixture("theta_compact_lgk10_n100");
+        let (_, hll) = fixture("hll8_lgk10_n30000");
+        assert!(HyperLogLog::from_datasketches(theta).is_err());
+        assert!(CompactThetaSketch::from_datasketches(hll).is_err());
+
+        let mut other_seed = theta.to_vec();
+        other_seed[6] ^= 1;
+        assert_eq!(
+            CompactThetaSketch::from_datasketches(&other_seed),
+            Err(DecodeError::Invalid(
+                "sketch was hashed with a different seed"
+            ))
+        );
+        for len in 0..theta.len() {
+            assert!(CompactThetaSketch::from_datasketches(&theta[..len]).is_err());
+        }
+    }
+}
@@ -0,0 +1,62 @@
+//! Errors reported by the fallible estimator APIs.
+use std::fmt;
+
+/// The reasons an estimator can fail to produce an estimate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DistinctError {
+    /// The sample set was still full after throwing away half of its elements, so the algorithm
+    /// gave up. This happens with probability at most `delta`.
+    ThresholdExceeded,
+    /// `eps` was NaN or outside of `(0, 1)`.
+    InvalidEpsilon(f64),
+    /// `delta` was NaN or outside of `(0, 1)`.
+    InvalidDelta(f64),
+    /// The sample set threshold computed from `eps`, `delta` and the stream length does not fit in
+    /// a `usize`.
+    ThresholdOverflow,
+    /// A sketch precision was outside of the supported range.
+    InvalidPrecision(u8),
+    /// Two sketches created with different precisions were combined.
+    PrecisionMismatch(u8, u8),
+    /// A sketch was asked to keep fewer sampled hashes than it needs to produce an estimate.
+    InvalidSampleSize(usize),
+    /// Two sketches created with different sample sizes were combined.
+    SampleSizeMismatch(usize, usize),
+    /// No level of an [`L0Sketch`](crate::L0Sketch) could be decoded, so the items with a
+    /// non-zero net count could not be recovered. This happens with small probability.
+    RecoveryFailed,
+}
+
+impl fmt::Display for DistinctError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ThresholdExceeded => {
+                write!(
+                    f,
+                    "sample set still full after halving, no estimate available"
+                )
+            }
+            Self::InvalidEpsilon(eps) => write!(f, "eps must be in (0, 1), got {}", eps),
+            Self::InvalidDelta(delta) => write!(f, "delta must be in (0, 1), got {}", delta),
+            Self::ThresholdOverflow => write!(f, "sample set threshold overflows usize"),
+            Self::InvalidPrecision(precision) => {
+                write!(f, "unsupported sketch precision {}", precision)
+            }
+            Self::PrecisionMismatch(left, right) => write!(
+                f,
+                "cannot combine sketches of precision {} and {}",
+                left, right
+            ),
+            Self::InvalidSampleSize(k) => write!(f, "sketch sample size {} is too small", k),
+            Self::SampleSizeMismatch(left, right) => write!(
+                f,
+                "cannot combine sketches of sample size {} and {}",
+                left, right
+            ),
+            Self::RecoveryFailed => write!(f, "no level of the sketch could be decoded"),
+        }
+    }
+}
+
+impl std::error::Error for DistinctError {}
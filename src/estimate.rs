@@ -0,0 +1,331 @@
+//! The result type returned by the fallible estimator APIs.
+use std::fmt;
+
+/// An estimate of the number of distinct elements in a stream, along with what is needed to audit
+/// and reproduce it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Estimate {
+    /// The point estimate.
+    pub value: f64,
+    /// Lower end of the interval the true count lies in with probability `confidence`.
+    pub lower: f64,
+    /// Upper end of the interval the true count lies in with probability `confidence`.
+    pub upper: f64,
+    /// Probability that the true count lies in `[lower, upper]`.
+    pub confidence: f64,
+    /// Number of stream elements the estimate is based on.
+    pub processed: usize,
+    /// Algorithm specific state at the time of the estimate.
+    pub diagnostics: Diagnostics,
+}
+
+/// Algorithm specific state reported alongside an [`Estimate`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Diagnostics {
+    /// State of the F0-Estimator of Chakraborty, Vinodchandran, and Meel.
+    Cvm {
+        /// Final sampling probability.
+        p: f64,
+        /// Final size of the sample set `chi`.
+        sample_len: usize,
+        /// Size at which the sample set gets halved.
+        thresh: usize,
+        /// Number of times the sample set was halved, i.e. `log2(1 / p)`.
+        rounds: u32,
+        /// Seed of the [`Gen`](crate::Gen) which drove the sampling. Feeding the same stream to an
+        /// estimator built from `Gen::new(Some(seed))` reproduces the estimate. `None` when the
+        /// estimator was driven by a caller-provided generator.
+        seed: Option<u64>,
+    },
+    /// State of a [`HyperLogLog`](crate::HyperLogLog) sketch.
+    HyperLogLog {
+        /// Base 2 logarithm of the number of registers.
+        precision: u8,
+        /// Number of registers which have not been set yet.
+        zero_registers: usize,
+        /// Whether the estimate was computed with linear counting rather than the raw
+        /// HyperLogLog estimate.
+        linear_counting: bool,
+    },
+    /// State of a [`HyperLogLogPlusPlus`](crate::HyperLogLogPlusPlus) sketch.
+    HyperLogLogPlusPlus {
+        /// Base 2 logarithm of the number of registers of the dense representation.
+        precision: u8,
+        /// Whether the sketch was still using its sparse encoding.
+        sparse: bool,
+        /// Whether the estimate was computed with linear counting.
+        linear_counting: bool,
+        /// Whether the empirical bias correction was applied to the raw estimate.
+        bias_corrected: bool,
+    },
+    /// State of a [`KmvSketch`](crate::KmvSketch), or of a set operation between two of them.
+    Kmv {
+        /// Number of smallest hashes the sketch keeps.
+        k: usize,
+        /// Number of sampled hashes the estimate was computed from.
+        sample_len: usize,
+        /// The `k`-th smallest hash scaled to `(0, 1]`, i.e. the fraction of the hash space the
+        /// sample covers. `1.0` while fewer than `k` hashes have been seen and the count is exact.
+        theta: f64,
+    },
+    /// State of a [`ThetaSketch`](crate::ThetaSketch), or of the result of a set expression over
+    /// theta sketches.
+    Theta {
+        /// Number of hashes retained below `theta`.
+        retained: usize,
+        /// Fraction of the hash space below the sketch's threshold, i.e. the sampling rate.
+        /// `1.0` while the sketch is exact.
+        theta: f64,
+    },
+    /// State of an [`ExactCounter`](crate::ExactCounter), which has nothing to report.
+    Exact,
+    /// State of F0-Estimators run in parallel over disjoint partitions of the stream, whose
+    /// estimates are summed: a
+    /// [`ConcurrentCvmSketch`](crate::ConcurrentCvmSketch), or `par_find_n_distinct` behind the
+    /// `rayon` feature.
+    PartitionedCvm {
+        /// Number of partitions, each counted by an estimator of its own.
+        partitions: usize,
+        /// Total size of the sample sets of the partitions.
+        sample_len: usize,
+        /// Seed of the [`Gen`](crate::Gen) the generators of the partitions were drawn from.
+        /// `None` when the estimator was driven by a caller-provided generator.
+        seed: Option<u64>,
+    },
+    /// State of an [`L0Sketch`](crate::L0Sketch).
+    L0 {
+        /// Number of items each level of the sketch is sized to recover.
+        k: usize,
+        /// Sampling level the estimate was read at. Items reach level `level` with probability
+        /// `2^-level`, and level 0 holds all of them.
+        level: u32,
+        /// Number of items with a non-zero net count recovered at that level.
+        recovered: usize,
+    },
+}
+
+impl Estimate {
+    /// Builds the estimate of the F0-Estimator from its final state. The paper guarantees the
+    /// estimate is within a factor of `(1 ± eps)` of the true count with probability `1 - delta`,
+    /// i.e. `(1 - eps) F0 <= value <= (1 + eps) F0`, so the true count lies within
+    /// `value / (1 + eps)` and `value / (1 - eps)`.
+    pub(crate) fn cvm(
+        eps: f64,
+        delta: f64,
+        p: f64,
+        sample_len: usize,
+        thresh: usize,
+        processed: usize,
+        seed: Option<u64>,
+    ) -> Self {
+        let value = sample_len as f64 / p;
+
+        Self {
+            value,
+            lower: value / (1.0 + eps),
+            upper: value / (1.0 - eps),
+            confidence: 1.0 - delta,
+            processed,
+            diagnostics: Diagnostics::Cvm {
+                p,
+                sample_len,
+                thresh,
+                rounds: (1.0 / p).log2() as u32,
+                seed,
+            },
+        }
+    }
+
+    /// Builds the estimate of a HyperLogLog sketch. Its relative standard error is
+    /// `1.04 / sqrt(2^precision)`.
+    pub(crate) fn hyperloglog(
+        value: f64,
+        precision: u8,
+        zero_registers: usize,
+        linear_counting: bool,
+        processed: usize,
+    ) -> Self {
+        Self::normal(
+            value,
+            1.04 / ((1u64 << precision) as f64).sqrt(),
+            processed,
+            Diagnostics::HyperLogLog {
+                precision,
+                zero_registers,
+                linear_counting,
+            },
+        )
+    }
+
+    /// Builds the estimate of a HyperLogLog++ sketch. While sparse its standard error is that of
+    /// the `2^25` buckets of the sparse encoding, afterwards that of a dense HyperLogLog.
+    pub(crate) fn hyperloglog_plus_plus(
+        value: f64,
+        precision: u8,
+        sparse: bool,
+        linear_counting: bool,
+        bias_corrected: bool,
+        processed: usize,
+    ) -> Self {
+        let buckets = if sparse { 25 } else { precision };
+
+        Self::normal(
+            value,
+            1.04 / ((1u64 << buckets) as f64).sqrt(),
+            processed,
+            Diagnostics::HyperLogLogPlusPlus {
+                precision,
+                sparse,
+                linear_counting,
+                bias_corrected,
+            },
+        )
+    }
+
+    /// Builds the estimate of a KMV sketch. `std_error` is the relative standard error, which is
+    /// about `1 / sqrt(sample_len)`.
+    pub(crate) fn kmv(
+        value: f64,
+        std_error: f64,
+        k: usize,
+        sample_len: usize,
+        theta: f64,
+        processed: usize,
+    ) -> Self {
+        Self::normal(
+            value,
+            std_error,
+            processed,
+            Diagnostics::Kmv {
+                k,
+                sample_len,
+                theta,
+            },
+        )
+    }
+
+    /// Builds the estimate of a theta sketch from its retained hashes and the bounds computed for
+    /// them at 95% confidence.
+    pub(crate) fn theta(
+        retained: usize,
+        theta: f64,
+        (lower, upper): (f64, f64),
+        processed: usize,
+    ) -> Self {
+        Self {
+            value: retained as f64 / theta,
+            lower,
+            upper,
+            confidence: 0.95,
+            processed,
+            diagnostics: Diagnostics::Theta { retained, theta },
+        }
+    }
+
+    /// Builds an estimate with the given relative standard error, using the usual normal
+    /// approximation for the interval at 95% confidence.
+    fn normal(value: f64, std_error: f64, processed: usize, diagnostics: Diagnostics) -> Self {
+        Self {
+            value,
+            lower: ((1.0 - 1.96 * std_error) * value).max(0.0),
+            upper: (1.0 + 1.96 * std_error) * value,
+            confidence: 0.95,
+            processed,
+            diagnostics,
+        }
+    }
+
+    /// Sums the estimates of F0-Estimators run over disjoint partitions of a stream, each with
+    /// failure probability `delta / partitions`. By the union bound every partition's count is
+    /// within its interval, from `value / (1 + eps)` to `value / (1 - eps)`, with probability
+    /// `1 - delta`, and then so is the total within the sum of the intervals.
+    pub(crate) fn partitioned_cvm(partitions: &[Estimate], delta: f64, seed: Option<u64>) -> Self {
+        let sample_len = partitions
+            .iter()
+            .map(|estimate| match estimate.diagnostics {
+                Diagnostics::Cvm { sample_len, .. } => sample_len,
+                _ => 0,
+            })
+            .sum();
+
+        Self {
+            value: partitions.iter().map(|estimate| estimate.value).sum(),
+            lower: partitions.iter().map(|estimate| estimate.lower).sum(),
+            upper: partitions.iter().map(|estimate| estimate.upper).sum(),
+            confidence: 1.0 - delta,
+            processed: partitions.iter().map(|estimate| estimate.processed).sum(),
+            diagnostics: Diagnostics::PartitionedCvm {
+                partitions: partitions.len(),
+                sample_len,
+                seed,
+            },
+        }
+    }
+
+    /// Builds the estimate of an L0 sketch from the items recovered at the level it was read at.
+    /// Above level 0 they are a sample of the items taken with probability `p = 2^-level`, so the
+    /// relative standard error is about `sqrt((1 - p) / recovered)`. Level 0 recovers every item,
+    /// so its count is exact.
+    pub(crate) fn l0(recovered: usize, level: u32, k: usize, processed: usize) -> Self {
+        let p = 0.5f64.powi(level as i32);
+        let value = recovered as f64 / p;
+        let std_error = ((1.0 - p) / recovered.max(1) as f64).sqrt();
+        let estimate = Self::normal(
+            value,
+            std_error,
+            processed,
+            Diagnostics::L0 {
+                k,
+                level,
+                recovered,
+            },
+        );
+
+        if level == 0 {
+            Self {
+                confidence: 1.0,
+                ..estimate
+            }
+        } else {
+            estimate
+        }
+    }
+
+    /// Builds the estimate of an exact counter, whose interval is the count itself.
+    pub(crate) fn exact(count: usize, processed: usize) -> Self {
+        let value = count as f64;
+
+        Self {
+            value,
+            lower: value,
+            upper: value,
+            confidence: 1.0,
+            processed,
+            diagnostics: Diagnostics::Exact,
+        }
+    }
+
+    /// Returns the point estimate as a whole number of elements, the way [`find_n_distinct`]
+    /// reports it.
+    ///
+    /// [`find_n_distinct`]: crate::find_n_distinct
+    pub fn count(&self) -> usize {
+        self.value as usize
+    }
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} [{:.0}, {:.0}] at {}% confidence",
+            self.value,
+            self.lower,
+            self.upper,
+            self.confidence * 100.0
+        )
+    }
+}
@@ -0,0 +1,155 @@
+//! An exact distinct counter, to verify the approximate ones against.
+use std::{collections::HashSet, hash::Hash, mem};
+
+use crate::{DistinctCounter, DistinctError, Estimate, Mergeable};
+
+/// Counts distinct elements exactly by keeping every one of them in a [`HashSet`].
+///
+/// Memory grows linearly with the number of distinct elements, so this is meant for verifying the
+/// approximate counters and for streams known to be small.
+///
+/// # Examples
+/// ```rust
+/// use distinction::ExactCounter;
+/// let counter: ExactCounter<i32> = [1, 10, 20, 10, 10, 30, 20, 10, 20, 20, 1, 1, 1]
+///     .into_iter()
+///     .collect();
+/// assert_eq!(counter.estimate(), 4);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(deserialize = "T: serde::Deserialize<'de> + Hash + Eq"))
+)]
+pub struct ExactCounter<T> {
+    seen: HashSet<T>,
+    processed: usize,
+}
+
+impl<T> ExactCounter<T>
+where
+    T: Hash + Eq,
+{
+    /// Creates an empty counter.
+    pub fn new() -> Self {
+        Self {
+            seen: HashSet::new(),
+            processed: 0,
+        }
+    }
+
+    /// Feeds a single element of the stream into the counter.
+    pub fn insert(&mut self, item: T) {
+        self.processed += 1;
+        self.seen.insert(item);
+    }
+
+    /// Returns the number of distinct elements seen so far.
+    pub fn estimate(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Returns the number of distinct elements seen so far as an [`Estimate`] whose interval is
+    /// the count itself.
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        Ok(Estimate::exact(self.seen.len(), self.processed))
+    }
+}
+
+impl<T> Default for ExactCounter<T>
+where
+    T: Hash + Eq,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Mergeable for ExactCounter<T>
+where
+    T: Hash + Eq + Clone,
+{
+    fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        self.seen.extend(other.seen.iter().cloned());
+        self.processed += other.processed;
+        Ok(())
+    }
+}
+
+impl<T> Extend<T> for ExactCounter<T>
+where
+    T: Hash + Eq,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for ExactCounter<T>
+where
+    T: Hash + Eq,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Self::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<T> DistinctCounter<T> for ExactCounter<T>
+where
+    T: Hash + Eq,
+{
+    fn insert(&mut self, item: T) {
+        self.insert(item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        // Each bucket of the table holds an element and a control byte.
+        mem::size_of::<Self>() + self.seen.capacity() * (mem::size_of::<T>() + 1)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn clear(&mut self) {
+        self.seen.clear();
+        self.processed = 0;
+    }
+}
+
+/// Counts borrowed byte strings, such as lines or the fields of a
+/// [`CsvCounter`](crate::csv::CsvCounter), copying only the distinct ones.
+impl DistinctCounter<&[u8]> for ExactCounter<Vec<u8>> {
+    fn insert(&mut self, item: &[u8]) {
+        self.processed += 1;
+        if !self.seen.contains(item) {
+            self.seen.insert(item.to_vec());
+        }
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        <Self as DistinctCounter<Vec<u8>>>::memory_bytes(self)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    fn clear(&mut self) {
+        self.seen.clear();
+        self.processed = 0;
+    }
+}
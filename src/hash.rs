@@ -0,0 +1,107 @@
+//! Fixed-width hashing shared by the hash-based sketches.
+use std::hash::{BuildHasher, Hash, Hasher};
+
+use siphasher::sip::SipHasher13;
+
+/// Keys for [`hash64`]. They are fixed so that a hash computed in one process can be compared with
+/// one computed in another, e.g. after a sketch has been persisted or sent to another worker.
+const KEY0: u64 = 0x6469_7374_696e_6374;
+const KEY1: u64 = 0x696f_6e5f_6861_7368;
+
+/// Hashes `item` to 64 bits with SipHash-1-3 under fixed keys.
+///
+/// The result is stable across processes and releases of this crate, but it is only as portable as
+/// the [`Hash`] implementation of `Q`: for example `usize` hashes differently on 32 and 64-bit
+/// targets.
+pub fn hash64<Q>(item: &Q) -> u64
+where
+    Q: Hash + ?Sized,
+{
+    let mut hasher = SipHasher13::new_with_keys(KEY0, KEY1);
+    item.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Builds the hasher behind [`hash64`], for hash tables whose iteration order must be the same from
+/// one run, platform or release of Rust to the next.
+///
+/// The keys are public, so anyone can precompute elements which collide in a table hashed this way
+/// and make every lookup linear in its size. Prefer a randomly keyed hasher such as
+/// [`RandomState`](std::collections::hash_map::RandomState) for tables fed untrusted input.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FixedState;
+
+impl BuildHasher for FixedState {
+    type Hasher = SipHasher13;
+
+    fn build_hasher(&self) -> SipHasher13 {
+        SipHasher13::new_with_keys(KEY0, KEY1)
+    }
+}
+
+/// Maps `hash` to one of `partitions` partitions by its top bits, evenly whatever their number.
+pub(crate) fn partition(hash: u64, partitions: usize) -> usize {
+    ((u128::from(hash) * partitions as u128) >> 64) as usize
+}
+
+/// Hashes `bytes` to 128 bits with the x64 variant of MurmurHash3 under `seed`, returning the two
+/// 64-bit halves of the hash.
+///
+/// This is the hash Apache DataSketches builds its sketches on. It is exposed so that sketches can
+/// be fed the same hashes as the ones built by other DataSketches libraries, see
+/// [`datasketches`](crate::datasketches).
+pub fn murmur3_x64_128(bytes: &[u8], seed: u64) -> (u64, u64) {
+    const C1: u64 = 0x87c3_7b91_1142_53d5;
+    const C2: u64 = 0x4cf5_ad43_2745_937f;
+
+    let mix_k1 = |k1: u64| k1.wrapping_mul(C1).rotate_left(31).wrapping_mul(C2);
+    let mix_k2 = |k2: u64| k2.wrapping_mul(C2).rotate_left(33).wrapping_mul(C1);
+    let read = |bytes: &[u8]| {
+        let mut word = [0; 8];
+        word[..bytes.len()].copy_from_slice(bytes);
+        u64::from_le_bytes(word)
+    };
+
+    let (mut h1, mut h2) = (seed, seed);
+    let mut blocks = bytes.chunks_exact(16);
+    for block in &mut blocks {
+        h1 ^= mix_k1(read(&block[..8]));
+        h1 = h1
+            .rotate_left(27)
+            .wrapping_add(h2)
+            .wrapping_mul(5)
+            .wrapping_add(0x52dc_e729);
+        h2 ^= mix_k2(read(&block[8..]));
+        h2 = h2
+            .rotate_left(31)
+            .wrapping_add(h1)
+            .wrapping_mul(5)
+            .wrapping_add(0x3849_5ab5);
+    }
+
+    let tail = blocks.remainder();
+    if tail.len() > 8 {
+        h2 ^= mix_k2(read(&tail[8..]));
+    }
+    if !tail.is_empty() {
+        h1 ^= mix_k1(read(&tail[..tail.len().min(8)]));
+    }
+
+    h1 ^= bytes.len() as u64;
+    h2 ^= bytes.len() as u64;
+    h1 = h1.wrapping_add(h2);
+    h2 = h2.wrapping_add(h1);
+    h1 = fmix64(h1);
+    h2 = fmix64(h2);
+    h1 = h1.wrapping_add(h2);
+    (h1, h2.wrapping_add(h1))
+}
+
+/// The finalization mix of MurmurHash3, which makes every input bit affect every output bit.
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
@@ -0,0 +1,584 @@
+//! Empirical bias correction tables for `HyperLogLogPlusPlus`.
+//!
+//! Generated by `cargo run --release --example hll_bias_tables`, do not edit by hand.
+//! Row `i` of each table is for precision `4` + i.
+
+/// Mean raw estimate at each sampled cardinality.
+#[rustfmt::skip]
+pub(super) const RAW_ESTIMATES: [[f64; 101]; 15] = [
+    [
+        10.8, 10.8, 11.2, 11.7, 12.2, 12.8,
+        12.8, 13.3, 13.8, 14.4, 15.0, 15.0,
+        15.6, 16.2, 16.8, 17.4, 17.4, 18.1,
+        18.8, 19.4, 20.1, 20.1, 20.8, 21.6,
+        22.4, 23.2, 23.2, 24.0, 24.8, 25.6,
+        26.4, 26.4, 27.2, 28.1, 28.9, 29.7,
+        29.7, 30.5, 31.3, 32.1, 33.0, 33.0,
+        33.9, 34.9, 35.6, 36.4, 36.4, 37.4,
+        38.2, 39.3, 40.3, 40.3, 41.2, 42.0,
+        43.0, 44.1, 44.1, 45.0, 46.2, 47.1,
+        48.2, 48.2, 49.3, 50.2, 51.1, 52.1,
+        52.1, 52.7, 53.6, 54.9, 55.7, 55.7,
+        56.7, 57.6, 58.7, 59.6, 59.6, 60.7,
+        61.8, 62.9, 64.1, 64.1, 65.1, 65.9,
+        66.8, 68.1, 68.1, 69.4, 70.4, 71.1,
+        72.2, 72.2, 73.0, 74.0, 74.9, 75.9,
+        75.9, 76.8, 77.7, 78.9, 80.2,
+    ],
+    [
+        22.3, 22.8, 23.7, 24.2, 25.3, 26.3,
+        26.8, 27.9, 28.5, 29.5, 30.7, 31.3,
+        32.5, 33.1, 34.4, 35.6, 36.3, 37.6,
+        38.3, 39.7, 41.1, 41.8, 43.2, 43.9,
+        45.4, 46.9, 47.7, 49.4, 50.2, 51.6,
+        53.3, 54.2, 55.8, 56.6, 58.3, 60.0,
+        60.9, 62.5, 63.3, 65.2, 67.0, 68.1,
+        69.8, 70.7, 72.4, 74.4, 75.3, 77.0,
+        78.0, 79.8, 81.6, 82.5, 84.5, 85.4,
+        87.5, 89.5, 90.3, 92.3, 93.1, 95.0,
+        96.8, 97.7, 99.4, 100.3, 102.3, 104.3,
+        105.3, 107.1, 108.0, 110.1, 111.7, 112.7,
+        114.9, 116.0, 117.8, 119.6, 120.6, 122.6,
+        123.5, 125.3, 127.4, 128.6, 130.5, 131.4,
+        133.4, 135.4, 136.3, 138.5, 139.3, 141.1,
+        143.0, 143.9, 146.0, 147.2, 149.5, 151.6,
+        152.7, 154.6, 155.6, 157.5, 159.6,
+    ],
+    [
+        45.4, 46.8, 48.3, 49.8, 51.4, 53.5,
+        55.1, 56.7, 58.4, 60.1, 62.4, 64.3,
+        66.1, 68.0, 69.9, 72.4, 74.3, 76.3,
+        78.4, 80.4, 83.1, 85.3, 87.6, 89.9,
+        92.1, 94.9, 97.2, 99.6, 101.9, 104.3,
+        107.3, 109.6, 111.9, 114.3, 116.7, 119.9,
+        122.3, 124.8, 127.3, 130.1, 133.6, 136.2,
+        138.7, 141.4, 144.1, 147.8, 150.5, 153.1,
+        155.7, 158.7, 162.2, 164.9, 167.5, 170.5,
+        173.3, 177.3, 180.1, 182.8, 185.5, 188.3,
+        192.3, 195.0, 197.8, 200.5, 203.0, 207.0,
+        209.9, 212.8, 216.0, 218.7, 222.5, 225.4,
+        228.3, 231.1, 233.8, 237.9, 240.9, 244.2,
+        247.0, 249.9, 254.1, 257.2, 259.8, 263.2,
+        266.5, 270.6, 273.6, 276.9, 279.9, 283.3,
+        288.1, 290.7, 293.9, 296.6, 299.6, 303.7,
+        307.1, 310.0, 312.7, 316.1, 320.1,
+    ],
+    [
+        91.6, 94.4, 97.4, 100.9, 104.0, 107.8,
+        111.0, 114.3, 118.2, 121.7, 125.8, 129.4,
+        133.0, 137.4, 141.2, 145.7, 149.6, 153.5,
+        158.1, 162.3, 167.3, 171.5, 175.8, 181.1,
+        185.8, 191.1, 195.6, 200.2, 205.8, 210.7,
+        216.3, 221.2, 225.9, 231.8, 236.8, 242.6,
+        247.6, 252.5, 258.7, 263.8, 269.7, 275.1,
+        280.4, 286.4, 291.5, 297.8, 303.7, 309.2,
+        315.9, 321.2, 327.6, 333.1, 338.4, 345.1,
+        350.6, 357.5, 363.2, 369.0, 375.3, 381.5,
+        388.5, 394.0, 400.0, 406.6, 412.2, 419.0,
+        425.0, 430.9, 437.8, 443.4, 450.1, 456.1,
+        462.3, 469.4, 474.9, 481.9, 487.5, 493.3,
+        500.3, 505.9, 512.6, 518.8, 524.8, 530.8,
+        536.8, 543.7, 549.5, 555.6, 562.5, 568.5,
+        575.7, 582.4, 588.2, 595.2, 601.1, 608.4,
+        614.7, 620.1, 627.6, 633.7, 640.8,
+    ],
+    [
+        183.9, 189.7, 196.2, 202.8, 209.5, 216.4,
+        222.8, 230.1, 237.4, 244.9, 252.5, 259.7,
+        267.6, 275.7, 283.7, 292.2, 300.2, 309.0,
+        317.8, 326.7, 336.0, 344.5, 353.8, 363.4,
+        372.8, 382.3, 391.3, 401.1, 411.2, 421.5,
+        431.8, 441.4, 451.9, 462.6, 473.4, 484.5,
+        494.7, 505.7, 516.6, 528.0, 539.4, 549.7,
+        561.0, 572.6, 584.0, 595.5, 606.5, 617.9,
+        629.5, 641.7, 653.6, 664.5, 675.9, 688.4,
+        700.4, 712.6, 723.6, 736.0, 748.5, 760.9,
+        773.0, 784.1, 796.0, 808.7, 821.6, 834.0,
+        845.5, 858.3, 871.2, 883.6, 895.9, 907.5,
+        921.1, 933.9, 946.9, 959.9, 971.2, 984.4,
+        997.1, 1009.5, 1022.0, 1033.8, 1046.8, 1059.9,
+        1072.7, 1085.6, 1097.6, 1111.4, 1123.6, 1137.5,
+        1150.0, 1162.1, 1174.9, 1187.3, 1200.3, 1213.0,
+        1225.1, 1238.7, 1251.9, 1265.0, 1278.1,
+    ],
+    [
+        368.5, 380.7, 393.7, 406.6, 420.1, 433.8,
+        447.3, 461.7, 475.9, 490.9, 506.3, 521.3,
+        537.4, 553.0, 569.5, 586.3, 602.7, 620.0,
+        637.0, 655.1, 673.5, 691.4, 710.1, 728.7,
+        747.7, 767.2, 786.4, 806.4, 825.7, 846.3,
+        867.0, 887.4, 908.0, 928.8, 950.0, 971.8,
+        993.0, 1014.9, 1036.2, 1058.5, 1080.8, 1102.7,
+        1125.8, 1148.4, 1171.8, 1194.7, 1216.8, 1240.8,
+        1263.6, 1287.2, 1310.7, 1334.0, 1358.6, 1381.9,
+        1406.5, 1430.8, 1453.9, 1478.0, 1501.8, 1526.3,
+        1551.2, 1574.8, 1599.4, 1623.5, 1649.4, 1674.4,
+        1699.9, 1724.7, 1749.5, 1774.8, 1800.6, 1825.3,
+        1850.2, 1875.8, 1901.5, 1926.6, 1951.6, 1977.1,
+        2001.6, 2027.8, 2053.2, 2077.4, 2103.8, 2128.0,
+        2154.2, 2180.1, 2205.2, 2231.3, 2257.5, 2282.9,
+        2309.0, 2334.5, 2359.2, 2384.2, 2409.7, 2435.8,
+        2460.1, 2486.2, 2511.4, 2536.9, 2562.2,
+    ],
+    [
+        737.8, 762.5, 787.9, 813.7, 840.2, 868.0,
+        895.7, 924.0, 953.0, 982.5, 1013.2, 1044.0,
+        1075.4, 1107.3, 1139.5, 1172.8, 1206.5, 1240.4,
+        1275.1, 1310.4, 1346.9, 1383.2, 1420.2, 1457.9,
+        1495.5, 1534.3, 1573.1, 1612.1, 1651.9, 1691.4,
+        1733.3, 1774.1, 1815.4, 1857.2, 1899.3, 1943.3,
+        1985.5, 2029.5, 2073.8, 2117.7, 2163.4, 2209.1,
+        2254.8, 2300.0, 2344.3, 2391.0, 2437.7, 2485.3,
+        2532.0, 2577.9, 2626.1, 2673.0, 2719.4, 2767.9,
+        2814.7, 2863.1, 2911.3, 2958.8, 3007.0, 3054.8,
+        3103.6, 3151.4, 3199.8, 3249.4, 3298.6, 3350.7,
+        3400.5, 3450.1, 3500.6, 3549.3, 3601.3, 3650.7,
+        3700.5, 3750.4, 3801.2, 3851.7, 3902.4, 3953.9,
+        4004.4, 4053.9, 4106.2, 4156.5, 4207.5, 4258.2,
+        4309.4, 4361.5, 4412.0, 4461.0, 4510.7, 4560.9,
+        4611.3, 4661.7, 4711.4, 4761.8, 4813.1, 4866.6,
+        4919.6, 4970.4, 5022.2, 5075.6, 5126.5,
+    ],
+    [
+        1476.4, 1526.0, 1576.9, 1629.5, 1682.5, 1737.3,
+        1792.8, 1849.6, 1908.1, 1967.2, 2027.9, 2089.4,
+        2151.8, 2216.2, 2280.6, 2347.2, 2414.8, 2482.8,
+        2552.1, 2622.2, 2694.2, 2766.5, 2839.6, 2914.4,
+        2990.5, 3067.9, 3145.5, 3223.1, 3302.7, 3382.4,
+        3464.0, 3546.0, 3628.0, 3712.1, 3797.0, 3883.1,
+        3969.7, 4056.5, 4144.0, 4231.7, 4320.6, 4408.6,
+        4497.8, 4589.0, 4680.4, 4772.9, 4866.1, 4960.0,
+        5052.4, 5145.7, 5240.5, 5335.5, 5430.0, 5526.7,
+        5623.1, 5720.4, 5817.9, 5914.1, 6012.3, 6110.8,
+        6208.8, 6307.6, 6405.2, 6505.2, 6605.7, 6707.0,
+        6804.6, 6904.7, 7005.0, 7103.8, 7203.0, 7301.4,
+        7400.5, 7502.6, 7603.6, 7703.7, 7804.4, 7903.5,
+        8006.2, 8108.6, 8209.5, 8310.0, 8408.9, 8510.1,
+        8609.6, 8712.9, 8814.0, 8916.0, 9016.9, 9119.7,
+        9221.7, 9322.5, 9426.1, 9529.0, 9632.0, 9734.4,
+        9836.4, 9938.0, 10039.9, 10140.7, 10242.7,
+    ],
+    [
+        2953.7, 3052.8, 3154.8, 3259.3, 3366.3, 3475.7,
+        3586.9, 3700.7, 3816.7, 3935.4, 4056.1, 4178.7,
+        4304.5, 4432.5, 4563.3, 4695.9, 4831.0, 4967.5,
+        5107.0, 5248.7, 5391.5, 5536.4, 5683.5, 5832.8,
+        5984.7, 6138.0, 6291.8, 6449.5, 6608.5, 6769.3,
+        6932.4, 7095.4, 7261.5, 7428.2, 7596.4, 7767.3,
+        7939.2, 8113.4, 8287.7, 8464.1, 8641.4, 8821.3,
+        9001.8, 9184.2, 9366.7, 9552.4, 9738.3, 9925.5,
+        10112.5, 10300.1, 10489.2, 10678.2, 10868.6, 11057.7,
+        11250.4, 11442.4, 11637.0, 11832.8, 12028.7, 12228.0,
+        12423.5, 12620.3, 12816.3, 13014.5, 13212.1, 13409.3,
+        13605.4, 13806.8, 14005.2, 14202.8, 14402.2, 14599.4,
+        14797.2, 14997.6, 15199.7, 15405.6, 15605.1, 15806.1,
+        16003.9, 16205.2, 16409.9, 16611.1, 16816.0, 17022.7,
+        17229.8, 17432.6, 17633.5, 17839.5, 18045.2, 18249.8,
+        18453.7, 18658.2, 18862.9, 19064.3, 19268.9, 19472.4,
+        19678.0, 19882.9, 20085.0, 20292.4, 20498.9,
+    ],
+    [
+        5908.1, 6106.7, 6311.7, 6521.4, 6733.8, 6952.8,
+        7176.0, 7404.0, 7638.2, 7876.5, 8117.0, 8364.9,
+        8615.0, 8870.9, 9131.6, 9396.7, 9664.8, 9936.5,
+        10212.6, 10496.6, 10783.4, 11072.3, 11367.1, 11664.4,
+        11967.9, 12274.3, 12584.0, 12902.1, 13222.6, 13545.3,
+        13873.1, 14197.8, 14529.4, 14865.2, 15204.1, 15550.9,
+        15895.9, 16245.4, 16593.9, 16951.5, 17309.5, 17674.4,
+        18034.5, 18402.6, 18768.9, 19138.3, 19517.6, 19886.7,
+        20256.6, 20631.3, 21013.6, 21396.2, 21774.0, 22161.1,
+        22542.9, 22932.2, 23320.1, 23708.4, 24096.1, 24485.4,
+        24875.0, 25259.0, 25648.8, 26043.1, 26429.5, 26828.2,
+        27232.1, 27634.5, 28038.1, 28437.7, 28845.4, 29242.4,
+        29642.8, 30042.6, 30445.2, 30844.2, 31245.4, 31644.0,
+        32050.8, 32458.2, 32857.0, 33254.3, 33653.8, 34067.6,
+        34478.8, 34885.5, 35287.3, 35685.6, 36091.1, 36495.2,
+        36910.5, 37309.7, 37712.9, 38119.7, 38537.1, 38949.7,
+        39353.4, 39759.6, 40171.5, 40571.6, 40978.9,
+    ],
+    [
+        11817.0, 12215.7, 12622.5, 13040.1, 13466.2, 13901.9,
+        14347.2, 14804.1, 15267.7, 15742.3, 16226.3, 16719.0,
+        17222.2, 17730.5, 18247.2, 18775.8, 19315.3, 19861.1,
+        20417.4, 20984.3, 21559.9, 22136.9, 22725.5, 23318.9,
+        23917.4, 24534.4, 25156.4, 25783.6, 26423.1, 27063.3,
+        27715.2, 28375.0, 29041.4, 29714.1, 30392.5, 31077.9,
+        31757.5, 32455.2, 33159.8, 33867.9, 34582.7, 35295.4,
+        36018.3, 36746.4, 37477.4, 38217.6, 38958.9, 39704.2,
+        40441.0, 41196.5, 41953.9, 42713.1, 43465.7, 44229.9,
+        45006.3, 45777.8, 46554.9, 47321.2, 48097.2, 48875.6,
+        49663.2, 50443.0, 51232.8, 52016.8, 52807.5, 53608.8,
+        54397.6, 55195.2, 55995.2, 56794.3, 57587.8, 58379.2,
+        59191.8, 60009.9, 60813.5, 61629.5, 62430.6, 63238.4,
+        64051.1, 64844.1, 65656.2, 66475.4, 67285.0, 68098.4,
+        68910.9, 69728.1, 70541.5, 71348.2, 72163.4, 72975.2,
+        73791.2, 74603.9, 75407.5, 76220.9, 77026.2, 77858.0,
+        78654.3, 79475.3, 80290.8, 81096.6, 81909.2,
+    ],
+    [
+        23634.8, 24430.7, 25247.0, 26079.6, 26934.5, 27809.4,
+        28700.6, 29612.7, 30544.3, 31492.2, 32459.9, 33445.8,
+        34447.2, 35474.2, 36510.9, 37570.4, 38647.2, 39743.7,
+        40857.6, 41987.6, 43136.1, 44297.8, 45479.0, 46672.8,
+        47887.0, 49113.7, 50356.9, 51604.8, 52884.7, 54180.0,
+        55482.1, 56801.7, 58123.5, 59460.5, 60818.4, 62179.8,
+        63557.1, 64943.2, 66342.3, 67750.0, 69176.2, 70597.5,
+        72041.6, 73493.4, 74953.5, 76430.0, 77914.1, 79399.1,
+        80898.0, 82393.3, 83896.1, 85421.7, 86936.5, 88467.0,
+        90005.0, 91546.0, 93089.2, 94632.5, 96192.1, 97751.4,
+        99317.1, 100894.2, 102476.8, 104066.1, 105643.6, 107236.0,
+        108838.4, 110439.8, 112017.3, 113615.3, 115211.4, 116824.0,
+        118417.3, 120031.5, 121636.9, 123237.6, 124867.0, 126486.6,
+        128104.0, 129716.9, 131333.6, 132935.5, 134552.2, 136185.0,
+        137817.5, 139459.3, 141085.9, 142716.4, 144346.3, 145979.9,
+        147614.0, 149242.3, 150883.3, 152531.4, 154167.7, 155803.7,
+        157430.6, 159078.7, 160723.2, 162363.8, 163985.1,
+    ],
+    [
+        47270.3, 48865.5, 50496.9, 52165.5, 53875.2, 55619.9,
+        57400.2, 59221.2, 61083.0, 62982.3, 64917.3, 66885.2,
+        68896.1, 70943.1, 73021.6, 75139.3, 77289.4, 79477.9,
+        81705.0, 83960.9, 86252.3, 88568.3, 90929.6, 93313.0,
+        95743.2, 98199.3, 100688.3, 103210.4, 105756.3, 108336.3,
+        110941.3, 113571.3, 116236.3, 118921.0, 121627.5, 124371.4,
+        127124.8, 129904.9, 132712.9, 135534.1, 138384.5, 141251.6,
+        144150.2, 147063.7, 149973.1, 152913.6, 155874.9, 158848.5,
+        161838.8, 164835.1, 167848.5, 170882.3, 173924.9, 176980.6,
+        180014.6, 183085.1, 186162.4, 189257.9, 192355.7, 195480.8,
+        198608.5, 201746.9, 204899.3, 208092.0, 211287.3, 214443.3,
+        217614.5, 220799.3, 223989.7, 227190.6, 230384.9, 233596.5,
+        236810.8, 240023.4, 243237.7, 246449.7, 249684.7, 252897.0,
+        256141.8, 259381.0, 262623.9, 265849.2, 269083.3, 272348.4,
+        275610.6, 278862.6, 282126.0, 285382.2, 288625.0, 291892.4,
+        295153.6, 298445.5, 301681.2, 304911.3, 308172.5, 311467.9,
+        314741.6, 318025.9, 321273.2, 324553.3, 327846.9,
+    ],
+    [
+        94541.5, 97729.8, 100994.9, 104336.3, 107752.8, 111245.6,
+        114813.1, 118460.5, 122178.5, 125972.6, 129844.1, 133787.8,
+        137802.3, 141894.1, 146060.5, 150297.2, 154600.9, 158976.0,
+        163428.9, 167951.1, 172538.6, 177184.0, 181899.0, 186662.0,
+        191501.1, 196407.8, 201372.7, 206410.0, 211504.6, 216658.1,
+        221837.2, 227096.6, 232405.8, 237763.8, 243186.0, 248645.1,
+        254158.6, 259695.6, 265295.7, 270940.6, 276626.2, 282348.3,
+        288114.6, 293931.2, 299789.0, 305652.5, 311569.3, 317532.0,
+        323506.0, 329527.9, 335549.1, 341610.8, 347727.4, 353824.1,
+        359962.1, 366133.6, 372311.2, 378502.5, 384739.6, 390979.4,
+        397236.0, 403490.8, 409774.4, 416075.7, 422398.7, 428742.6,
+        435096.6, 441451.0, 447831.3, 454221.7, 460630.0, 467034.5,
+        473478.4, 479911.9, 486303.6, 492709.1, 499131.1, 505609.0,
+        512047.7, 518519.3, 524971.1, 531491.3, 538002.4, 544486.3,
+        550998.3, 557500.8, 564024.6, 570515.4, 577035.0, 583555.3,
+        590081.1, 596620.1, 603113.8, 609673.3, 616194.3, 622726.5,
+        629257.4, 635770.7, 642298.6, 648849.2, 655378.5,
+    ],
+    [
+        189083.7, 195461.9, 201989.5, 208665.0, 215494.7, 222474.8,
+        229606.3, 236893.1, 244324.9, 251908.0, 259647.9, 267532.5,
+        275564.7, 283736.8, 292059.6, 300523.0, 309146.9, 317912.3,
+        326814.5, 335850.1, 345030.6, 354343.4, 363783.1, 373358.3,
+        383050.3, 392866.6, 402797.0, 412858.5, 423057.1, 433341.9,
+        443740.2, 454246.6, 464856.4, 475567.1, 486406.0, 497326.7,
+        508345.1, 519475.5, 530694.9, 541965.8, 553355.4, 564829.0,
+        576338.1, 587937.8, 599631.1, 611380.7, 623230.8, 635125.3,
+        647064.3, 659070.1, 671123.8, 683242.6, 695397.3, 707606.5,
+        719884.0, 732188.9, 744521.0, 756957.4, 769424.0, 781945.8,
+        794497.2, 807071.5, 819690.8, 832373.9, 845049.9, 857741.4,
+        870495.2, 883184.9, 895968.0, 908795.5, 921617.2, 934450.7,
+        947315.0, 960164.8, 973030.3, 985903.3, 998845.4, 1011757.3,
+        1024688.8, 1037645.2, 1050563.1, 1063540.2, 1076518.7, 1089513.3,
+        1102513.4, 1115467.8, 1128462.9, 1141466.4, 1154512.3, 1167508.9,
+        1180604.1, 1193667.1, 1206694.5, 1219752.4, 1232779.7, 1245847.3,
+        1258913.8, 1271956.3, 1285070.7, 1298170.9, 1311276.8,
+    ],
+];
+
+/// Mean raw estimate minus the true cardinality.
+#[rustfmt::skip]
+pub(super) const BIAS: [[f64; 101]; 15] = [
+    [
+        10.8, 10.8, 10.2, 9.7, 9.2, 8.8,
+        8.8, 8.3, 7.8, 7.4, 7.0, 7.0,
+        6.6, 6.2, 5.8, 5.4, 5.4, 5.1,
+        4.8, 4.4, 4.1, 4.1, 3.8, 3.6,
+        3.4, 3.2, 3.2, 3.0, 2.8, 2.6,
+        2.4, 2.4, 2.2, 2.1, 1.9, 1.7,
+        1.7, 1.5, 1.3, 1.1, 1.0, 1.0,
+        0.9, 0.9, 0.6, 0.4, 0.4, 0.4,
+        0.2, 0.3, 0.3, 0.3, 0.2, 0.0,
+        0.0, 0.1, 0.1, 0.0, 0.2, 0.1,
+        0.2, 0.2, 0.3, 0.2, 0.1, 0.1,
+        0.1, -0.3, -0.4, -0.1, -0.3, -0.3,
+        -0.3, -0.4, -0.3, -0.4, -0.4, -0.3,
+        -0.2, -0.1, 0.1, 0.1, 0.1, -0.1,
+        -0.2, 0.1, 0.1, 0.4, 0.4, 0.1,
+        0.2, 0.2, 0.0, 0.0, -0.1, -0.1,
+        -0.1, -0.2, -0.3, -0.1, 0.2,
+    ],
+    [
+        22.3, 21.8, 20.7, 20.2, 19.3, 18.3,
+        17.8, 16.9, 16.5, 15.5, 14.7, 14.3,
+        13.5, 13.1, 12.4, 11.6, 11.3, 10.6,
+        10.3, 9.7, 9.1, 8.8, 8.2, 7.9,
+        7.4, 6.9, 6.7, 6.4, 6.2, 5.6,
+        5.3, 5.2, 4.8, 4.6, 4.3, 4.0,
+        3.9, 3.5, 3.3, 3.2, 3.0, 3.1,
+        2.8, 2.7, 2.4, 2.4, 2.3, 2.0,
+        2.0, 1.8, 1.6, 1.5, 1.5, 1.4,
+        1.5, 1.5, 1.3, 1.3, 1.1, 1.0,
+        0.8, 0.7, 0.4, 0.3, 0.3, 0.3,
+        0.3, 0.1, 0.0, 0.1, -0.3, -0.3,
+        -0.1, 0.0, -0.2, -0.4, -0.4, -0.4,
+        -0.5, -0.7, -0.6, -0.4, -0.5, -0.6,
+        -0.6, -0.6, -0.7, -0.5, -0.7, -0.9,
+        -1.0, -1.1, -1.0, -0.8, -0.5, -0.4,
+        -0.3, -0.4, -0.4, -0.5, -0.4,
+    ],
+    [
+        45.4, 43.8, 42.3, 40.8, 39.4, 37.5,
+        36.1, 34.7, 33.4, 32.1, 30.4, 29.3,
+        28.1, 27.0, 25.9, 24.4, 23.3, 22.3,
+        21.4, 20.4, 19.1, 18.3, 17.6, 16.9,
+        16.1, 14.9, 14.2, 13.6, 12.9, 12.3,
+        11.3, 10.6, 9.9, 9.3, 8.7, 7.9,
+        7.3, 6.8, 6.3, 6.1, 5.6, 5.2,
+        4.7, 4.4, 4.1, 3.8, 3.5, 3.1,
+        2.7, 2.7, 2.2, 1.9, 1.5, 1.5,
+        1.3, 1.3, 1.1, 0.8, 0.5, 0.3,
+        0.3, 0.0, -0.2, -0.5, -1.0, -1.0,
+        -1.1, -1.2, -1.0, -1.3, -1.5, -1.6,
+        -1.7, -1.9, -2.2, -2.1, -2.1, -1.8,
+        -2.0, -2.1, -1.9, -1.8, -2.2, -1.8,
+        -1.5, -1.4, -1.4, -1.1, -1.1, -0.7,
+        0.1, -0.3, -0.1, -0.4, -0.4, -0.3,
+        0.1, 0.0, -0.3, 0.1, 0.1,
+    ],
+    [
+        91.6, 88.4, 85.4, 81.9, 79.0, 75.8,
+        73.0, 70.3, 67.2, 64.7, 61.8, 59.4,
+        57.0, 54.4, 52.2, 49.7, 47.6, 45.5,
+        43.1, 41.3, 39.3, 37.5, 35.8, 34.1,
+        32.8, 31.1, 29.6, 28.2, 26.8, 25.7,
+        24.3, 23.2, 21.9, 20.8, 19.8, 18.6,
+        17.6, 16.5, 15.7, 14.8, 13.7, 13.1,
+        12.4, 11.4, 10.5, 9.8, 9.7, 9.2,
+        8.9, 8.2, 7.6, 7.1, 6.4, 6.1,
+        5.6, 5.5, 5.2, 5.0, 4.3, 4.5,
+        4.5, 4.0, 4.0, 3.6, 3.2, 3.0,
+        3.0, 2.9, 2.8, 2.4, 2.1, 2.1,
+        2.3, 2.4, 1.9, 1.9, 1.5, 1.3,
+        1.3, 0.9, 0.6, 0.8, 0.8, -0.2,
+        -0.2, -0.3, -0.5, -0.4, -0.5, -0.5,
+        -0.3, 0.4, 0.2, 0.2, 0.1, 0.4,
+        0.7, 0.1, 0.6, 0.7, 0.8,
+    ],
+    [
+        183.9, 177.7, 171.2, 164.8, 158.5, 152.4,
+        146.8, 141.1, 135.4, 129.9, 124.5, 119.7,
+        114.6, 109.7, 104.7, 100.2, 96.2, 92.0,
+        87.8, 83.7, 80.0, 76.5, 72.8, 69.4,
+        65.8, 62.3, 59.3, 56.1, 53.2, 50.5,
+        47.8, 45.4, 42.9, 40.6, 38.4, 36.5,
+        34.7, 32.7, 30.6, 29.0, 27.4, 25.7,
+        24.0, 22.6, 21.0, 19.5, 18.5, 16.9,
+        15.5, 14.7, 13.6, 12.5, 10.9, 10.4,
+        9.4, 8.6, 7.6, 7.0, 6.5, 5.9,
+        5.0, 4.1, 3.0, 2.7, 2.6, 2.0,
+        1.5, 1.3, 1.2, 0.6, -0.1, -0.5,
+        0.1, -0.1, -0.1, -0.1, -0.8, -0.6,
+        -0.9, -1.5, -2.0, -2.2, -2.2, -2.1,
+        -2.3, -2.4, -2.4, -1.6, -2.4, -1.5,
+        -2.0, -1.9, -2.1, -2.7, -2.7, -3.0,
+        -2.9, -2.3, -2.1, -2.0, -1.9,
+    ],
+    [
+        368.5, 355.7, 342.7, 330.6, 318.1, 305.8,
+        294.3, 282.7, 271.9, 260.9, 250.3, 240.3,
+        230.4, 221.0, 211.5, 202.3, 193.7, 185.0,
+        177.0, 169.1, 161.5, 154.4, 147.1, 140.7,
+        133.7, 127.2, 121.4, 115.4, 109.7, 104.3,
+        99.0, 94.4, 89.0, 84.8, 80.0, 75.8,
+        72.0, 67.9, 64.2, 60.5, 56.8, 53.7,
+        50.8, 48.4, 45.8, 42.7, 39.8, 37.8,
+        35.6, 33.2, 30.7, 29.0, 27.6, 25.9,
+        24.5, 22.8, 20.9, 19.0, 17.8, 16.3,
+        15.2, 13.8, 12.4, 11.5, 11.4, 10.4,
+        10.9, 9.7, 9.5, 8.8, 8.6, 8.3,
+        7.2, 7.8, 7.5, 6.6, 6.6, 6.1,
+        5.6, 5.8, 5.2, 4.4, 4.8, 4.0,
+        4.2, 4.1, 4.2, 4.3, 5.5, 4.9,
+        5.0, 5.5, 4.2, 4.2, 3.7, 3.8,
+        3.1, 3.2, 3.4, 2.9, 2.2,
+    ],
+    [
+        737.8, 711.5, 685.9, 660.7, 636.2, 612.0,
+        588.7, 566.0, 544.0, 522.5, 501.2, 481.0,
+        461.4, 442.3, 423.5, 404.8, 387.5, 370.4,
+        354.1, 338.4, 322.9, 308.2, 294.2, 280.9,
+        267.5, 254.3, 242.1, 230.1, 218.9, 207.4,
+        197.3, 187.1, 177.4, 168.2, 159.3, 151.3,
+        142.5, 135.5, 128.8, 121.7, 115.4, 110.1,
+        104.8, 99.0, 92.3, 87.0, 82.7, 79.3,
+        75.0, 69.9, 66.1, 62.0, 57.4, 54.9,
+        50.7, 47.1, 44.3, 40.8, 38.0, 34.8,
+        31.6, 28.4, 25.8, 24.4, 22.6, 22.7,
+        21.5, 20.1, 19.6, 17.3, 17.3, 15.7,
+        14.5, 13.4, 13.2, 11.7, 11.4, 11.9,
+        11.4, 9.9, 10.2, 9.5, 9.5, 9.2,
+        9.4, 9.5, 9.0, 7.0, 5.7, 4.9,
+        3.3, 2.7, 1.4, 0.8, 1.1, 2.6,
+        4.6, 4.4, 5.2, 7.6, 6.5,
+    ],
+    [
+        1476.4, 1424.0, 1372.9, 1322.5, 1273.5, 1225.3,
+        1178.8, 1133.6, 1089.1, 1046.2, 1003.9, 963.4,
+        923.8, 885.2, 847.6, 811.2, 776.8, 742.8,
+        709.1, 677.2, 646.2, 616.5, 587.6, 559.4,
+        533.5, 507.9, 483.5, 459.1, 435.7, 413.4,
+        392.0, 372.0, 352.0, 333.1, 316.0, 299.1,
+        283.7, 268.5, 253.0, 238.7, 224.6, 210.6,
+        197.8, 186.0, 175.4, 164.9, 156.1, 148.0,
+        137.4, 128.7, 120.5, 113.5, 106.0, 99.7,
+        94.1, 88.4, 83.9, 78.1, 73.3, 69.8,
+        64.8, 61.6, 57.2, 54.2, 52.7, 51.0,
+        46.6, 44.7, 42.0, 38.8, 35.0, 31.4,
+        28.5, 27.6, 26.6, 23.7, 22.4, 19.5,
+        19.2, 19.6, 17.5, 16.0, 12.9, 11.1,
+        8.6, 8.9, 8.0, 8.0, 5.9, 6.7,
+        5.7, 4.5, 6.1, 6.0, 7.0, 6.4,
+        6.4, 6.0, 4.9, 3.7, 2.7,
+    ],
+    [
+        2953.7, 2848.8, 2745.8, 2645.3, 2547.3, 2451.7,
+        2358.9, 2267.7, 2178.7, 2092.4, 2008.1, 1926.7,
+        1847.5, 1770.5, 1696.3, 1623.9, 1555.0, 1486.5,
+        1421.0, 1357.7, 1295.5, 1236.4, 1178.5, 1122.8,
+        1069.7, 1018.0, 967.8, 920.5, 874.5, 830.3,
+        788.4, 747.4, 708.5, 670.2, 633.4, 599.3,
+        567.2, 536.4, 505.7, 477.1, 449.4, 425.3,
+        400.8, 378.2, 355.7, 336.4, 318.3, 300.5,
+        282.5, 265.1, 249.2, 234.2, 219.6, 203.7,
+        191.4, 178.4, 169.0, 159.8, 150.7, 145.0,
+        135.5, 128.3, 119.3, 112.5, 105.1, 97.3,
+        89.4, 85.8, 79.2, 71.8, 66.2, 59.4,
+        52.2, 47.6, 44.7, 45.6, 41.1, 37.1,
+        29.9, 26.2, 25.9, 23.1, 23.0, 24.7,
+        26.8, 24.6, 21.5, 22.5, 23.2, 22.8,
+        21.7, 22.2, 21.9, 18.3, 17.9, 16.4,
+        18.0, 17.9, 15.0, 17.4, 18.9,
+    ],
+    [
+        5908.1, 5697.7, 5492.7, 5293.4, 5095.8, 4904.8,
+        4719.0, 4537.0, 4362.2, 4190.5, 4021.0, 3859.9,
+        3700.0, 3546.9, 3397.6, 3252.7, 3111.8, 2973.5,
+        2840.6, 2714.6, 2591.4, 2471.3, 2356.1, 2244.4,
+        2137.9, 2034.3, 1935.0, 1843.1, 1754.6, 1667.3,
+        1585.1, 1500.8, 1422.4, 1349.2, 1278.1, 1214.9,
+        1150.9, 1090.4, 1029.9, 977.5, 925.5, 881.4,
+        831.5, 790.6, 746.9, 706.3, 676.6, 635.7,
+        596.6, 561.3, 533.6, 507.2, 475.0, 453.1,
+        424.9, 404.2, 383.1, 361.4, 340.1, 319.4,
+        299.0, 274.0, 253.8, 239.1, 215.5, 204.2,
+        199.1, 191.5, 186.1, 175.7, 173.4, 161.4,
+        151.8, 142.6, 135.2, 124.2, 116.4, 105.0,
+        102.8, 100.2, 89.0, 77.3, 66.8, 71.6,
+        72.8, 69.5, 62.3, 50.6, 47.1, 41.2,
+        46.5, 36.7, 29.9, 27.7, 35.1, 37.7,
+        32.4, 28.6, 31.5, 21.6, 18.9,
+    ],
+    [
+        11817.0, 11396.7, 10984.5, 10583.1, 10190.2, 9805.9,
+        9432.2, 9070.1, 8714.7, 8370.3, 8034.3, 7708.0,
+        7392.2, 7081.5, 6779.2, 6487.8, 6208.3, 5935.1,
+        5672.4, 5420.3, 5175.9, 4933.9, 4703.5, 4477.9,
+        4257.4, 4054.4, 3857.4, 3665.6, 3486.1, 3307.3,
+        3139.2, 2980.0, 2827.4, 2681.1, 2540.5, 2405.9,
+        2266.5, 2145.2, 2030.8, 1919.9, 1814.7, 1708.4,
+        1612.3, 1521.4, 1433.4, 1353.6, 1275.9, 1202.2,
+        1120.0, 1056.5, 993.9, 934.1, 867.7, 812.9,
+        770.3, 721.8, 679.9, 627.2, 584.2, 543.6,
+        511.2, 472.0, 442.8, 407.8, 379.5, 360.8,
+        330.6, 309.2, 290.2, 270.3, 243.8, 216.2,
+        209.8, 208.9, 193.5, 189.5, 171.6, 160.4,
+        154.1, 128.1, 120.2, 120.4, 111.0, 105.4,
+        98.9, 96.1, 90.5, 78.2, 74.4, 67.2,
+        63.2, 56.9, 41.5, 35.9, 22.2, 34.0,
+        11.3, 13.3, 9.8, -3.4, -10.8,
+    ],
+    [
+        23634.8, 22792.7, 21971.0, 21164.6, 20381.5, 19617.4,
+        18870.6, 18144.7, 17437.3, 16747.2, 16075.9, 15423.8,
+        14787.2, 14175.2, 13573.9, 12994.4, 12433.2, 11891.7,
+        11366.6, 10858.6, 10368.1, 9891.8, 9435.0, 8989.8,
+        8566.0, 8153.7, 7758.9, 7368.8, 7009.7, 6667.0,
+        6330.1, 6011.7, 5695.5, 5393.5, 5113.4, 4835.8,
+        4575.1, 4323.2, 4083.3, 3853.0, 3640.2, 3423.5,
+        3229.6, 3042.4, 2864.5, 2702.0, 2548.1, 2395.1,
+        2255.0, 2112.3, 1976.1, 1863.7, 1740.5, 1632.0,
+        1532.0, 1434.0, 1339.2, 1244.5, 1165.1, 1086.4,
+        1013.1, 952.2, 896.8, 847.1, 786.6, 740.0,
+        704.4, 667.8, 606.3, 566.3, 523.4, 498.0,
+        453.3, 428.5, 395.9, 357.6, 349.0, 330.6,
+        309.0, 283.9, 261.6, 225.5, 204.2, 198.0,
+        192.5, 195.3, 183.9, 176.4, 167.3, 162.9,
+        158.0, 148.3, 151.3, 160.4, 158.7, 155.7,
+        144.6, 154.7, 160.2, 162.8, 145.1,
+    ],
+    [
+        47270.3, 45589.5, 43943.9, 42335.5, 40768.2, 39235.9,
+        37740.2, 36284.2, 34869.0, 33491.3, 32149.3, 30841.2,
+        29575.1, 28345.1, 27146.6, 25987.3, 24861.4, 23772.9,
+        22723.0, 21701.9, 20716.3, 19756.3, 18840.6, 17947.0,
+        17100.2, 16279.3, 15492.3, 14737.4, 14006.3, 13309.3,
+        12637.3, 11991.3, 11379.3, 10787.0, 10216.5, 9683.4,
+        9160.8, 8663.9, 8194.9, 7739.1, 7312.5, 6903.6,
+        6525.2, 6161.7, 5794.1, 5457.6, 5142.9, 4839.5,
+        4552.8, 4272.1, 4008.5, 3766.3, 3531.9, 3310.6,
+        3067.6, 2861.1, 2662.4, 2480.9, 2301.7, 2149.8,
+        2000.5, 1862.9, 1738.3, 1654.0, 1572.3, 1451.3,
+        1346.5, 1254.3, 1167.7, 1091.6, 1008.9, 944.5,
+        881.8, 817.4, 754.7, 689.7, 648.7, 584.0,
+        551.8, 514.0, 479.9, 429.2, 386.3, 374.4,
+        359.6, 334.6, 322.0, 301.2, 267.0, 257.4,
+        241.6, 257.5, 216.2, 169.3, 153.5, 171.9,
+        169.6, 176.9, 147.2, 150.3, 166.9,
+    ],
+    [
+        94541.5, 91176.8, 87887.9, 84676.3, 81538.8, 78477.6,
+        75492.1, 72585.5, 69750.5, 66990.6, 64308.1, 61698.8,
+        59159.3, 56698.1, 54310.5, 51993.2, 49743.9, 47565.0,
+        45464.9, 43433.1, 41466.6, 39559.0, 37720.0, 35930.0,
+        34215.1, 32567.8, 30979.7, 29463.0, 28004.6, 26604.1,
+        25229.2, 23935.6, 22690.8, 21495.8, 20364.0, 19269.1,
+        18229.6, 17212.6, 16259.7, 15350.6, 14482.2, 13651.3,
+        12863.6, 12127.2, 11431.0, 10740.5, 10104.3, 9513.0,
+        8934.0, 8401.9, 7869.1, 7377.8, 6940.4, 6484.1,
+        6068.1, 5685.6, 5310.2, 4947.5, 4631.6, 4317.4,
+        4020.0, 3721.8, 3451.4, 3199.7, 2968.7, 2758.6,
+        2559.6, 2360.0, 2187.3, 2023.7, 1878.0, 1729.5,
+        1619.4, 1499.9, 1337.6, 1189.1, 1058.1, 982.0,
+        867.7, 785.3, 683.1, 650.3, 607.4, 538.3,
+        496.3, 444.8, 415.6, 352.4, 319.0, 285.3,
+        257.1, 243.1, 182.8, 189.3, 156.3, 134.5,
+        112.4, 71.7, 46.6, 43.2, 18.5,
+    ],
+    [
+        189083.7, 182354.9, 175775.5, 169344.0, 163066.7, 156938.8,
+        150963.3, 145143.1, 139467.9, 133944.0, 128575.9, 123353.5,
+        118278.7, 113343.8, 108559.6, 103915.0, 99431.9, 95090.3,
+        90885.5, 86814.1, 82886.6, 79092.4, 75425.1, 71893.3,
+        68478.3, 65186.6, 62010.0, 58964.5, 56056.1, 53233.9,
+        50524.2, 47923.6, 45426.4, 43030.1, 40762.0, 38574.7,
+        36486.1, 34509.5, 32621.9, 30785.8, 29067.4, 27434.0,
+        25836.1, 24328.8, 22915.1, 21556.7, 20299.8, 19087.3,
+        17919.3, 16818.1, 15763.8, 14775.6, 13823.3, 12925.5,
+        12096.0, 11292.9, 10518.0, 9847.4, 9207.0, 8621.8,
+        8065.2, 7532.5, 7044.8, 6620.9, 6189.9, 5773.4,
+        5420.2, 5002.9, 4679.0, 4399.5, 4113.2, 3839.7,
+        3597.0, 3339.8, 3098.3, 2863.3, 2698.4, 2503.3,
+        2327.8, 2177.2, 1987.1, 1857.2, 1728.7, 1616.3,
+        1509.4, 1355.8, 1243.9, 1140.4, 1079.3, 968.9,
+        956.1, 912.1, 832.5, 783.4, 703.7, 663.3,
+        622.8, 558.3, 565.7, 558.9, 556.8,
+    ],
+];
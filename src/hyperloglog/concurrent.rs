@@ -0,0 +1,260 @@
+//! A HyperLogLog which many threads can feed at once.
+use std::{
+    hash::Hash,
+    mem,
+    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+};
+
+use super::{split_hash, HyperLogLog};
+use crate::{hash::hash64, DistinctCounter, DistinctError, Estimate};
+
+/// Number of counters the processed elements are spread over, so that threads inserting different
+/// elements do not all write to the same cache line.
+const PROCESSED_STRIPES: usize = 16;
+
+/// A counter on a cache line of its own.
+#[derive(Debug, Default)]
+#[repr(align(64))]
+struct Padded(AtomicUsize);
+
+/// A [`HyperLogLog`] which is fed through a shared reference, for counting a stream ingested by
+/// many threads into one sketch.
+///
+/// Registers only ever grow, so each is updated lock-free with an atomic maximum and no update can
+/// be lost: once every insertion has returned, the registers are exactly those of a
+/// [`HyperLogLog`] fed the same elements in any order. Registers which already hold a larger rank,
+/// the vast majority once a sketch has filled up, are only read. The count of processed elements
+/// is spread over several counters, picked by hash, to keep writers apart.
+///
+/// Estimates are computed from a [`snapshot`](ConcurrentHyperLogLog::snapshot) of the registers,
+/// which can be taken while other threads keep inserting; it then reflects some of the concurrent
+/// insertions but not necessarily all of them.
+///
+/// # Examples
+/// ```rust
+/// use distinction::ConcurrentHyperLogLog;
+/// use std::thread;
+///
+/// let hll = ConcurrentHyperLogLog::new(12);
+/// thread::scope(|scope| {
+///     for thread in 0..4 {
+///         let hll = &hll;
+///         scope.spawn(move || {
+///             for i in 0..1000 {
+///                 hll.insert(&(i % (10 * (thread + 1))));
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(hll.estimate(), 40);
+/// ```
+#[derive(Debug)]
+pub struct ConcurrentHyperLogLog {
+    precision: u8,
+    registers: Box<[AtomicU8]>,
+    processed: [Padded; PROCESSED_STRIPES],
+}
+
+impl ConcurrentHyperLogLog {
+    /// Creates an empty sketch with `2^precision` registers.
+    ///
+    /// # Panics
+    /// Panics if `precision` is outside of [`MIN_PRECISION`](super::MIN_PRECISION)`..=`
+    /// [`MAX_PRECISION`](super::MAX_PRECISION).
+    pub fn new(precision: u8) -> Self {
+        Self::try_new(precision).expect("invalid HyperLogLog precision")
+    }
+
+    /// Fallible version of [`ConcurrentHyperLogLog::new`].
+    pub fn try_new(precision: u8) -> Result<Self, DistinctError> {
+        HyperLogLog::try_new(precision).map(Self::from)
+    }
+
+    /// Returns the precision the sketch was created with.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Feeds a single element of the stream into the sketch.
+    pub fn insert<Q>(&self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item));
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`].
+    pub fn insert_hash(&self, hash: u64) {
+        self.processed[hash as usize % PROCESSED_STRIPES]
+            .0
+            .fetch_add(1, Ordering::Relaxed);
+
+        let (index, rank) = split_hash(hash, self.precision);
+        let register = &self.registers[index];
+        if register.load(Ordering::Relaxed) < rank {
+            register.fetch_max(rank, Ordering::Relaxed);
+        }
+    }
+
+    /// Copies the current state of the sketch into a [`HyperLogLog`], e.g. to merge or serialize
+    /// it.
+    pub fn snapshot(&self) -> HyperLogLog {
+        HyperLogLog {
+            precision: self.precision,
+            registers: self
+                .registers
+                .iter()
+                .map(|register| register.load(Ordering::Relaxed))
+                .collect(),
+            processed: self.processed(),
+        }
+    }
+
+    /// Returns the sketch fed so far, once no other thread can insert into it any more.
+    pub fn into_inner(self) -> HyperLogLog {
+        let processed = self.processed();
+        HyperLogLog {
+            precision: self.precision,
+            registers: Vec::from(self.registers)
+                .into_iter()
+                .map(AtomicU8::into_inner)
+                .collect(),
+            processed,
+        }
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far.
+    pub fn estimate(&self) -> usize {
+        self.snapshot().estimate()
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far. See
+    /// [`HyperLogLog::try_estimate`].
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        self.snapshot().try_estimate()
+    }
+
+    /// Folds the registers of `other` into this sketch, while other threads may keep inserting.
+    ///
+    /// Fails if the two sketches were created with different precisions.
+    pub fn merge(&self, other: &HyperLogLog) -> Result<(), DistinctError> {
+        if self.precision != other.precision {
+            return Err(DistinctError::PrecisionMismatch(
+                self.precision,
+                other.precision,
+            ));
+        }
+
+        for (register, &theirs) in self.registers.iter().zip(&other.registers) {
+            register.fetch_max(theirs, Ordering::Relaxed);
+        }
+        self.processed[0]
+            .0
+            .fetch_add(other.processed, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    fn processed(&self) -> usize {
+        self.processed
+            .iter()
+            .map(|stripe| stripe.0.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+impl From<HyperLogLog> for ConcurrentHyperLogLog {
+    fn from(hll: HyperLogLog) -> Self {
+        let mut processed: [Padded; PROCESSED_STRIPES] = Default::default();
+        *processed[0].0.get_mut() = hll.processed;
+
+        Self {
+            precision: hll.precision,
+            registers: hll.registers.into_iter().map(AtomicU8::new).collect(),
+            processed,
+        }
+    }
+}
+
+impl<Q> DistinctCounter<Q> for ConcurrentHyperLogLog
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        ConcurrentHyperLogLog::insert(self, &item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.registers.len()
+    }
+
+    /// The registers are fixed in size, so this is the same as
+    /// [`clear`](DistinctCounter::clear).
+    fn reset(&mut self) {
+        DistinctCounter::<Q>::clear(self);
+    }
+
+    fn clear(&mut self) {
+        for register in self.registers.iter_mut() {
+            *register.get_mut() = 0;
+        }
+        for stripe in &mut self.processed {
+            *stripe.0.get_mut() = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperloglog::DEFAULT_PRECISION;
+    use std::thread;
+
+    #[test]
+    fn no_update_is_lost_under_contention() {
+        let hll = ConcurrentHyperLogLog::new(DEFAULT_PRECISION);
+        // Every thread inserts the same elements, racing on the same registers.
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for i in 0..50_000u64 {
+                        hll.insert(&i);
+                    }
+                });
+            }
+        });
+        let sequential: HyperLogLog = (0..50_000u64).collect();
+
+        let concurrent = hll.into_inner();
+        assert_eq!(concurrent.registers, sequential.registers);
+        assert_eq!(concurrent.processed, 8 * 50_000);
+
+        let estimate = concurrent.try_estimate().unwrap();
+        assert!(
+            estimate.lower <= 50_000.0 && 50_000.0 <= estimate.upper,
+            "{}",
+            estimate
+        );
+    }
+
+    #[test]
+    fn snapshots_merge_with_sequential_sketches() {
+        let hll = ConcurrentHyperLogLog::from((0..1000).collect::<HyperLogLog>());
+        let other: HyperLogLog = (500..2000).collect();
+        hll.merge(&other).unwrap();
+        assert_eq!(hll.snapshot(), {
+            let mut union: HyperLogLog = (0..1000).collect();
+            union.merge(&other).unwrap();
+            union
+        });
+
+        assert_eq!(
+            hll.merge(&HyperLogLog::new(10)),
+            Err(DistinctError::PrecisionMismatch(14, 10))
+        );
+    }
+}
@@ -0,0 +1,369 @@
+//! The HyperLogLog cardinality estimator of Flajolet, Fusy, Gandouet and Meunier.
+use std::{hash::Hash, mem};
+
+use crate::{
+    codec::{self, DecodeError, Kind, Reader},
+    datasketches::{self, HllType},
+    hash::hash64,
+    DistinctCounter, DistinctError, Estimate, Mergeable,
+};
+
+mod bias;
+mod concurrent;
+mod plus_plus;
+mod sliding;
+
+pub use concurrent::ConcurrentHyperLogLog;
+pub use plus_plus::HyperLogLogPlusPlus;
+pub use sliding::SlidingHyperLogLog;
+
+/// Smallest supported precision.
+pub const MIN_PRECISION: u8 = 4;
+
+/// Largest supported precision.
+pub const MAX_PRECISION: u8 = 18;
+
+/// Precision used when a sketch is built without explicit parameters, e.g. through
+/// [`FromIterator`]. Gives 16384 registers and a standard error of about 0.8%.
+pub const DEFAULT_PRECISION: u8 = 14;
+
+/// A [HyperLogLog](https://algo.inria.fr/flajolet/Publications/FlFuGaMe07.pdf) sketch.
+///
+/// Where [`CvmEstimator`](crate::CvmEstimator) keeps a sample whose size depends on `eps` and
+/// `delta`, a HyperLogLog keeps `2^precision` one-byte registers no matter how long the stream is.
+/// This makes it a good fit for long-lived counters and for merging many sketches, at the cost of a
+/// probabilistic error of about `1.04 / sqrt(2^precision)` rather than a hard `(eps, delta)`
+/// guarantee.
+///
+/// Elements are hashed with [`hash64`]. The raw estimate is scaled by the
+/// bias correction constant `alpha_m`, and small cardinalities, where the raw estimate is biased,
+/// are estimated with linear counting over the empty registers instead. Since the hash is 64 bits
+/// wide no large range correction is needed.
+///
+/// # Examples
+/// ```rust
+/// use distinction::HyperLogLog;
+/// let mut hll = HyperLogLog::new(12);
+/// for item in [1, 10, 20, 10, 10, 30, 20, 10, 20, 20, 1, 1, 1] {
+///     hll.insert(&item);
+/// }
+/// assert_eq!(hll.estimate(), 4);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+    processed: usize,
+}
+
+impl HyperLogLog {
+    /// Creates an empty sketch with `2^precision` registers.
+    ///
+    /// # Panics
+    /// Panics if `precision` is outside of [`MIN_PRECISION`]`..=`[`MAX_PRECISION`].
+    pub fn new(precision: u8) -> Self {
+        Self::try_new(precision).expect("invalid HyperLogLog precision")
+    }
+
+    /// Fallible version of [`HyperLogLog::new`].
+    pub fn try_new(precision: u8) -> Result<Self, DistinctError> {
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return Err(DistinctError::InvalidPrecision(precision));
+        }
+
+        Ok(Self {
+            precision,
+            registers: vec![0; 1 << precision],
+            processed: 0,
+        })
+    }
+
+    /// Returns the precision the sketch was created with.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Feeds a single element of the stream into the sketch.
+    pub fn insert<Q>(&mut self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item));
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`].
+    pub fn insert_hash(&mut self, hash: u64) {
+        self.processed += 1;
+
+        let (index, rank) = split_hash(hash, self.precision);
+        let register = &mut self.registers[index];
+        *register = (*register).max(rank);
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far.
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far. A HyperLogLog
+    /// always produces an estimate; the `Result` matches [`CvmEstimator::try_estimate`].
+    ///
+    /// [`CvmEstimator::try_estimate`]: crate::CvmEstimator::try_estimate
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        let m = self.registers.len();
+        let raw = raw_estimate(&self.registers);
+
+        let zero_registers = self.registers.iter().filter(|&&r| r == 0).count();
+        let linear_counting = raw <= 2.5 * m as f64 && zero_registers > 0;
+        let value = if linear_counting {
+            linear_counting_estimate(m, zero_registers)
+        } else {
+            raw
+        };
+
+        Ok(Estimate::hyperloglog(
+            value,
+            self.precision,
+            zero_registers,
+            linear_counting,
+            self.processed,
+        ))
+    }
+
+    /// Folds the registers of `other` into this sketch, so that it estimates the number of
+    /// distinct elements across both streams. Unlike [`CvmEstimator::merge`] the result is exactly
+    /// the sketch of the concatenated stream, overlapping or not.
+    ///
+    /// Fails if the two sketches were created with different precisions.
+    ///
+    /// [`CvmEstimator::merge`]: crate::CvmEstimator::merge
+    pub fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        if self.precision != other.precision {
+            return Err(DistinctError::PrecisionMismatch(
+                self.precision,
+                other.precision,
+            ));
+        }
+
+        for (register, &theirs) in self.registers.iter_mut().zip(&other.registers) {
+            *register = (*register).max(theirs);
+        }
+        self.processed += other.processed;
+
+        Ok(())
+    }
+
+    /// Serializes the sketch in the format described in [`codec`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        codec::encode(Kind::HyperLogLog, |writer| {
+            writer.u8(self.precision);
+            writer.usize(self.processed);
+            writer.bytes(&self.registers);
+        })
+    }
+
+    /// Reads back a sketch written by [`HyperLogLog::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        codec::decode(bytes, Kind::HyperLogLog, |reader, _| {
+            let precision = read_precision(reader)?;
+            let processed = reader.usize()?;
+            let registers = read_registers(reader, precision)?;
+
+            Ok(Self {
+                precision,
+                registers,
+                processed,
+            })
+        })
+    }
+
+    /// Serializes the sketch as an Apache DataSketches HyperLogLog sketch of the given type, see
+    /// [`datasketches`].
+    pub fn to_datasketches(&self, hll_type: HllType) -> Vec<u8> {
+        datasketches::write_hll(self.precision, &self.registers, hll_type)
+    }
+
+    /// Reads an Apache DataSketches HyperLogLog sketch of any type, see [`datasketches`].
+    ///
+    /// Fails if the sketch has more registers than [`MAX_PRECISION`] allows.
+    pub fn from_datasketches(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (precision, registers) = datasketches::read_hll(bytes)?;
+        Ok(Self {
+            precision,
+            registers,
+            processed: 0,
+        })
+    }
+}
+
+fn read_precision(reader: &mut Reader) -> Result<u8, DecodeError> {
+    let precision = reader.u8()?;
+    if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+        return Err(DecodeError::Invalid("precision out of range"));
+    }
+    Ok(precision)
+}
+
+/// Reads `2^precision` dense registers, checking that each holds a possible rank.
+fn read_registers(reader: &mut Reader, precision: u8) -> Result<Vec<u8>, DecodeError> {
+    let registers = reader.bytes(1 << precision)?;
+    if registers.iter().any(|&rank| rank > 64 - precision + 1) {
+        return Err(DecodeError::Invalid("register holds an impossible rank"));
+    }
+    Ok(registers.to_vec())
+}
+
+/// Splits a hash into the index of the register it updates and the value observed for it.
+///
+/// The top `precision` bits pick the register, the position of the first set bit in the remaining
+/// ones is the value. The sentinel bit caps that value at `64 - precision + 1`.
+fn split_hash(hash: u64, precision: u8) -> (usize, u8) {
+    let index = (hash >> (64 - precision)) as usize;
+    let rest = (hash << precision) | (1 << (precision - 1));
+    (index, rest.leading_zeros() as u8 + 1)
+}
+
+/// The raw HyperLogLog estimate, the bias corrected harmonic mean of `2^register`.
+fn raw_estimate(registers: &[u8]) -> f64 {
+    let m = registers.len() as f64;
+    let alpha = match registers.len() {
+        16 => 0.673,
+        32 => 0.697,
+        64 => 0.709,
+        _ => 0.7213 / (1.0 + 1.079 / m),
+    };
+
+    let sum: f64 = registers
+        .iter()
+        .map(|&register| 2f64.powi(-(register as i32)))
+        .sum();
+    alpha * m * m / sum
+}
+
+/// Linear counting: the expected number of distinct elements that leaves `zero` out of `m` buckets
+/// empty.
+fn linear_counting_estimate(m: usize, zero: usize) -> f64 {
+    m as f64 * (m as f64 / zero as f64).ln()
+}
+
+impl<Q> DistinctCounter<Q> for HyperLogLog
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        self.insert(&item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.registers.capacity()
+    }
+
+    /// The registers are fixed in size, so this is the same as
+    /// [`clear`](DistinctCounter::clear).
+    fn reset(&mut self) {
+        DistinctCounter::<Q>::clear(self);
+    }
+
+    fn clear(&mut self) {
+        self.registers.fill(0);
+        self.processed = 0;
+    }
+}
+
+impl Mergeable for HyperLogLog {
+    fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        HyperLogLog::merge(self, other)
+    }
+}
+
+impl<Q> Extend<Q> for HyperLogLog
+where
+    Q: Hash,
+{
+    fn extend<I: IntoIterator<Item = Q>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<Q> FromIterator<Q> for HyperLogLog
+where
+    Q: Hash,
+{
+    /// Builds a sketch with [`DEFAULT_PRECISION`].
+    fn from_iter<I: IntoIterator<Item = Q>>(iter: I) -> Self {
+        let mut hll = Self::new(DEFAULT_PRECISION);
+        hll.extend(iter);
+        hll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Diagnostics;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn estimate_within_standard_error() {
+        for n in [100u64, 10_000, 1_000_000] {
+            let hll: HyperLogLog = (0..n).collect();
+            let estimate = hll.try_estimate().unwrap();
+            let relative_error = 1.04 / ((1 << DEFAULT_PRECISION) as f64).sqrt();
+
+            assert!((estimate.value - n as f64).abs() <= 3.0 * relative_error * n as f64);
+            assert!(estimate.lower < estimate.value && estimate.value < estimate.upper);
+        }
+    }
+
+    #[test]
+    fn small_cardinalities_use_linear_counting() {
+        let hll: HyperLogLog = (0..10).chain(0..10).collect();
+        let estimate = hll.try_estimate().unwrap();
+
+        assert_eq!(estimate.count(), 10);
+        assert_eq!(estimate.processed, 20);
+        assert!(matches!(
+            estimate.diagnostics,
+            Diagnostics::HyperLogLog {
+                linear_counting: true,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn merge_matches_union() {
+        let mut left: HyperLogLog = (0..60_000).collect();
+        let right: HyperLogLog = (40_000..100_000).collect();
+        let union: HyperLogLog = (0..100_000).collect();
+
+        left.merge(&right).unwrap();
+        assert_eq!(left.registers, union.registers);
+
+        assert_eq!(
+            left.merge(&HyperLogLog::new(10)),
+            Err(DistinctError::PrecisionMismatch(14, 10))
+        );
+        assert_eq!(
+            HyperLogLog::try_new(3),
+            Err(DistinctError::InvalidPrecision(3))
+        );
+    }
+
+    quickcheck! {
+        fn qc_prop_bytes_round_trip(stream: Vec<u32>, precision: u8) -> bool {
+            let mut hll = HyperLogLog::new(MIN_PRECISION + precision % 8);
+            hll.extend(stream);
+
+            let bytes = hll.to_bytes();
+            HyperLogLog::from_bytes(&bytes) == Ok(hll)
+        }
+    }
+}
@@ -0,0 +1,735 @@
+//! HyperLogLog++, the HyperLogLog variant of Heule, Nunkesser and Hall.
+use std::{borrow::Cow, hash::Hash, mem};
+
+use super::{
+    bias::{BIAS, RAW_ESTIMATES},
+    linear_counting_estimate, raw_estimate, read_precision, read_registers, split_hash,
+    DEFAULT_PRECISION, MAX_PRECISION, MIN_PRECISION,
+};
+use crate::{
+    codec::{self, DecodeError, Kind},
+    hash::hash64,
+    DistinctCounter, DistinctError, Estimate, Mergeable,
+};
+
+/// Precision of the sparse encoding. Hashes are kept with `2^25` buckets until the sketch
+/// converts to dense registers.
+const SPARSE_PRECISION: u8 = 25;
+
+/// Number of nearest raw estimates whose bias is averaged when correcting an estimate.
+const BIAS_NEIGHBOURS: usize = 6;
+
+/// Cardinality below which linear counting beats the bias corrected estimate, for each precision
+/// starting at [`MIN_PRECISION`]. Taken from the paper.
+const LINEAR_COUNTING_THRESHOLDS: [f64; 15] = [
+    10.0, 20.0, 40.0, 80.0, 220.0, 400.0, 900.0, 1800.0, 3100.0, 6500.0, 11500.0, 20000.0, 50000.0,
+    120000.0, 350000.0,
+];
+
+/// A [HyperLogLog++](https://research.google/pubs/pub40671/) sketch.
+///
+/// Behaves like a [`HyperLogLog`](super::HyperLogLog) with three refinements:
+///
+/// - Small sketches use a sparse encoding, a sorted list of the 25-bit bucket indices and ranks
+///   that have been observed. A sketch of a handful of elements takes a few dozen bytes instead of
+///   `2^precision`, and is nearly exact. Once the list would take more memory than the dense
+///   registers the sketch converts to them.
+/// - Between linear counting and `5 * 2^precision` the raw estimate is corrected with empirical
+///   bias tables, which removes the error bump [`HyperLogLog`](super::HyperLogLog) shows around
+///   the switch from linear counting.
+/// - Elements are hashed with the 64-bit [`hash64`], so no large range correction is needed.
+///
+/// # Examples
+/// ```rust
+/// use distinction::HyperLogLogPlusPlus;
+/// let mut hll = HyperLogLogPlusPlus::new(14);
+/// for item in [1, 10, 20, 10, 10, 30, 20, 10, 20, 20, 1, 1, 1] {
+///     hll.insert(&item);
+/// }
+/// assert!(hll.is_sparse());
+/// assert_eq!(hll.estimate(), 4);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HyperLogLogPlusPlus {
+    precision: u8,
+    registers: Registers,
+    processed: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Registers {
+    /// Sorted list of encoded hashes with one entry per sparse bucket, and the unsorted entries
+    /// inserted since the list was last rebuilt.
+    Sparse {
+        list: Vec<u32>,
+        buffer: Vec<u32>,
+    },
+    Dense(Vec<u8>),
+}
+
+impl HyperLogLogPlusPlus {
+    /// Creates an empty sketch which converts to `2^precision` registers once it grows large
+    /// enough.
+    ///
+    /// # Panics
+    /// Panics if `precision` is outside of [`MIN_PRECISION`]`..=`[`MAX_PRECISION`].
+    pub fn new(precision: u8) -> Self {
+        Self::try_new(precision).expect("invalid HyperLogLog precision")
+    }
+
+    /// Fallible version of [`HyperLogLogPlusPlus::new`].
+    pub fn try_new(precision: u8) -> Result<Self, DistinctError> {
+        if !(MIN_PRECISION..=MAX_PRECISION).contains(&precision) {
+            return Err(DistinctError::InvalidPrecision(precision));
+        }
+
+        Ok(Self {
+            precision,
+            registers: Registers::Sparse {
+                list: Vec::new(),
+                buffer: Vec::new(),
+            },
+            processed: 0,
+        })
+    }
+
+    /// Returns the precision the sketch was created with.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Returns whether the sketch still uses its sparse encoding.
+    pub fn is_sparse(&self) -> bool {
+        matches!(self.registers, Registers::Sparse { .. })
+    }
+
+    /// Feeds a single element of the stream into the sketch.
+    pub fn insert<Q>(&mut self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item));
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`].
+    pub fn insert_hash(&mut self, hash: u64) {
+        self.processed += 1;
+        let sparse_limit = self.sparse_limit();
+
+        match &mut self.registers {
+            Registers::Sparse { buffer, .. } => {
+                buffer.push(encode(hash, self.precision));
+                if buffer.len() >= sparse_limit {
+                    self.flush();
+                }
+            }
+            Registers::Dense(registers) => {
+                let (index, rank) = split_hash(hash, self.precision);
+                registers[index] = registers[index].max(rank);
+            }
+        }
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far.
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far. A
+    /// HyperLogLog++ always produces an estimate; the `Result` matches
+    /// [`CvmEstimator::try_estimate`].
+    ///
+    /// [`CvmEstimator::try_estimate`]: crate::CvmEstimator::try_estimate
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        let registers = match &self.registers {
+            Registers::Sparse { list, buffer } => {
+                let buckets = 1 << SPARSE_PRECISION;
+                let occupied = if buffer.is_empty() {
+                    list.len()
+                } else {
+                    rebuild(list, buffer).len()
+                };
+
+                return Ok(Estimate::hyperloglog_plus_plus(
+                    linear_counting_estimate(buckets, buckets - occupied),
+                    self.precision,
+                    true,
+                    true,
+                    false,
+                    self.processed,
+                ));
+            }
+            Registers::Dense(registers) => registers,
+        };
+
+        let m = registers.len();
+        let raw = raw_estimate(registers);
+        let bias_corrected = raw <= 5.0 * m as f64;
+        let corrected = if bias_corrected {
+            (raw - estimate_bias(self.precision, raw)).max(0.0)
+        } else {
+            raw
+        };
+
+        let zero_registers = registers.iter().filter(|&&r| r == 0).count();
+        let linear = (zero_registers > 0).then(|| linear_counting_estimate(m, zero_registers));
+        let threshold = LINEAR_COUNTING_THRESHOLDS[usize::from(self.precision - MIN_PRECISION)];
+
+        let (value, linear_counting) = match linear {
+            Some(linear) if linear <= threshold => (linear, true),
+            _ => (corrected, false),
+        };
+
+        Ok(Estimate::hyperloglog_plus_plus(
+            value,
+            self.precision,
+            false,
+            linear_counting,
+            bias_corrected && !linear_counting,
+            self.processed,
+        ))
+    }
+
+    /// Folds `other` into this sketch, so that it estimates the number of distinct elements
+    /// across both streams. As for [`HyperLogLog::merge`] the result is the sketch of the
+    /// concatenated stream. Two sparse sketches stay sparse as long as their union is small
+    /// enough.
+    ///
+    /// Fails if the two sketches were created with different precisions.
+    ///
+    /// [`HyperLogLog::merge`]: super::HyperLogLog::merge
+    pub fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        if self.precision != other.precision {
+            return Err(DistinctError::PrecisionMismatch(
+                self.precision,
+                other.precision,
+            ));
+        }
+
+        match (&mut self.registers, &other.registers) {
+            (
+                Registers::Sparse { buffer, .. },
+                Registers::Sparse {
+                    list: their_list,
+                    buffer: their_buffer,
+                },
+            ) => {
+                buffer.extend(their_list);
+                buffer.extend(their_buffer);
+                self.flush();
+            }
+            _ => {
+                self.densify();
+                let theirs = other.dense_registers();
+                if let Registers::Dense(registers) = &mut self.registers {
+                    for (register, &theirs) in registers.iter_mut().zip(theirs.iter()) {
+                        *register = (*register).max(theirs);
+                    }
+                }
+            }
+        }
+        self.processed += other.processed;
+
+        Ok(())
+    }
+
+    /// Serializes the sketch in the format described in [`codec`]. A sparse sketch
+    /// is written as its sorted list of encoded hashes, so it stays small on disk too.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        codec::encode(Kind::HyperLogLogPlusPlus, |writer| {
+            writer.u8(self.precision);
+            writer.usize(self.processed);
+            match &self.registers {
+                Registers::Sparse { list, buffer } => {
+                    let list = rebuild(list, buffer);
+                    writer.u8(0);
+                    writer.usize(list.len());
+                    for entry in list {
+                        writer.u32(entry);
+                    }
+                }
+                Registers::Dense(registers) => {
+                    writer.u8(1);
+                    writer.bytes(registers);
+                }
+            }
+        })
+    }
+
+    /// Reads back a sketch written by [`HyperLogLogPlusPlus::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        codec::decode(bytes, Kind::HyperLogLogPlusPlus, |reader, _| {
+            let precision = read_precision(reader)?;
+            let processed = reader.usize()?;
+
+            let registers = match reader.u8()? {
+                0 => {
+                    let len = reader.usize()?;
+                    // The list and the buffer each stay below the sparse limit.
+                    if len >= 2 * (1 << precision) / mem::size_of::<u32>() {
+                        return Err(DecodeError::Invalid("sparse list too large"));
+                    }
+                    let list = (0..len)
+                        .map(|_| reader.u32())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if !list.iter().all(|&entry| is_valid_entry(entry, precision))
+                        || list
+                            .windows(2)
+                            .any(|pair| sparse_index(pair[0]) >= sparse_index(pair[1]))
+                    {
+                        return Err(DecodeError::Invalid("malformed sparse list"));
+                    }
+
+                    Registers::Sparse {
+                        list,
+                        buffer: Vec::new(),
+                    }
+                }
+                1 => Registers::Dense(read_registers(reader, precision)?),
+                _ => return Err(DecodeError::Invalid("unknown register representation")),
+            };
+
+            Ok(Self {
+                precision,
+                registers,
+                processed,
+            })
+        })
+    }
+
+    /// Number of entries the sparse list may hold before it takes more memory than the dense
+    /// registers. The buffer is flushed into the list at the same size.
+    fn sparse_limit(&self) -> usize {
+        (1 << self.precision) / mem::size_of::<u32>()
+    }
+
+    /// Sorts the buffer into the sparse list, converting to dense registers if the list has grown
+    /// too large.
+    fn flush(&mut self) {
+        let sparse_limit = self.sparse_limit();
+
+        if let Registers::Sparse { list, buffer } = &mut self.registers {
+            *list = rebuild(list, buffer);
+            buffer.clear();
+
+            if list.len() > sparse_limit {
+                self.densify();
+            }
+        }
+    }
+
+    /// Converts the sketch to dense registers, if it is not already.
+    fn densify(&mut self) {
+        if self.is_sparse() {
+            self.registers = Registers::Dense(self.dense_registers().into_owned());
+        }
+    }
+
+    /// Returns the dense registers this sketch corresponds to.
+    fn dense_registers(&self) -> Cow<'_, [u8]> {
+        match &self.registers {
+            Registers::Sparse { list, buffer } => {
+                let mut registers = vec![0; 1 << self.precision];
+                for &entry in list.iter().chain(buffer) {
+                    let (index, rank) = decode(entry, self.precision);
+                    registers[index] = registers[index].max(rank);
+                }
+                Cow::Owned(registers)
+            }
+            Registers::Dense(registers) => Cow::Borrowed(registers),
+        }
+    }
+}
+
+/// Encodes a hash for the sparse list.
+///
+/// The top 25 bits are the sparse bucket index. If the bits of it below the top `precision` ones
+/// are not all zero they already determine the dense rank, and the entry is just `index << 1`.
+/// Otherwise the rank of the remaining hash bits is stored too, as `index << 7 | rank << 1 | 1`.
+fn encode(hash: u64, precision: u8) -> u32 {
+    let index = (hash >> (64 - SPARSE_PRECISION)) as u32;
+    let low_bits = (1 << (SPARSE_PRECISION - precision)) - 1;
+
+    if index & low_bits == 0 {
+        let rest = (hash << SPARSE_PRECISION) | (1 << (SPARSE_PRECISION - 1));
+        let rank = rest.leading_zeros() + 1;
+        (index << 7) | (rank << 1) | 1
+    } else {
+        index << 1
+    }
+}
+
+/// Returns the sparse bucket index of an encoded hash.
+fn sparse_index(entry: u32) -> u32 {
+    if entry & 1 == 1 {
+        entry >> 7
+    } else {
+        entry >> 1
+    }
+}
+
+/// Returns whether `entry` is one [`encode`] can produce at `precision`.
+fn is_valid_entry(entry: u32, precision: u8) -> bool {
+    let low_bits = (1 << (SPARSE_PRECISION - precision)) - 1;
+    let index = sparse_index(entry);
+
+    if entry & 1 == 1 {
+        let rank = (entry >> 1) & 0x3f;
+        index & low_bits == 0 && (1..=u32::from(64 - SPARSE_PRECISION + 1)).contains(&rank)
+    } else {
+        index & low_bits != 0 && index >> SPARSE_PRECISION == 0
+    }
+}
+
+/// Decodes an entry of the sparse list into the dense register index and rank of its hash, the
+/// same as [`split_hash`] would have returned.
+fn decode(entry: u32, precision: u8) -> (usize, u8) {
+    let low = u32::from(SPARSE_PRECISION - precision);
+    let index = sparse_index(entry);
+
+    let rank = if entry & 1 == 1 {
+        ((entry >> 1) & 0x3f) + low
+    } else {
+        let bits = index & ((1 << low) - 1);
+        bits.leading_zeros() - (32 - low) + 1
+    };
+
+    ((index >> low) as usize, rank as u8)
+}
+
+/// Returns the sorted sparse list holding the entries of both `list` and `buffer`, keeping the
+/// largest rank for each sparse bucket.
+fn rebuild(list: &[u32], buffer: &[u32]) -> Vec<u32> {
+    let mut merged: Vec<u32> = list.iter().chain(buffer).copied().collect();
+    // For a given bucket a larger entry always means a larger rank.
+    merged.sort_unstable_by_key(|&entry| (sparse_index(entry), u32::MAX - entry));
+    merged.dedup_by_key(|entry| sparse_index(*entry));
+    merged
+}
+
+/// Estimates the bias of a raw estimate by averaging the bias of its nearest neighbours in the
+/// empirical tables.
+fn estimate_bias(precision: u8, raw: f64) -> f64 {
+    let row = usize::from(precision - MIN_PRECISION);
+    let raw_estimates = &RAW_ESTIMATES[row];
+
+    let mut neighbours: Vec<usize> = (0..raw_estimates.len()).collect();
+    neighbours.sort_by(|&a, &b| {
+        (raw_estimates[a] - raw)
+            .abs()
+            .total_cmp(&(raw_estimates[b] - raw).abs())
+    });
+
+    neighbours[..BIAS_NEIGHBOURS]
+        .iter()
+        .map(|&i| BIAS[row][i])
+        .sum::<f64>()
+        / BIAS_NEIGHBOURS as f64
+}
+
+impl<Q> DistinctCounter<Q> for HyperLogLogPlusPlus
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        self.insert(&item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        let heap = match &self.registers {
+            Registers::Sparse { list, buffer } => {
+                (list.capacity() + buffer.capacity()) * mem::size_of::<u32>()
+            }
+            Registers::Dense(registers) => registers.capacity(),
+        };
+        mem::size_of::<Self>() + heap
+    }
+
+    /// Returns the sketch to an empty sparse encoding.
+    fn reset(&mut self) {
+        *self = Self::new(self.precision);
+    }
+
+    /// A sketch that has converted to dense registers stays dense.
+    fn clear(&mut self) {
+        match &mut self.registers {
+            Registers::Sparse { list, buffer } => {
+                list.clear();
+                buffer.clear();
+            }
+            Registers::Dense(registers) => registers.fill(0),
+        }
+        self.processed = 0;
+    }
+}
+
+impl Mergeable for HyperLogLogPlusPlus {
+    fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        HyperLogLogPlusPlus::merge(self, other)
+    }
+}
+
+impl<Q> Extend<Q> for HyperLogLogPlusPlus
+where
+    Q: Hash,
+{
+    fn extend<I: IntoIterator<Item = Q>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<Q> FromIterator<Q> for HyperLogLogPlusPlus
+where
+    Q: Hash,
+{
+    /// Builds a sketch with [`DEFAULT_PRECISION`].
+    fn from_iter<I: IntoIterator<Item = Q>>(iter: I) -> Self {
+        let mut hll = Self::new(DEFAULT_PRECISION);
+        hll.extend(iter);
+        hll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Diagnostics, Gen, HyperLogLog};
+    use quickcheck::quickcheck;
+    use rand::RngCore;
+
+    /// Feeds `n` random hashes into a fresh sketch, standing in for `n` distinct elements.
+    fn sketch_of(precision: u8, n: u64, gen: &mut Gen) -> HyperLogLogPlusPlus {
+        let mut hll = HyperLogLogPlusPlus::new(precision);
+        for _ in 0..n {
+            hll.insert_hash(gen.next_u64());
+        }
+        hll
+    }
+
+    fn assert_within_standard_error(hll: &HyperLogLogPlusPlus, n: u64) {
+        let relative_error = 1.04 / ((1u64 << hll.precision()) as f64).sqrt();
+        let estimate = hll.try_estimate().unwrap();
+
+        assert!(
+            (estimate.value - n as f64).abs() <= 3.0 * relative_error * n as f64,
+            "estimated {} for {} distinct elements",
+            estimate.value,
+            n
+        );
+    }
+
+    #[test]
+    fn estimate_within_standard_error() {
+        let mut gen = Gen::new(Some(12));
+
+        for n in [1, 10, 100, 1_000] {
+            let hll = sketch_of(DEFAULT_PRECISION, n, &mut gen);
+            assert!(hll.is_sparse());
+            assert!(hll.estimate().abs_diff(n as usize) <= 1);
+        }
+        for n in [10_000, 100_000, 1_000_000] {
+            let hll = sketch_of(DEFAULT_PRECISION, n, &mut gen);
+            assert!(!hll.is_sparse());
+            assert_within_standard_error(&hll, n);
+        }
+    }
+
+    #[test]
+    #[ignore = "feeds 10^9 hashes, run with --release"]
+    fn estimate_within_standard_error_up_to_a_billion() {
+        let mut gen = Gen::new(Some(13));
+        let mut hll = HyperLogLogPlusPlus::new(DEFAULT_PRECISION);
+        let mut n = 0;
+
+        for checkpoint in [10_000_000, 100_000_000, 1_000_000_000] {
+            while n < checkpoint {
+                hll.insert_hash(gen.next_u64());
+                n += 1;
+            }
+            assert_within_standard_error(&hll, n);
+        }
+    }
+
+    #[test]
+    fn bias_correction_removes_bias() {
+        // Just above the linear counting threshold, where the raw estimate is biased the most.
+        let precision = 10;
+        let n = 1_500;
+        let runs = 200;
+        let mut gen = Gen::new(Some(14));
+
+        let mut total = 0.0;
+        for _ in 0..runs {
+            let estimate = sketch_of(precision, n, &mut gen).try_estimate().unwrap();
+            assert!(matches!(
+                estimate.diagnostics,
+                Diagnostics::HyperLogLogPlusPlus {
+                    sparse: false,
+                    linear_counting: false,
+                    bias_corrected: true,
+                    ..
+                }
+            ));
+            total += estimate.value;
+        }
+
+        let mean = total / runs as f64;
+        assert!((mean - n as f64).abs() <= 0.01 * n as f64);
+    }
+
+    #[test]
+    fn estimate_unbiased_across_bias_correction_range() {
+        // From above the linear counting threshold up to where bias correction stops at 5m.
+        let runs = 100;
+        let mut gen = Gen::new(Some(15));
+
+        for precision in [8, 10, 12] {
+            let m = 1u64 << precision;
+            // Three standard errors of the mean of `runs` estimates.
+            let tolerance = 3.0 * 1.04 / (m as f64).sqrt() / (runs as f64).sqrt();
+
+            for n in (3..=9).map(|half| half * m / 2) {
+                let total: f64 = (0..runs)
+                    .map(|_| {
+                        sketch_of(precision, n, &mut gen)
+                            .try_estimate()
+                            .unwrap()
+                            .value
+                    })
+                    .sum();
+
+                let mean = total / runs as f64;
+                assert!(
+                    (mean - n as f64).abs() <= tolerance * n as f64,
+                    "precision {precision}: mean estimate {mean} for {n} distinct elements"
+                );
+            }
+        }
+    }
+
+    /// The first points of `rawEstimateData` and `biasData` published with the HyperLogLog++ paper,
+    /// for precisions 4 and 14, as pairs of mean raw estimate and bias. Each was measured at the
+    /// cardinality `raw - bias`.
+    const PUBLISHED: [(u8, [(f64, f64); 10]); 2] = [
+        (
+            4,
+            [
+                (11.0, 10.0),
+                (11.717, 9.717),
+                (12.207, 9.207),
+                (12.7896, 8.7896),
+                (13.2882, 8.2882),
+                (13.8204, 7.8204),
+                (14.3772, 7.3772),
+                (14.9342, 6.9342),
+                (15.5202, 6.5202),
+                (16.161, 6.161),
+            ],
+        ),
+        (
+            14,
+            [
+                (11817.475, 11816.475),
+                (12015.0046, 11605.0046),
+                (12215.3792, 11395.3792),
+                (12417.7504, 11188.7504),
+                (12623.1814, 10984.1814),
+                (12830.0086, 10782.0086),
+                (13040.0072, 10582.0072),
+                (13252.503, 10384.503),
+                (13466.178, 10189.178),
+                (13683.2738, 9996.2738),
+            ],
+        ),
+    ];
+
+    #[test]
+    fn bias_tables_match_published_data() {
+        for (precision, points) in PUBLISHED {
+            let row = usize::from(precision - MIN_PRECISION);
+            // The cardinalities the generated tables were sampled at, see
+            // examples/hll_bias_tables.rs.
+            let max = 5 * (1usize << precision);
+            let cardinalities: Vec<f64> = (0..=100).map(|i| (i * max / 100) as f64).collect();
+
+            for (raw, bias) in points {
+                let n = raw - bias;
+                let i = cardinalities.partition_point(|&c| c <= n) - 1;
+                let t = (n - cardinalities[i]) / (cardinalities[i + 1] - cardinalities[i]);
+                let generated = BIAS[row][i] + t * (BIAS[row][i + 1] - BIAS[row][i]);
+                // Both are means over a few hundred runs, so allow for their sampling noise.
+                assert!(
+                    (generated - bias).abs() <= 0.25 + 1e-3 * bias,
+                    "precision {precision}, cardinality {n}: published bias {bias}, generated {generated}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn converts_to_dense_registers_of_plain_hyperloglog() {
+        let mut gen = Gen::new(Some(15));
+        let hashes: Vec<u64> = (0..2_000).map(|_| gen.next_u64()).collect();
+        let mut hll = HyperLogLogPlusPlus::new(10);
+        let mut plain = HyperLogLog::new(10);
+
+        for (i, &hash) in hashes.iter().enumerate() {
+            hll.insert_hash(hash);
+            plain.insert_hash(hash);
+
+            if i == 100 {
+                assert!(hll.is_sparse());
+                assert_eq!(hll.dense_registers(), plain.registers);
+            }
+        }
+
+        assert!(!hll.is_sparse());
+        assert_eq!(hll.dense_registers(), plain.registers);
+    }
+
+    #[test]
+    fn merge_matches_union() {
+        let union: HyperLogLogPlusPlus = (0..100_000).collect();
+
+        let mut left: HyperLogLogPlusPlus = (0..60_000).collect();
+        left.merge(&(40_000..100_000).collect()).unwrap();
+        assert_eq!(left.dense_registers(), union.dense_registers());
+
+        let mut sparse: HyperLogLogPlusPlus = (0..100).collect();
+        sparse.merge(&(50..150).collect()).unwrap();
+        assert!(sparse.is_sparse());
+        assert_eq!(sparse.estimate(), 150);
+
+        sparse.merge(&union).unwrap();
+        assert!(!sparse.is_sparse());
+        assert_eq!(sparse.dense_registers(), union.dense_registers());
+
+        assert_eq!(
+            sparse.merge(&HyperLogLogPlusPlus::new(10)),
+            Err(DistinctError::PrecisionMismatch(14, 10))
+        );
+    }
+
+    quickcheck! {
+        fn qc_prop_bytes_round_trip(stream: Vec<u32>, precision: u8) -> bool {
+            let mut hll = HyperLogLogPlusPlus::new(MIN_PRECISION + precision % 8);
+            hll.extend(stream);
+
+            let bytes = hll.to_bytes();
+            HyperLogLogPlusPlus::from_bytes(&bytes).is_ok_and(|restored| {
+                restored.is_sparse() == hll.is_sparse()
+                    && restored.dense_registers() == hll.dense_registers()
+                    && restored.try_estimate() == hll.try_estimate()
+                    && restored.to_bytes() == bytes
+            })
+        }
+    }
+}
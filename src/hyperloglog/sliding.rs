@@ -0,0 +1,240 @@
+//! A HyperLogLog over a sliding window of timestamped elements.
+use std::{hash::Hash, mem};
+
+use super::{split_hash, HyperLogLog};
+use crate::{hash::hash64, DistinctCounter, DistinctError, Estimate};
+
+/// A [Sliding HyperLogLog](https://hal.science/hal-00465313), which estimates the number of
+/// distinct elements inserted within any window of time ending now, up to a maximum width.
+///
+/// Where a [`HyperLogLog`] register keeps the largest rank observed, a sliding register keeps every
+/// rank that may still become the largest of some window: the list of `(timestamp, rank)` pairs
+/// not dominated by a pair both later and larger. A window's register value is then the rank of
+/// the first pair inside the window, and its estimate is that of a [`HyperLogLog`] fed exactly the
+/// elements stamped within it. The lists hold about `ln(n)` pairs each for `n` elements in the
+/// window, and pairs older than the maximum width are dropped as registers are updated.
+///
+/// Timestamps are `u64` in whatever unit suits the caller, such as seconds or milliseconds. Using
+/// the position of each element in the stream instead gives a count-based window, over the last
+/// `width` elements. Elements may arrive out of order. Since a pair is only dropped once a later
+/// one dominates it, windows are meant to end at the current time: a window ending at `now` holds
+/// every element stamped after `now - width`, including any stamped after `now`.
+///
+/// # Examples
+/// ```rust
+/// use distinction::SlidingHyperLogLog;
+/// // Up to an hour of history, at second resolution.
+/// let mut hll = SlidingHyperLogLog::new(14, 3600);
+/// for second in 0..3600 {
+///     // A new user every second, along with one of 10 regulars.
+///     hll.insert(&format!("user-{}", second), second);
+///     hll.insert(&format!("regular-{}", second % 10), second);
+/// }
+/// assert_eq!(hll.estimate_window(3599, 60), 70);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SlidingHyperLogLog {
+    precision: u8,
+    max_width: u64,
+    /// For every register, the pairs of timestamp and rank that may still be the largest rank of a
+    /// window, by increasing timestamp and decreasing rank.
+    registers: Vec<Vec<(u64, u8)>>,
+    latest: u64,
+    processed: usize,
+}
+
+impl SlidingHyperLogLog {
+    /// Creates an empty sketch with `2^precision` registers, which answers queries over windows of
+    /// up to `max_width`.
+    ///
+    /// # Panics
+    /// Panics if `precision` is outside of [`MIN_PRECISION`](super::MIN_PRECISION)`..=`
+    /// [`MAX_PRECISION`](super::MAX_PRECISION).
+    pub fn new(precision: u8, max_width: u64) -> Self {
+        Self::try_new(precision, max_width).expect("invalid HyperLogLog precision")
+    }
+
+    /// Fallible version of [`SlidingHyperLogLog::new`].
+    pub fn try_new(precision: u8, max_width: u64) -> Result<Self, DistinctError> {
+        HyperLogLog::try_new(precision)?;
+
+        Ok(Self {
+            precision,
+            max_width,
+            registers: vec![Vec::new(); 1 << precision],
+            latest: 0,
+            processed: 0,
+        })
+    }
+
+    /// Returns the precision the sketch was created with.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// Returns the widest window the sketch answers queries over.
+    pub fn max_width(&self) -> u64 {
+        self.max_width
+    }
+
+    /// Feeds a single element of the stream, stamped with `ts`, into the sketch.
+    pub fn insert<Q>(&mut self, item: &Q, ts: u64)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item), ts);
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`], stamped with `ts`.
+    pub fn insert_hash(&mut self, hash: u64, ts: u64) {
+        self.processed += 1;
+        self.latest = self.latest.max(ts);
+        let (latest, max_width) = (self.latest, self.max_width);
+        let expired = |t: u64| latest.saturating_sub(t) >= max_width;
+
+        let (index, rank) = split_hash(hash, self.precision);
+        let pairs = &mut self.registers[index];
+        let live = pairs.partition_point(|&(t, _)| expired(t));
+        pairs.drain(..live);
+        if expired(ts) {
+            return;
+        }
+
+        // The first pair stamped no earlier than `ts` has the largest rank of those, and dominates
+        // the new pair if that rank is at least as large.
+        let at = pairs.partition_point(|&(t, _)| t < ts);
+        let end = match pairs.get(at) {
+            Some(&(_, r)) if r >= rank => return,
+            // A pair stamped at the same time with a smaller rank is dominated by the new one.
+            Some(&(t, _)) if t == ts => at + 1,
+            _ => at,
+        };
+        // So are the earlier pairs with a smaller rank, which end the ones before `at`.
+        let start = pairs[..at].partition_point(|&(_, r)| r > rank);
+        pairs.splice(start..end, [(ts, rank)]);
+    }
+
+    /// Returns the sketch of the elements stamped after `now - width`, which is a plain
+    /// [`HyperLogLog`] that can be merged with others or serialized. Windows wider than
+    /// [`max_width`](SlidingHyperLogLog::max_width) are narrowed to it, since older elements may
+    /// already have been dropped.
+    ///
+    /// The returned sketch counts every element processed so far as processed, in the window or
+    /// not.
+    pub fn window(&self, now: u64, width: u64) -> HyperLogLog {
+        let width = width.min(self.max_width);
+        let registers = self
+            .registers
+            .iter()
+            .map(|pairs| {
+                let first = pairs.partition_point(|&(t, _)| now.saturating_sub(t) >= width);
+                pairs.get(first).map_or(0, |&(_, rank)| rank)
+            })
+            .collect();
+
+        HyperLogLog {
+            precision: self.precision,
+            registers,
+            processed: self.processed,
+        }
+    }
+
+    /// Returns the estimate of the number of distinct elements stamped after `now - width`.
+    pub fn estimate_window(&self, now: u64, width: u64) -> usize {
+        self.window(now, width).estimate()
+    }
+
+    /// Returns the estimate of the number of distinct elements stamped after `now - width`. See
+    /// [`SlidingHyperLogLog::window`] and [`HyperLogLog::try_estimate`].
+    pub fn try_estimate_window(&self, now: u64, width: u64) -> Result<Estimate, DistinctError> {
+        self.window(now, width).try_estimate()
+    }
+}
+
+/// Elements are fed along with their timestamp, and estimates are over the widest window ending
+/// at the latest timestamp seen.
+impl<Q> DistinctCounter<(Q, u64)> for SlidingHyperLogLog
+where
+    Q: Hash,
+{
+    fn insert(&mut self, (item, ts): (Q, u64)) {
+        self.insert(&item, ts);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate_window(self.latest, self.max_width)
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.registers.capacity() * mem::size_of::<Vec<(u64, u8)>>()
+            + self
+                .registers
+                .iter()
+                .map(|pairs| pairs.capacity() * mem::size_of::<(u64, u8)>())
+                .sum::<usize>()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.precision, self.max_width);
+    }
+
+    fn clear(&mut self) {
+        self.registers.iter_mut().for_each(Vec::clear);
+        self.latest = 0;
+        self.processed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperloglog::DEFAULT_PRECISION;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn windows_match_sketches_of_their_elements() {
+        let mut sliding = SlidingHyperLogLog::new(DEFAULT_PRECISION, 50_000);
+        for ts in 0..200_000u64 {
+            sliding.insert(&ts, ts);
+        }
+
+        for width in [1, 1000, 50_000] {
+            let window: HyperLogLog = (200_000 - width..200_000).collect();
+            assert_eq!(sliding.window(199_999, width).registers, window.registers);
+        }
+        // Wider windows are narrowed to the maximum width.
+        assert_eq!(
+            sliding.window(199_999, 80_000).registers,
+            sliding.window(199_999, 50_000).registers
+        );
+        assert_eq!(sliding.estimate_window(199_999, 0), 0);
+
+        let estimate = sliding.try_estimate_window(199_999, 10_000).unwrap();
+        assert!(
+            estimate.lower <= 10_000.0 && 10_000.0 <= estimate.upper,
+            "{}",
+            estimate
+        );
+        assert_eq!(estimate.processed, 200_000);
+
+        // Expired pairs are dropped, leaving a few per register.
+        let pairs: usize = sliding.registers.iter().map(Vec::len).sum();
+        assert!(pairs < 20 << DEFAULT_PRECISION, "{}", pairs);
+    }
+
+    quickcheck! {
+        fn qc_prop_out_of_order_windows(stream: Vec<(u16, u8)>, now: u8, width: u8) -> bool {
+            let mut sliding = SlidingHyperLogLog::new(4, u64::MAX);
+            let mut window = HyperLogLog::new(4);
+            for &(item, ts) in &stream {
+                sliding.insert(&item, ts.into());
+                if now.saturating_sub(ts) < width {
+                    window.insert(&item);
+                }
+            }
+            sliding.window(now.into(), width.into()).registers == window.registers
+        }
+    }
+}
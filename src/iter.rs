@@ -0,0 +1,80 @@
+//! Estimating the number of distinct elements directly on iterator chains.
+use std::hash::Hash;
+
+use rand::RngCore;
+
+use crate::{seeded, CvmEstimator, DistinctError, Estimate, HashSample};
+
+/// Extension trait adding [`approx_distinct`](ApproxDistinct::approx_distinct) to every iterator
+/// over hashable elements.
+///
+/// The sample is hashed under fixed, public keys, see [`HashSample`] for what that means for
+/// untrusted input.
+///
+/// # Examples
+/// ```rust
+/// use distinction::ApproxDistinct;
+/// let estimate = "the quick brown fox jumps over the lazy dog"
+///     .split(' ')
+///     .approx_distinct(0.1, 0.005)
+///     .unwrap();
+/// assert_eq!(estimate.count(), 8);
+/// ```
+pub trait ApproxDistinct: Iterator {
+    /// Consumes the iterator and estimates the number of distinct elements it yielded with a
+    /// hash-indexed [`CvmEstimator`]. The threshold is sized from the iterator's length when it is
+    /// known exactly, otherwise the estimator runs in [unbounded](CvmEstimator::unbounded) mode.
+    fn approx_distinct(self, eps: f64, delta: f64) -> Result<Estimate, DistinctError>;
+
+    /// Same as [`approx_distinct`](ApproxDistinct::approx_distinct), but the sampling is driven by
+    /// the caller's generator.
+    fn approx_distinct_with_rng<R>(
+        self,
+        eps: f64,
+        delta: f64,
+        rng: &mut R,
+    ) -> Result<Estimate, DistinctError>
+    where
+        R: RngCore + ?Sized;
+}
+
+impl<I> ApproxDistinct for I
+where
+    I: Iterator,
+    I::Item: Hash + Eq,
+{
+    fn approx_distinct(self, eps: f64, delta: f64) -> Result<Estimate, DistinctError> {
+        let (gen, seed) = seeded(None);
+        approx_distinct_seeded(self, eps, delta, gen, seed)
+    }
+
+    fn approx_distinct_with_rng<R>(
+        self,
+        eps: f64,
+        delta: f64,
+        rng: &mut R,
+    ) -> Result<Estimate, DistinctError>
+    where
+        R: RngCore + ?Sized,
+    {
+        approx_distinct_seeded(self, eps, delta, rng, None)
+    }
+}
+
+fn approx_distinct_seeded<I, R>(
+    iter: I,
+    eps: f64,
+    delta: f64,
+    rng: R,
+    seed: Option<u64>,
+) -> Result<Estimate, DistinctError>
+where
+    I: Iterator,
+    I::Item: Hash + Eq,
+    R: RngCore,
+{
+    let mut estimator: CvmEstimator<I::Item, HashSample<I::Item>, R> =
+        CvmEstimator::for_size_hint(iter.size_hint(), eps, delta, rng, seed)?;
+    estimator.extend(iter);
+    estimator.try_estimate()
+}
@@ -0,0 +1,902 @@
+//! Counting the distinct values of fields of JSON Lines inputs.
+//!
+//! A [`JsonlCounter`] reads one JSON document per line from any [`BufRead`], extracts the values
+//! at the given [`Path`]s, such as `.user.id`, and feeds each to a counter of its own, so that all
+//! the fields are counted in a single pass. Documents are scanned in place rather than parsed into
+//! a tree: only the values on the paths are looked at, and the rest of each line is only checked
+//! to be well formed.
+//!
+//! Values are counted by an encoding which tags them with their JSON type: strings by their
+//! contents with escapes resolved, numbers by their text, and arrays and objects by their elements,
+//! with whitespace dropped and the members of objects sorted by name. The string `"1"` and the
+//! number `1` are therefore different values, as are `null` and `"null"`, while
+//! `{"a": 1, "b": 2}` and `{"b":2,"a":1}` are the same. Numbers are not normalized, so `1` and
+//! `1.0` are different values too.
+//!
+//! # Examples
+//! ```rust
+//! use distinction::{
+//!     jsonl::{Arrays, JsonlCounter, JsonlOptions},
+//!     HyperLogLog,
+//! };
+//! let input = r#"
+//! {"user": {"id": 1}, "tags": ["a", "b"]}
+//! {"user": {"id": 1}, "tags": ["b"]}
+//! {"user": {"id": "1"}, "tags": []}
+//! {"tags": ["c", "a"]}
+//! "#;
+//! let options = JsonlOptions {
+//!     arrays: Arrays::Elements,
+//!     ..JsonlOptions::default()
+//! };
+//! let paths = [".user.id".parse().unwrap(), ".tags".parse().unwrap()];
+//! let mut counter = JsonlCounter::new(options, paths, || HyperLogLog::new(12));
+//! counter.read(input.as_bytes()).unwrap();
+//!
+//! let report = counter.finish().report().unwrap();
+//! assert_eq!(report.rows, 4);
+//! assert_eq!(report.columns[0].name, ".user.id");
+//! assert_eq!(report.columns[0].estimate.count(), 2);
+//! assert_eq!(report.columns[1].name, ".tags");
+//! assert_eq!(report.columns[1].estimate.count(), 3);
+//! ```
+use std::{borrow::Cow, fmt, io, io::BufRead, str, str::FromStr};
+
+use crate::{report::ColumnCounters, DistinctCounter};
+
+/// Documents nested deeper than this are rejected rather than risking the stack.
+const MAX_DEPTH: usize = 128;
+
+/// What [`Missing::Null`] counts for a line without a value at a path. Every value is encoded with
+/// at least its tag, so this is distinct from all of them.
+const MISSING: &[u8] = &[];
+
+/// What to do with lines which have no value at a path.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Missing {
+    /// Count nothing for the line.
+    #[default]
+    Skip,
+    /// Count the line as having a value of its own, which is distinct from every JSON value,
+    /// `null` included.
+    Null,
+    /// Stop with [`JsonlError::MissingField`].
+    Error,
+}
+
+/// How arrays met on a path are counted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Arrays {
+    /// An array at the end of a path is a single value, and an array in the middle of one has no
+    /// fields.
+    #[default]
+    Whole,
+    /// Every element of an array is counted, and a field of an array is the field of each of its
+    /// elements, so `.items.id` counts the ids of all the items.
+    Elements,
+}
+
+/// How the lines of a [`JsonlCounter`] are turned into values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonlOptions {
+    /// What to do with lines which have no value at a path, [`Missing::Skip`] by default.
+    pub missing: Missing,
+    /// How arrays are counted, [`Arrays::Whole`] by default.
+    pub arrays: Arrays,
+}
+
+/// A step of a [`Path`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// The location of a value within a JSON document.
+///
+/// Paths are written as in `jq`: `.` is the whole document, `.user.id` the field `id` of the
+/// field `user`, `.items[0]` the first element of `items`, and `."user name"` a field whose name
+/// is not a plain identifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path(Vec<Segment>);
+
+impl FromStr for Path {
+    type Err = JsonlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || JsonlError::InvalidPath(s.to_owned());
+        if s == "." {
+            return Ok(Self(Vec::new()));
+        }
+
+        let mut segments = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            if let Some(quoted) = rest.strip_prefix(".\"") {
+                let (key, after) = quoted.split_once('"').ok_or_else(invalid)?;
+                segments.push(Segment::Key(key.to_owned()));
+                rest = after;
+            } else if let Some(key) = rest.strip_prefix('.') {
+                let end = key.find(['.', '[']).unwrap_or(key.len());
+                if end == 0 {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Key(key[..end].to_owned()));
+                rest = &key[end..];
+            } else if let Some(index) = rest.strip_prefix('[') {
+                let (index, after) = index.split_once(']').ok_or_else(invalid)?;
+                segments.push(Segment::Index(index.parse().map_err(|_| invalid())?));
+                rest = after;
+            } else {
+                return Err(invalid());
+            }
+        }
+        if segments.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self(segments))
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str(".");
+        }
+        for segment in &self.0 {
+            match segment {
+                Segment::Key(key)
+                    if !key.is_empty() && key.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+                {
+                    write!(f, ".{}", key)?
+                }
+                Segment::Key(key) => write!(f, ".\"{}\"", key)?,
+                Segment::Index(index) => write!(f, "[{}]", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The reasons JSON Lines input can fail to be counted.
+#[derive(Debug)]
+pub enum JsonlError {
+    /// Reading the input failed.
+    Io(io::Error),
+    /// A path could not be parsed.
+    InvalidPath(String),
+    /// The line with this number is not a well formed JSON document.
+    InvalidJson(usize),
+    /// The line has no value at the path, and [`Missing::Error`] was asked for.
+    MissingField {
+        /// The number of the line, starting from 1.
+        line: usize,
+        /// The path, as displayed.
+        path: String,
+    },
+}
+
+impl fmt::Display for JsonlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => error.fmt(f),
+            Self::InvalidPath(path) => write!(f, "invalid path `{}`", path),
+            Self::InvalidJson(line) => write!(f, "invalid JSON on line {}", line),
+            Self::MissingField { line, path } => {
+                write!(f, "no value at `{}` on line {}", path, line)
+            }
+        }
+    }
+}
+
+impl std::error::Error for JsonlError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for JsonlError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Counts the distinct values at paths of JSON Lines inputs, with a counter per path.
+///
+/// Every input given to [`read`](JsonlCounter::read) is counted as part of the same stream. Blank
+/// lines are skipped, and a line which is not a well formed document stops the count with
+/// [`JsonlError::InvalidJson`].
+#[derive(Debug)]
+pub struct JsonlCounter<C> {
+    options: JsonlOptions,
+    paths: Vec<Path>,
+    counters: ColumnCounters<C>,
+    line: Vec<u8>,
+    line_number: usize,
+    /// Whether each path had a value on the current line.
+    found: Vec<bool>,
+}
+
+impl<C> JsonlCounter<C>
+where
+    C: for<'a> DistinctCounter<&'a [u8]>,
+{
+    /// Creates a counter of the values at `paths`, each counted by a counter created by
+    /// `new_counter`.
+    pub fn new<P, F>(options: JsonlOptions, paths: P, mut new_counter: F) -> Self
+    where
+        P: IntoIterator<Item = Path>,
+        F: FnMut() -> C,
+    {
+        let paths: Vec<Path> = paths.into_iter().collect();
+        let columns = paths
+            .iter()
+            .map(|path| (path.to_string(), new_counter()))
+            .collect();
+
+        Self {
+            options,
+            paths,
+            counters: ColumnCounters { rows: 0, columns },
+            line: Vec::new(),
+            line_number: 0,
+            found: Vec::new(),
+        }
+    }
+
+    /// Counts the lines of `reader`.
+    pub fn read<R: BufRead>(&mut self, mut reader: R) -> Result<(), JsonlError> {
+        let Self {
+            options,
+            paths,
+            counters,
+            line,
+            line_number,
+            found,
+        } = self;
+        let elements = options.arrays == Arrays::Elements;
+        let selected: Vec<(usize, &[Segment])> =
+            paths.iter().map(|path| &path.0[..]).enumerate().collect();
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', line)? == 0 {
+                return Ok(());
+            }
+            *line_number += 1;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            counters.rows += 1;
+
+            // Every path is selected in the same scan of the line.
+            found.clear();
+            found.resize(paths.len(), false);
+            let mut scanner = Scanner::new(line);
+            scanner
+                .select(&selected, elements, found, &mut |index, value| {
+                    counters.columns[index].1.insert(value)
+                })
+                .and_then(|()| scanner.end())
+                .map_err(|Invalid| JsonlError::InvalidJson(*line_number))?;
+
+            for ((path, (_, counter)), _) in paths
+                .iter()
+                .zip(&mut counters.columns)
+                .zip(found.iter())
+                .filter(|(_, &found)| !found)
+            {
+                match options.missing {
+                    Missing::Skip => {}
+                    Missing::Null => counter.insert(MISSING),
+                    Missing::Error => {
+                        return Err(JsonlError::MissingField {
+                            line: *line_number,
+                            path: path.to_string(),
+                        })
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the counter of every path.
+    pub fn finish(self) -> ColumnCounters<C> {
+        self.counters
+    }
+}
+
+/// The document being scanned is not well formed.
+#[derive(Debug)]
+struct Invalid;
+
+/// Walks a JSON document in place.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    /// Calls `emit` with the index of every path of `paths` which leads to a value within the value
+    /// at the current position, along with the encoding of that value, and sets `found` for those
+    /// paths. Each path is given as the segments left to follow from the current position. With
+    /// `elements`, arrays are looked into rather than being values.
+    fn select<'p>(
+        &mut self,
+        paths: &[(usize, &'p [Segment])],
+        elements: bool,
+        found: &mut [bool],
+        emit: &mut dyn FnMut(usize, &[u8]),
+    ) -> Result<(), Invalid> {
+        self.skip_whitespace();
+        if paths.is_empty() {
+            return self.skip_value();
+        }
+        if elements && self.peek() == Some(b'[') {
+            // An empty array at the end of a path is present, it just has no values.
+            for &(index, path) in paths {
+                found[index] |= path.is_empty();
+            }
+            return self.array(|scanner, i| {
+                let inner: Vec<(usize, &'p [Segment])> = paths
+                    .iter()
+                    .filter_map(|&(index, path)| match path.first() {
+                        Some(Segment::Index(at)) => (*at == i).then(|| (index, &path[1..])),
+                        _ => Some((index, path)),
+                    })
+                    .collect();
+                scanner.select(&inner, elements, found, emit)
+            });
+        }
+
+        let start = self.pos;
+        if paths.iter().any(|(_, path)| path.is_empty()) {
+            let mut value = Vec::new();
+            self.canonical(&mut value)?;
+            for &(index, _) in paths.iter().filter(|(_, path)| path.is_empty()) {
+                found[index] = true;
+                emit(index, &value);
+            }
+            if paths.iter().all(|(_, path)| path.is_empty()) {
+                return Ok(());
+            }
+            // Other paths lead into the value, so it is scanned again for them.
+            self.pos = start;
+        }
+        match self.peek() {
+            Some(b'{') => self.object(|scanner, name| {
+                let inner: Vec<(usize, &'p [Segment])> = paths
+                    .iter()
+                    .filter_map(|&(index, path)| match path.first() {
+                        Some(Segment::Key(key)) if *key.as_bytes() == *name => {
+                            Some((index, &path[1..]))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                scanner.select(&inner, elements, found, emit)
+            }),
+            Some(b'[') => self.array(|scanner, i| {
+                let inner: Vec<(usize, &'p [Segment])> = paths
+                    .iter()
+                    .filter_map(|&(index, path)| match path.first() {
+                        Some(Segment::Index(at)) if *at == i => Some((index, &path[1..])),
+                        _ => None,
+                    })
+                    .collect();
+                scanner.select(&inner, elements, found, emit)
+            }),
+            _ => self.skip_value(),
+        }
+    }
+
+    /// Scans the value at the current position, appending its encoding to `out`: a tag for its
+    /// type, followed by the length and bytes of the contents of strings and the text of numbers,
+    /// or by the encoded elements of arrays and members of objects and a closing tag. Members are
+    /// sorted, so that objects which only differ in the order of their members encode the same.
+    fn canonical(&mut self, out: &mut Vec<u8>) -> Result<(), Invalid> {
+        fn tagged(out: &mut Vec<u8>, tag: u8, bytes: &[u8]) {
+            out.push(tag);
+            out.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+            out.extend_from_slice(bytes);
+        }
+
+        self.skip_whitespace();
+        match self.peek().ok_or(Invalid)? {
+            b'{' => {
+                let mut members = Vec::new();
+                self.object(|scanner, name| {
+                    let mut member = Vec::new();
+                    tagged(&mut member, b'"', &name);
+                    scanner.canonical(&mut member)?;
+                    members.push(member);
+                    Ok(())
+                })?;
+                members.sort_unstable();
+                out.push(b'{');
+                members
+                    .iter()
+                    .for_each(|member| out.extend_from_slice(member));
+                out.push(b'}');
+            }
+            b'[' => {
+                out.push(b'[');
+                self.array(|scanner, _| scanner.canonical(out))?;
+                out.push(b']');
+            }
+            b'"' => {
+                let contents = self.string()?;
+                tagged(out, b'"', &contents);
+            }
+            b't' => {
+                self.literal(b"true")?;
+                out.push(b't');
+            }
+            b'f' => {
+                self.literal(b"false")?;
+                out.push(b'f');
+            }
+            b'n' => {
+                self.literal(b"null")?;
+                out.push(b'n');
+            }
+            _ => {
+                let start = self.pos;
+                self.number()?;
+                tagged(out, b'#', &self.bytes[start..self.pos]);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that nothing but whitespace follows the document.
+    fn end(&mut self) -> Result<(), Invalid> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(Invalid),
+        }
+    }
+
+    fn skip_value(&mut self) -> Result<(), Invalid> {
+        self.skip_whitespace();
+        match self.peek().ok_or(Invalid)? {
+            b'{' => self.object(|scanner, _| scanner.skip_value()),
+            b'[' => self.array(|scanner, _| scanner.skip_value()),
+            b'"' => self.string().map(drop),
+            b't' => self.literal(b"true"),
+            b'f' => self.literal(b"false"),
+            b'n' => self.literal(b"null"),
+            b'-' | b'0'..=b'9' => self.number(),
+            _ => Err(Invalid),
+        }
+    }
+
+    /// Scans an object, calling `member` with the scanner positioned on the value of every member
+    /// and the member's name.
+    fn object(
+        &mut self,
+        mut member: impl FnMut(&mut Self, Cow<'a, [u8]>) -> Result<(), Invalid>,
+    ) -> Result<(), Invalid> {
+        self.enter(b'{')?;
+        if self.eat(b'}') {
+            self.depth -= 1;
+            return Ok(());
+        }
+        loop {
+            self.skip_whitespace();
+            let name = self.string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            member(self, name)?;
+            self.skip_whitespace();
+            if self.eat(b'}') {
+                self.depth -= 1;
+                return Ok(());
+            }
+            self.expect(b',')?;
+        }
+    }
+
+    /// Scans an array, calling `element` with the scanner positioned on every element and its
+    /// index.
+    fn array(
+        &mut self,
+        mut element: impl FnMut(&mut Self, usize) -> Result<(), Invalid>,
+    ) -> Result<(), Invalid> {
+        self.enter(b'[')?;
+        if self.eat(b']') {
+            self.depth -= 1;
+            return Ok(());
+        }
+        for index in 0.. {
+            element(self, index)?;
+            self.skip_whitespace();
+            if self.eat(b']') {
+                break;
+            }
+            self.expect(b',')?;
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Opens an object or an array.
+    fn enter(&mut self, open: u8) -> Result<(), Invalid> {
+        self.expect(open)?;
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(Invalid);
+        }
+        self.skip_whitespace();
+        Ok(())
+    }
+
+    /// Scans a string, returning its contents with escapes resolved. Strings without escapes are
+    /// borrowed from the document. Strings holding control characters or invalid UTF-8 are
+    /// rejected.
+    fn string(&mut self) -> Result<Cow<'a, [u8]>, Invalid> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        loop {
+            match self.next().ok_or(Invalid)? {
+                b'"' => {
+                    let contents = &self.bytes[start..self.pos - 1];
+                    str::from_utf8(contents).map_err(|_| Invalid)?;
+                    return Ok(Cow::Borrowed(contents));
+                }
+                b'\\' => break,
+                0..=0x1f => return Err(Invalid),
+                _ => {}
+            }
+        }
+
+        let mut contents = self.bytes[start..self.pos - 1].to_vec();
+        self.pos -= 1;
+        loop {
+            match self.next().ok_or(Invalid)? {
+                b'"' => {
+                    str::from_utf8(&contents).map_err(|_| Invalid)?;
+                    return Ok(Cow::Owned(contents));
+                }
+                b'\\' => {
+                    let unescaped = match self.next().ok_or(Invalid)? {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(Invalid),
+                    };
+                    let mut utf8 = [0; 4];
+                    contents.extend_from_slice(unescaped.encode_utf8(&mut utf8).as_bytes());
+                }
+                0..=0x1f => return Err(Invalid),
+                byte => contents.push(byte),
+            }
+        }
+    }
+
+    /// Reads the code point of a `\u` escape, the `\u` being already read, combining surrogate
+    /// pairs.
+    fn unicode_escape(&mut self) -> Result<char, Invalid> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            self.expect(b'\\')?;
+            self.expect(b'u')?;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(Invalid);
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or(Invalid)
+    }
+
+    fn hex4(&mut self) -> Result<u32, Invalid> {
+        let digits = self.bytes.get(self.pos..self.pos + 4).ok_or(Invalid)?;
+        let digits = str::from_utf8(digits).map_err(|_| Invalid)?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| Invalid)?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    /// Scans a number: an optional minus sign, an integer part without leading zeros, then an
+    /// optional fraction and exponent.
+    fn number(&mut self) -> Result<(), Invalid> {
+        self.eat(b'-');
+        match self.peek() {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => self.digits()?,
+            _ => return Err(Invalid),
+        }
+        if self.eat(b'.') {
+            self.digits()?;
+        }
+        if self.eat(b'e') || self.eat(b'E') {
+            if !self.eat(b'+') {
+                self.eat(b'-');
+            }
+            self.digits()?;
+        }
+        Ok(())
+    }
+
+    /// Scans one or more digits.
+    fn digits(&mut self) -> Result<(), Invalid> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        match self.pos > start {
+            true => Ok(()),
+            false => Err(Invalid),
+        }
+    }
+
+    fn literal(&mut self, literal: &[u8]) -> Result<(), Invalid> {
+        match self.bytes[self.pos..].starts_with(literal) {
+            true => {
+                self.pos += literal.len();
+                Ok(())
+            }
+            false => Err(Invalid),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+        self.pos += usize::from(matched);
+        matched
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), Invalid> {
+        match self.eat(byte) {
+            true => Ok(()),
+            false => Err(Invalid),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExactCounter;
+
+    fn values(line: &str, path: &str, elements: bool) -> Option<Vec<Vec<u8>>> {
+        let path: Path = path.parse().unwrap();
+        let mut values = Vec::new();
+        let mut scanner = Scanner::new(line.as_bytes());
+        scanner
+            .select(&[(0, &path.0)], elements, &mut [false], &mut |_, value| {
+                values.push(value.to_vec())
+            })
+            .and_then(|()| scanner.end())
+            .ok()?;
+        Some(values)
+    }
+
+    /// The encoding of the whole of `json`.
+    fn encoded(json: &str) -> Vec<u8> {
+        let mut values = values(json, ".", false).expect("well formed JSON");
+        assert_eq!(values.len(), 1);
+        values.remove(0)
+    }
+
+    #[test]
+    fn parses_and_displays_paths() {
+        for path in [".", ".user.id", ".items[2].name", ".\"user name\"[0]"] {
+            assert_eq!(path.parse::<Path>().unwrap().to_string(), path);
+        }
+        for path in ["", "user", ".a..b", ".a[x]", ".a[1", ".\"a"] {
+            assert!(
+                matches!(path.parse::<Path>(), Err(JsonlError::InvalidPath(p)) if p == path),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn selects_values_on_paths() {
+        let line =
+            r#" {"a": {"b": "x\"\u00e9\ud83d\ude00", "c": [1, 2.5e3, {"d": null}]}, "e": true} "#;
+        assert_eq!(
+            values(line, ".a.b", false).unwrap(),
+            [encoded("\"x\\\"é😀\"")]
+        );
+        assert_eq!(values(line, ".a.c[1]", false).unwrap(), [encoded("2.5e3")]);
+        assert_eq!(
+            values(line, ".a.c", false).unwrap(),
+            [encoded("[1,2.5e3,{\"d\":null}]")]
+        );
+        assert_eq!(
+            values(line, ".a.c", true).unwrap(),
+            [encoded("1"), encoded("2.5e3"), encoded("{\"d\": null}")]
+        );
+        assert_eq!(values(line, ".a.c.d", true).unwrap(), [encoded("null")]);
+        assert_eq!(
+            values(line, ".a.c.d", false).unwrap(),
+            Vec::<Vec<u8>>::new()
+        );
+        assert_eq!(values(line, ".e.f", false).unwrap(), Vec::<Vec<u8>>::new());
+        assert_eq!(values(line, ".", false).unwrap(), [encoded(line.trim())]);
+
+        for line in [
+            "{\"a\": 1",
+            "{\"a\": tru}",
+            "{\"a\": 1} x",
+            "[1,]",
+            "\"\\ud800\"",
+            &"[".repeat(MAX_DEPTH + 1),
+            // Malformed numbers.
+            "-",
+            "01",
+            "+1",
+            "1.",
+            ".5",
+            "1e",
+            "1e+",
+            "1.2.3",
+            "--1",
+            "0x10",
+            // Control characters and invalid UTF-8 in strings.
+            "\"a\tb\"",
+            "\"\\n\u{1}\"",
+        ] {
+            assert_eq!(values(line, ".a", false), None, "{}", line);
+        }
+        assert_eq!(values("\"\\u00e9\\ta\"", ".", false).unwrap().len(), 1);
+        for number in ["0", "-0", "12", "-1.5", "1e10", "1E-2", "0.5e+3"] {
+            assert_eq!(values(number, ".", false).unwrap().len(), 1, "{}", number);
+        }
+        // Paths leading into each other are selected in the same scan.
+        let paths: Vec<Path> = [".a", ".a.c", ".a.c[2].d", ".e", ".a"]
+            .iter()
+            .map(|path| path.parse().unwrap())
+            .collect();
+        let selected: Vec<(usize, &[Segment])> =
+            paths.iter().map(|path| &path.0[..]).enumerate().collect();
+        let mut found = [false; 5];
+        let mut selections = Vec::new();
+        let mut scanner = Scanner::new(line.as_bytes());
+        scanner
+            .select(&selected, false, &mut found, &mut |index, value| {
+                selections.push((index, value.to_vec()))
+            })
+            .unwrap();
+        selections.sort();
+        let a = values(line, ".a", false).unwrap().remove(0);
+        assert_eq!(
+            selections,
+            [
+                (0, a.clone()),
+                (1, encoded("[1, 2.5e3, {\"d\": null}]")),
+                (2, encoded("null")),
+                (3, encoded("true")),
+                (4, a),
+            ]
+        );
+        assert_eq!(found, [true; 5]);
+
+        let mut invalid = Scanner::new(b"\"\xff\"");
+        assert!(invalid.string().is_err());
+        let mut invalid = Scanner::new(b"\"\\n\xc3\"");
+        assert!(invalid.string().is_err());
+    }
+
+    #[test]
+    fn values_are_told_apart_by_type() {
+        // The same text in values of different types.
+        assert_ne!(encoded("1"), encoded("\"1\""));
+        assert_ne!(encoded("null"), encoded("\"null\""));
+        assert_ne!(encoded("true"), encoded("\"true\""));
+        assert_ne!(encoded("[\"a\",\"b\"]"), encoded("[\"ab\"]"));
+        assert_ne!(encoded("{\"a\":\"b\"}"), encoded("[\"a\",\"b\"]"));
+        assert_ne!(encoded("1"), encoded("1.0"));
+        for json in ["null", "\"\"", "[]", "{}", "0"] {
+            assert_ne!(encoded(json), MISSING, "{}", json);
+        }
+
+        // The same value written differently.
+        assert_eq!(
+            encoded("{\"a\":1,\"b\":[2, {\"c\": 3, \"d\": 4}]}"),
+            encoded(" { \"b\" : [ 2 ,{\"d\":4,\"c\":3} ] , \"a\" : 1 } ")
+        );
+        assert_eq!(encoded("\"\\u0041\\/\""), encoded("\"A/\""));
+    }
+
+    #[test]
+    fn counts_fields_of_lines() {
+        let input = "{\"id\": 1, \"tags\": [\"a\"]}\n\n{\"id\": 2, \"tags\": []}\r\n{\"tags\": [\"b\", \"a\"]}\n";
+        let counts = |options: JsonlOptions| {
+            let paths = [".id".parse().unwrap(), ".tags".parse().unwrap()];
+            let mut counter = JsonlCounter::new(options, paths, ExactCounter::<Vec<u8>>::new);
+            counter.read(input.as_bytes()).map(|()| {
+                let counters = counter.finish();
+                assert_eq!(counters.rows, 3);
+                counters
+                    .columns
+                    .iter()
+                    .map(|(_, counter)| counter.estimate())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(counts(JsonlOptions::default()).unwrap(), [2, 3]);
+        let elements = JsonlOptions {
+            arrays: Arrays::Elements,
+            ..JsonlOptions::default()
+        };
+        assert_eq!(counts(elements).unwrap(), [2, 2]);
+        let null = JsonlOptions {
+            missing: Missing::Null,
+            ..elements
+        };
+        assert_eq!(counts(null).unwrap(), [3, 2]);
+        // Missing values are distinct from `null` and `"null"`.
+        let mut counter = JsonlCounter::new(
+            null,
+            [".a".parse().unwrap(), ".b".parse().unwrap()],
+            ExactCounter::<Vec<u8>>::new,
+        );
+        let input = "{\"a\": null, \"b\": {\"x\": 1, \"y\": 2}}\n{\"a\": \"null\", \"b\": {\"y\": 2, \"x\": 1}}\n{}\n";
+        counter.read(input.as_bytes()).unwrap();
+        let missing: Vec<usize> = counter
+            .finish()
+            .columns
+            .iter()
+            .map(|(_, counter)| counter.estimate())
+            .collect();
+        assert_eq!(missing, [3, 2]);
+        let error = JsonlOptions {
+            missing: Missing::Error,
+            ..JsonlOptions::default()
+        };
+        assert!(matches!(
+            counts(error),
+            Err(JsonlError::MissingField { line: 4, path }) if path == ".id"
+        ));
+
+        let mut counter = JsonlCounter::new(JsonlOptions::default(), [Path(Vec::new())], || {
+            ExactCounter::<Vec<u8>>::new()
+        });
+        assert!(matches!(
+            counter.read("1\n{\n".as_bytes()),
+            Err(JsonlError::InvalidJson(2))
+        ));
+    }
+}
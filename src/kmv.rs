@@ -0,0 +1,409 @@
+//! The K-Minimum-Values sketch of Bar-Yossef et al., also known as a bottom-k sketch.
+use std::{collections::BTreeSet, hash::Hash, mem};
+
+use crate::{
+    codec::{self, DecodeError, Kind},
+    hash::hash64,
+    DistinctCounter, DistinctError, Estimate, Mergeable,
+};
+
+/// Smallest supported number of hashes. A full sketch of `k` hashes has a standard error of
+/// `1 / sqrt(k - 2)`, which is only finite from 3 hashes on.
+pub const MIN_K: usize = 3;
+
+/// Number of hashes kept when a sketch is built without explicit parameters, e.g. through
+/// [`FromIterator`]. Gives a standard error of about 1.6%.
+pub const DEFAULT_K: usize = 4096;
+
+/// A K-Minimum-Values sketch.
+///
+/// Elements are hashed with [`hash64`] and the sketch keeps the `k` smallest distinct hashes it has
+/// seen. If the `k`-th smallest, scaled to the unit interval, is `U_k`, then `(k - 1) / U_k` is an
+/// unbiased estimate of the number of distinct elements, with a relative standard error of about
+/// `1 / sqrt(k - 2)`. Until `k` distinct hashes have been seen the count is exact.
+///
+/// Like the sample set of the [`CvmEstimator`](crate::CvmEstimator), the kept hashes are a uniform
+/// sample of the distinct elements, but it is chosen by the hash rather than by coin flips. Two
+/// sketches therefore sample consistently: the bottom `k` of the union of two streams can be
+/// computed from their sketches, which gives [`merge`](KmvSketch::merge), and the sample tells
+/// which fraction of the union lies in both or just one of the streams, which gives
+/// [`intersection`](KmvSketch::intersection) and [`difference`](KmvSketch::difference).
+///
+/// # Examples
+/// ```rust
+/// use distinction::KmvSketch;
+/// let mut left = KmvSketch::new(4096);
+/// let mut right = KmvSketch::new(4096);
+/// left.extend(0..100_000);
+/// right.extend(50_000..200_000);
+///
+/// let both = left.intersection(&right).value;
+/// assert!((both - 50_000.0).abs() < 5_000.0);
+///
+/// left.merge(&right);
+/// let union = left.try_estimate().unwrap().value;
+/// assert!((union - 200_000.0).abs() < 10_000.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KmvSketch {
+    k: usize,
+    hashes: BTreeSet<u64>,
+    processed: usize,
+}
+
+impl KmvSketch {
+    /// Creates an empty sketch which keeps the `k` smallest hashes.
+    ///
+    /// # Panics
+    /// Panics if `k` is less than [`MIN_K`].
+    pub fn new(k: usize) -> Self {
+        Self::try_new(k).expect("invalid KMV sample size")
+    }
+
+    /// Fallible version of [`KmvSketch::new`].
+    pub fn try_new(k: usize) -> Result<Self, DistinctError> {
+        if k < MIN_K {
+            return Err(DistinctError::InvalidSampleSize(k));
+        }
+
+        Ok(Self {
+            k,
+            hashes: BTreeSet::new(),
+            processed: 0,
+        })
+    }
+
+    /// Returns the number of hashes the sketch keeps.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Returns the sampled hashes in increasing order.
+    pub fn hashes(&self) -> impl Iterator<Item = u64> + '_ {
+        self.hashes.iter().copied()
+    }
+
+    /// Feeds a single element of the stream into the sketch.
+    pub fn insert<Q>(&mut self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item));
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`].
+    pub fn insert_hash(&mut self, hash: u64) {
+        self.processed += 1;
+
+        if self.hashes.len() < self.k {
+            self.hashes.insert(hash);
+        } else if self.hashes.last().is_some_and(|&largest| hash < largest)
+            && self.hashes.insert(hash)
+        {
+            self.hashes.pop_last();
+        }
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far.
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far. A KMV sketch
+    /// always produces an estimate; the `Result` matches [`CvmEstimator::try_estimate`].
+    ///
+    /// [`CvmEstimator::try_estimate`]: crate::CvmEstimator::try_estimate
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        let len = self.hashes.len();
+        let estimate = match self.hashes.last() {
+            Some(&largest) if len == self.k => {
+                let theta = unit(largest);
+                let std_error = 1.0 / ((self.k - 2) as f64).sqrt();
+                Estimate::kmv(
+                    (self.k - 1) as f64 / theta,
+                    std_error,
+                    self.k,
+                    len,
+                    theta,
+                    self.processed,
+                )
+            }
+            _ => Estimate::kmv(len as f64, 0.0, self.k, len, 1.0, self.processed),
+        };
+
+        Ok(estimate)
+    }
+
+    /// Folds `other` into this sketch, so that it estimates the number of distinct elements
+    /// across both streams. The result is exactly the sketch of the concatenated stream. If the two
+    /// sketches keep a different number of hashes, the merged one keeps the smaller number.
+    pub fn merge(&mut self, other: &Self) {
+        self.k = self.k.min(other.k);
+        self.hashes.extend(&other.hashes);
+        while self.hashes.len() > self.k {
+            self.hashes.pop_last();
+        }
+        self.processed += other.processed;
+    }
+
+    /// Estimates the number of distinct elements which appear in both this sketch's stream and
+    /// `other`'s.
+    pub fn intersection(&self, other: &Self) -> Estimate {
+        self.set_operation(other, |left, right| left && right)
+    }
+
+    /// Estimates the number of distinct elements which appear in this sketch's stream but not in
+    /// `other`'s.
+    pub fn difference(&self, other: &Self) -> Estimate {
+        self.set_operation(other, |left, right| left && !right)
+    }
+
+    /// Estimates the Jaccard similarity of the distinct elements of the two streams, the size of
+    /// their intersection over the size of their union.
+    pub fn jaccard(&self, other: &Self) -> f64 {
+        let union = self.union_sample(other);
+        if union.is_empty() {
+            return 0.0;
+        }
+
+        let both = union
+            .iter()
+            .filter(|hash| self.hashes.contains(hash) && other.hashes.contains(hash))
+            .count();
+        both as f64 / union.len() as f64
+    }
+
+    /// Serializes the sketch in the format described in [`codec`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        codec::encode(Kind::Kmv, |writer| {
+            writer.usize(self.k);
+            writer.usize(self.processed);
+            writer.usize(self.hashes.len());
+            for &hash in &self.hashes {
+                writer.u64(hash);
+            }
+        })
+    }
+
+    /// Reads back a sketch written by [`KmvSketch::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        codec::decode(bytes, Kind::Kmv, |reader, _| {
+            let k = reader.usize()?;
+            let processed = reader.usize()?;
+            let hashes = reader.sorted_u64s()?;
+            if k < MIN_K || hashes.len() > k {
+                return Err(DecodeError::Invalid("sample size out of range"));
+            }
+
+            Ok(Self {
+                k,
+                hashes: hashes.into_iter().collect(),
+                processed,
+            })
+        })
+    }
+
+    /// Returns the bottom `k` hashes of the union of both streams, for the smaller of the two
+    /// `k`s. Every hash in it which belongs to either stream is in that stream's sample too.
+    fn union_sample(&self, other: &Self) -> Vec<u64> {
+        self.hashes
+            .union(&other.hashes)
+            .copied()
+            .take(self.k.min(other.k))
+            .collect()
+    }
+
+    /// Estimates the number of distinct elements of the union whose membership in the two streams
+    /// satisfies `keep`, by scaling the union estimate with the fraction of its sample that does.
+    fn set_operation(&self, other: &Self, keep: impl Fn(bool, bool) -> bool) -> Estimate {
+        let k = self.k.min(other.k);
+        let union = self.union_sample(other);
+        let processed = self.processed + other.processed;
+
+        let matching = union
+            .iter()
+            .filter(|hash| keep(self.hashes.contains(hash), other.hashes.contains(hash)))
+            .count();
+
+        match union.last() {
+            Some(&largest) if union.len() == k => {
+                let theta = unit(largest);
+                let value = matching as f64 / k as f64 * (k - 1) as f64 / theta;
+                let std_error = if matching == 0 {
+                    0.0
+                } else {
+                    1.0 / (matching as f64).sqrt()
+                };
+                Estimate::kmv(value, std_error, k, matching, theta, processed)
+            }
+            _ => Estimate::kmv(matching as f64, 0.0, k, matching, 1.0, processed),
+        }
+    }
+}
+
+/// Scales a hash to the unit interval `(0, 1]`.
+fn unit(hash: u64) -> f64 {
+    (hash as f64 + 1.0) / 2f64.powi(64)
+}
+
+impl<Q> DistinctCounter<Q> for KmvSketch
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        self.insert(&item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    /// Approximates the memory held by the tree of hashes as `8` bytes per hash.
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.hashes.len() * mem::size_of::<u64>()
+    }
+
+    /// A [`BTreeSet`] frees its nodes as it empties, so this is the same as
+    /// [`clear`](DistinctCounter::clear).
+    fn reset(&mut self) {
+        DistinctCounter::<Q>::clear(self);
+    }
+
+    fn clear(&mut self) {
+        self.hashes.clear();
+        self.processed = 0;
+    }
+}
+
+impl Mergeable for KmvSketch {
+    /// Never fails: sketches keeping a different number of hashes merge into one keeping the
+    /// smaller number.
+    fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        KmvSketch::merge(self, other);
+        Ok(())
+    }
+}
+
+impl<Q> Extend<Q> for KmvSketch
+where
+    Q: Hash,
+{
+    fn extend<I: IntoIterator<Item = Q>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<Q> FromIterator<Q> for KmvSketch
+where
+    Q: Hash,
+{
+    /// Builds a sketch with [`DEFAULT_K`].
+    fn from_iter<I: IntoIterator<Item = Q>>(iter: I) -> Self {
+        let mut kmv = Self::new(DEFAULT_K);
+        kmv.extend(iter);
+        kmv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Diagnostics;
+    use quickcheck::quickcheck;
+
+    fn assert_close(estimate: Estimate, expected: f64, std_errors: f64) {
+        let Diagnostics::Kmv { sample_len, .. } = estimate.diagnostics else {
+            panic!("not a KMV estimate: {:?}", estimate);
+        };
+        let tolerance = std_errors * expected / (sample_len as f64).sqrt();
+        assert!(
+            (estimate.value - expected).abs() <= tolerance,
+            "estimated {} instead of {}",
+            estimate.value,
+            expected
+        );
+    }
+
+    #[test]
+    fn estimate_within_standard_error() {
+        let small: KmvSketch = (0..100).chain(0..100).collect();
+        let estimate = small.try_estimate().unwrap();
+        assert_eq!(
+            (estimate.value, estimate.lower, estimate.upper),
+            (100.0, 100.0, 100.0)
+        );
+        assert_eq!(estimate.processed, 200);
+
+        for n in [10_000u64, 1_000_000] {
+            let kmv: KmvSketch = (0..n).collect();
+            assert_eq!(kmv.hashes().count(), DEFAULT_K);
+            assert_close(kmv.try_estimate().unwrap(), n as f64, 3.0);
+        }
+
+        assert_eq!(
+            KmvSketch::try_new(1),
+            Err(DistinctError::InvalidSampleSize(1))
+        );
+        assert_eq!(
+            KmvSketch::try_new(2),
+            Err(DistinctError::InvalidSampleSize(2))
+        );
+        // The smallest sketch still has a finite interval once full.
+        let mut kmv = KmvSketch::new(MIN_K);
+        kmv.extend(0..1000);
+        assert_eq!(kmv.hashes().count(), MIN_K);
+        let estimate = kmv.try_estimate().unwrap();
+        assert!(
+            estimate.lower.is_finite() && estimate.upper.is_finite(),
+            "{}",
+            estimate
+        );
+    }
+
+    #[test]
+    fn merge_matches_union() {
+        let mut left: KmvSketch = (0..60_000).collect();
+        let right: KmvSketch = (40_000..100_000).collect();
+        let union: KmvSketch = (0..100_000).collect();
+
+        left.merge(&right);
+        assert!(left.hashes().eq(union.hashes()));
+
+        let mut small = KmvSketch::new(100);
+        small.extend(0..1_000);
+        left.merge(&small);
+        assert_eq!(left.k(), 100);
+        assert!(left.hashes().eq(union.hashes().take(100)));
+    }
+
+    #[test]
+    fn set_operations() {
+        let left: KmvSketch = (0..60_000).collect();
+        let right: KmvSketch = (40_000..100_000).collect();
+
+        assert_close(left.intersection(&right), 20_000.0, 3.0);
+        assert_close(left.difference(&right), 40_000.0, 3.0);
+        assert_close(right.difference(&left), 40_000.0, 3.0);
+        assert!((left.jaccard(&right) - 0.2).abs() <= 0.03);
+
+        let disjoint: KmvSketch = (100_000..200_000).collect();
+        assert_eq!(left.intersection(&disjoint).value, 0.0);
+
+        let small: KmvSketch = (0..60).collect();
+        let other: KmvSketch = (40..100).collect();
+        assert_eq!(small.intersection(&other).value, 20.0);
+        assert_eq!(small.difference(&other).value, 40.0);
+    }
+
+    quickcheck! {
+        fn qc_prop_bytes_round_trip(stream: Vec<u32>, k: u8) -> bool {
+            let mut kmv = KmvSketch::new(MIN_K + k as usize);
+            kmv.extend(stream);
+
+            KmvSketch::from_bytes(&kmv.to_bytes()) == Ok(kmv)
+        }
+    }
+}
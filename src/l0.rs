@@ -0,0 +1,478 @@
+//! An L0 sketch, counting the distinct elements of a stream of insertions and deletions.
+use std::{hash::Hash, mem};
+
+use crate::{hash::hash64, splitmix64, DistinctCounter, DistinctError, Estimate, Mergeable};
+
+/// Number of items each level is sized to recover when a sketch is built without explicit
+/// parameters, e.g. through [`FromIterator`]. Gives a standard error of about 4%.
+pub const DEFAULT_K: usize = 1024;
+
+/// Number of sampling levels. Level `i` holds the items whose level hash has `i` leading zeros,
+/// and every level below.
+const LEVELS: usize = 64;
+
+/// The Mersenne prime `2^61 - 1`, the order of the field the cells count in.
+const P: u64 = (1 << 61) - 1;
+
+/// A sketch of a turnstile stream, where every update inserts or deletes an item with some
+/// multiplicity, which estimates the number of items whose net count is not zero: the L0 norm of
+/// the stream.
+///
+/// The [`CvmEstimator`](crate::CvmEstimator) and the other sketches only ever see insertions, so
+/// they cannot forget an item once it has been retracted. This sketch is linear instead: every
+/// update adds to it, and a deletion cancels out the matching insertion exactly, whatever happened
+/// in between.
+///
+/// Items are subsampled into nested levels, an item reaching level `i` with probability `2^-i`.
+/// Every level is an invertible Bloom lookup table of `2k` cells, each summing the counts of the
+/// items hashed to it, their identifiers weighted by their counts, and a checksum, all modulo the
+/// prime `2^61 - 1`. A cell left with a single item reveals it, and removing that item from its
+/// other cells reveals more, which recovers all the items of a level with high probability as long
+/// as it holds fewer than about `k` of them. The estimate is read at the lowest level recovered in
+/// full: below `k` items that is level 0 and the count is exact, otherwise about `k / 2` to `k`
+/// items are sampled, for a relative standard error of at most about `sqrt(2 / k)`.
+///
+/// Levels are allocated as items reach them, about `log2(n)` of them for `n` items, each taking
+/// `48 k` bytes. Items are identified by their [`hash64`], reduced modulo the prime, so two
+/// different items with the same identifier are counted once.
+///
+/// # Examples
+/// ```rust
+/// use distinction::L0Sketch;
+/// let mut subscribers = L0Sketch::new(1024);
+/// for user in 0..1000 {
+///     subscribers.insert(&user);
+/// }
+/// for user in 0..300 {
+///     subscribers.delete(&user);
+/// }
+/// // Subscribed twice, unsubscribed once.
+/// subscribers.update(&1, 2);
+/// subscribers.delete(&1);
+/// assert_eq!(subscribers.estimate(), 701);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct L0Sketch {
+    k: usize,
+    /// The cells of every level reached so far, `3 * width` each.
+    levels: Vec<Vec<Cell>>,
+    processed: usize,
+}
+
+/// The sums, in the field, of what the updates hashed to a cell added to it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Cell {
+    count: u64,
+    key_sum: u64,
+    check_sum: u64,
+}
+
+impl L0Sketch {
+    /// Creates an empty sketch whose levels are each sized to recover about `k` items.
+    ///
+    /// # Panics
+    /// Panics if `k` is 0.
+    pub fn new(k: usize) -> Self {
+        Self::try_new(k).expect("invalid L0 sample size")
+    }
+
+    /// Fallible version of [`L0Sketch::new`].
+    pub fn try_new(k: usize) -> Result<Self, DistinctError> {
+        if k == 0 {
+            return Err(DistinctError::InvalidSampleSize(k));
+        }
+
+        Ok(Self {
+            k,
+            levels: Vec::new(),
+            processed: 0,
+        })
+    }
+
+    /// Returns the number of items each level is sized to recover.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Inserts a single occurrence of `item`.
+    pub fn insert<Q>(&mut self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.update(item, 1);
+    }
+
+    /// Deletes a single occurrence of `item`. Deleting an item which was never inserted leaves it
+    /// with a negative net count, which counts as non-zero.
+    pub fn delete<Q>(&mut self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.update(item, -1);
+    }
+
+    /// Adds `multiplicity` occurrences of `item`, deleting them if it is negative.
+    pub fn update<Q>(&mut self, item: &Q, multiplicity: i64)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.update_hash(hash64(item), multiplicity);
+    }
+
+    /// Adds `multiplicity` occurrences of an item which has already been hashed with [`hash64`].
+    pub fn update_hash(&mut self, hash: u64, multiplicity: i64) {
+        self.processed += 1;
+
+        let key = hash % P;
+        let multiplicity = i128::from(multiplicity).rem_euclid(P.into()) as u64;
+        let hashes = KeyHashes::new(key);
+        let width = self.width();
+        if self.levels.len() <= hashes.level {
+            self.levels
+                .resize_with(hashes.level + 1, || vec![Cell::default(); 3 * width]);
+        }
+        for cells in &mut self.levels[..=hashes.level] {
+            for index in hashes.cells(width) {
+                cells[index].add(key, hashes.check, multiplicity);
+            }
+        }
+    }
+
+    /// Returns the current estimate of the number of items with a non-zero net count.
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
+    }
+
+    /// Returns the current estimate of the number of items with a non-zero net count, read at
+    /// the lowest level which can be decoded.
+    ///
+    /// Fails with [`DistinctError::RecoveryFailed`] in the unlikely case that no level can be.
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        if self.levels.is_empty() {
+            return Ok(Estimate::l0(0, 0, self.k, self.processed));
+        }
+
+        (0..self.levels.len())
+            .find_map(|level| {
+                let recovered = self.decode(level)?;
+                Some(Estimate::l0(
+                    recovered,
+                    level as u32,
+                    self.k,
+                    self.processed,
+                ))
+            })
+            .ok_or(DistinctError::RecoveryFailed)
+    }
+
+    /// Adds the updates of `other` to this sketch, so that it counts the net counts of both
+    /// streams. The result is exactly the sketch of the concatenated stream.
+    ///
+    /// Fails if the two sketches were created with different `k`.
+    pub fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        if self.k != other.k {
+            return Err(DistinctError::SampleSizeMismatch(self.k, other.k));
+        }
+
+        let width = self.width();
+        if self.levels.len() < other.levels.len() {
+            self.levels
+                .resize_with(other.levels.len(), || vec![Cell::default(); 3 * width]);
+        }
+        for (cells, theirs) in self.levels.iter_mut().zip(&other.levels) {
+            for (cell, theirs) in cells.iter_mut().zip(theirs) {
+                cell.merge(theirs);
+            }
+        }
+        self.processed += other.processed;
+
+        Ok(())
+    }
+
+    /// Number of cells in each of the three parts of a level, so that a level has about `2k`.
+    fn width(&self) -> usize {
+        (2 * self.k).div_ceil(3)
+    }
+
+    /// Recovers every item of a level with a non-zero net count, by repeatedly removing an item
+    /// from every cell it was hashed to once a cell holds it alone. Returns their number, or
+    /// `None` if some cells still hold several items once none holds a single one.
+    fn decode(&self, level: usize) -> Option<usize> {
+        let width = self.width();
+        let mut cells = self.levels[level].clone();
+        let mut pending: Vec<usize> = (0..cells.len()).collect();
+        let mut recovered = 0;
+
+        while let Some(index) = pending.pop() {
+            let Some((key, hashes, count)) = cells[index].pure(index, width) else {
+                continue;
+            };
+            recovered += 1;
+            for index in hashes.cells(width) {
+                cells[index].add(key, hashes.check, P - count);
+                pending.push(index);
+            }
+        }
+
+        cells
+            .iter()
+            .all(|cell| *cell == Cell::default())
+            .then_some(recovered)
+    }
+}
+
+impl Cell {
+    /// Adds `multiplicity` occurrences of the item identified by `key`.
+    fn add(&mut self, key: u64, check: u64, multiplicity: u64) {
+        self.count = add(self.count, multiplicity);
+        self.key_sum = add(self.key_sum, mul(key, multiplicity));
+        self.check_sum = add(self.check_sum, mul(check, multiplicity));
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.count = add(self.count, other.count);
+        self.key_sum = add(self.key_sum, other.key_sum);
+        self.check_sum = add(self.check_sum, other.check_sum);
+    }
+
+    /// Returns the item this cell, at `index`, holds alone along with its net count, if it does.
+    /// The identifier is the key sum divided by the count; it is only that of a single item if it
+    /// hashes to this cell and its checksum matches, which a mix of several items passes with
+    /// probability about `2^-61`.
+    fn pure(&self, index: usize, width: usize) -> Option<(u64, KeyHashes, u64)> {
+        if self.count == 0 {
+            return None;
+        }
+        let key = mul(self.key_sum, inverse(self.count));
+        let hashes = KeyHashes::new(key);
+        let pure =
+            mul(hashes.check, self.count) == self.check_sum && hashes.cells(width).contains(&index);
+        pure.then_some((key, hashes, self.count))
+    }
+}
+
+/// The hashes of an item's identifier which place it in the sketch.
+#[derive(Debug, Clone, Copy)]
+struct KeyHashes {
+    /// Highest level the item reaches.
+    level: usize,
+    /// Where it goes in each of the three parts of a level.
+    picks: [u64; 3],
+    /// The checksum it adds, weighted by its count.
+    check: u64,
+}
+
+impl KeyHashes {
+    fn new(key: u64) -> Self {
+        let mut state = key;
+        let level = (splitmix64(&mut state).leading_zeros() as usize).min(LEVELS - 1);
+        let picks = [(); 3].map(|_| splitmix64(&mut state));
+        let check = splitmix64(&mut state) % P;
+
+        Self {
+            level,
+            picks,
+            check,
+        }
+    }
+
+    /// Returns the cell the item goes to in each part of a level.
+    fn cells(&self, width: usize) -> [usize; 3] {
+        let mut part = 0;
+        self.picks.map(|pick| {
+            let index = part * width + (pick % width as u64) as usize;
+            part += 1;
+            index
+        })
+    }
+}
+
+fn add(a: u64, b: u64) -> u64 {
+    let sum = a + b;
+    if sum >= P {
+        sum - P
+    } else {
+        sum
+    }
+}
+
+fn mul(a: u64, b: u64) -> u64 {
+    let product = u128::from(a) * u128::from(b);
+    // Since 2^61 = 1 modulo P, the high bits fold back onto the low ones.
+    let folded = (product as u64 & P) + (product >> 61) as u64;
+    let folded = (folded & P) + (folded >> 61);
+    if folded >= P {
+        folded - P
+    } else {
+        folded
+    }
+}
+
+/// The multiplicative inverse of a non-zero element, `a^(P - 2)` by Fermat's little theorem.
+fn inverse(a: u64) -> u64 {
+    let (mut base, mut exponent, mut result) = (a, P - 2, 1);
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul(result, base);
+        }
+        base = mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+impl Mergeable for L0Sketch {
+    fn merge(&mut self, other: &Self) -> Result<(), DistinctError> {
+        L0Sketch::merge(self, other)
+    }
+}
+
+/// Every element fed through the trait is an insertion.
+impl<Q> DistinctCounter<Q> for L0Sketch
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        self.insert(&item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.levels.capacity() * mem::size_of::<Vec<Cell>>()
+            + self
+                .levels
+                .iter()
+                .map(|cells| cells.capacity() * mem::size_of::<Cell>())
+                .sum::<usize>()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.k);
+    }
+
+    fn clear(&mut self) {
+        for cells in &mut self.levels {
+            cells.fill(Cell::default());
+        }
+        self.processed = 0;
+    }
+}
+
+impl<Q> Extend<Q> for L0Sketch
+where
+    Q: Hash,
+{
+    fn extend<I: IntoIterator<Item = Q>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<Q> FromIterator<Q> for L0Sketch
+where
+    Q: Hash,
+{
+    /// Builds a sketch with [`DEFAULT_K`] from insertions.
+    fn from_iter<I: IntoIterator<Item = Q>>(iter: I) -> Self {
+        let mut sketch = Self::new(DEFAULT_K);
+        sketch.extend(iter);
+        sketch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Diagnostics;
+    use quickcheck::quickcheck;
+    use std::collections::HashMap;
+
+    #[test]
+    fn field_arithmetic() {
+        assert_eq!(mul(P - 1, P - 1), 1);
+        assert_eq!(add(P - 1, 2), 1);
+        for a in [1, 2, 12345, P - 2, 1 << 60] {
+            assert_eq!(mul(a, inverse(a)), 1);
+        }
+    }
+
+    #[test]
+    fn deletions_cancel_insertions() {
+        let mut sketch = L0Sketch::new(256);
+        // Interleaved, so that retractions arrive while the stream is far above `k`.
+        for i in 0..200_000u64 {
+            sketch.insert(&i);
+            if i % 2 == 0 {
+                sketch.update(&(i / 2), -1);
+            }
+        }
+        // 0..100_000 were deleted once, leaving 100_000..200_000.
+        let estimate = sketch.try_estimate().unwrap();
+        assert!(
+            estimate.lower <= 100_000.0 && 100_000.0 <= estimate.upper,
+            "{}",
+            estimate
+        );
+        assert_eq!(estimate.processed, 300_000);
+        let Diagnostics::L0 {
+            level, recovered, ..
+        } = estimate.diagnostics
+        else {
+            panic!("unexpected diagnostics {:?}", estimate.diagnostics);
+        };
+        assert!(level > 0 && recovered > 100);
+
+        // Deleting everything else empties the sketch, down to an exact count of 0.
+        for i in 100_000..200_000u64 {
+            sketch.delete(&i);
+        }
+        let estimate = sketch.try_estimate().unwrap();
+        assert_eq!((estimate.value, estimate.confidence), (0.0, 1.0));
+    }
+
+    #[test]
+    fn merge_adds_streams() {
+        let mut left = L0Sketch::new(64);
+        let mut right = L0Sketch::new(64);
+        let mut both = L0Sketch::new(64);
+        for i in 0..5000 {
+            left.insert(&i);
+            right.delete(&(i * 2));
+            both.insert(&i);
+            both.delete(&(i * 2));
+        }
+
+        left.merge(&right).unwrap();
+        assert_eq!(left, both);
+        assert_eq!(
+            left.merge(&L0Sketch::new(32)),
+            Err(DistinctError::SampleSizeMismatch(64, 32))
+        );
+        assert_eq!(
+            L0Sketch::try_new(0),
+            Err(DistinctError::InvalidSampleSize(0))
+        );
+    }
+
+    quickcheck! {
+        fn qc_prop_small_streams_are_exact(updates: Vec<(u8, i8)>) -> bool {
+            let mut sketch = L0Sketch::new(512);
+            let mut net: HashMap<u8, i64> = HashMap::new();
+            for &(item, multiplicity) in &updates {
+                sketch.update(&item, multiplicity.into());
+                *net.entry(item).or_default() += i64::from(multiplicity);
+            }
+
+            let estimate = sketch.try_estimate().unwrap();
+            let nonzero = net.values().filter(|&&count| count != 0).count();
+            estimate.value == nonzero as f64 && estimate.confidence == 1.0
+        }
+    }
+}
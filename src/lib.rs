@@ -1,49 +1,831 @@
-use rand::{
-    self,
-    distributions::{Distribution, Standard},
-    rngs::SmallRng,
-    Rng, SeedableRng,
+use rand::{rngs::OsRng, Rng, RngCore};
+use std::{
+    borrow::Borrow,
+    collections::{hash_map::RandomState, HashSet},
+    hash::{BuildHasher, Hash},
+    marker::PhantomData,
+    mem,
+    ops::AddAssign,
 };
 
-#[cfg(test)]
-use rand::distributions::uniform::{SampleRange, SampleUniform};
+pub mod codec;
+mod counter;
+pub mod csv;
+pub mod datasketches;
+mod error;
+mod estimate;
+mod exact;
+pub mod hash;
+pub mod hyperloglog;
+mod iter;
+pub mod jsonl;
+pub mod kmv;
+pub mod l0;
+#[cfg(feature = "rayon")]
+mod par;
+pub mod report;
+pub mod sample;
+mod sketch;
+pub mod theta;
+pub mod window;
+
+pub use codec::DecodeError;
+pub use counter::{DistinctCounter, Mergeable};
+pub use error::DistinctError;
+pub use estimate::{Diagnostics, Estimate};
+pub use exact::ExactCounter;
+pub use hyperloglog::{
+    ConcurrentHyperLogLog, HyperLogLog, HyperLogLogPlusPlus, SlidingHyperLogLog,
+};
+pub use iter::ApproxDistinct;
+pub use kmv::KmvSketch;
+pub use l0::L0Sketch;
+#[cfg(feature = "rayon")]
+pub use par::{
+    par_find_n_distinct, try_par_find_n_distinct, try_par_find_n_distinct_with_partitions,
+    ParApproxDistinct,
+};
+pub use sample::{HashSample, SampleSet};
+pub use sketch::{ConcurrentCvmSketch, CvmSketch};
+pub use theta::{CompactThetaSketch, ThetaIntersection, ThetaSketch, ThetaUnion};
+pub use window::WindowedCounter;
 
+/// The default source of randomness for the estimators: xoshiro256++ seeded through SplitMix64.
+///
+/// Unlike `rand`'s `SmallRng`, whose algorithm differs between 32 and 64-bit targets and may
+/// change between releases of `rand`, `Gen` produces the same sequence for a given seed on every
+/// platform, so an estimate computed from a seed can be reproduced anywhere. Any other [`RngCore`]
+/// can be used through the `*_with_rng` constructors; `rand_chacha::ChaCha8Rng`,
+/// `rand_pcg::Pcg64` and the generators of `rand_xoshiro` are portable in the same way and
+/// `ChaCha` is also cryptographically strong, while `SmallRng`, `StdRng` and `ThreadRng` are not
+/// reproducible across platforms or releases.
+///
+/// Note that reproducibility also depends on the elements: a [`HashSample`] halves its elements in
+/// the order of their SipHash-1-3 hashes under fixed keys, which do not change between releases,
+/// but the [`Hash`] implementation of types such as `usize` differs between 32 and 64-bit
+/// targets.
+///
+/// With the `serde` feature `Gen` serializes its full state, not just its seed, so an estimator
+/// restored mid-stream goes on drawing the same numbers as the original.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Gen {
-    rng: SmallRng,
+    state: [u64; 4],
+    seed: u64,
 }
 
 impl Gen {
+    /// Creates a generator from `seed`, or from a seed drawn from the OS if none is given.
     pub fn new(seed: Option<u64>) -> Self {
-        let rng = match seed {
-            Some(seed) => SmallRng::seed_from_u64(seed),
-            None => SmallRng::from_entropy(),
+        // Always seed explicitly, drawing the seed from the OS if none was given, so that every run
+        // can be reproduced from the seed reported in its `Estimate`.
+        let seed = seed.unwrap_or_else(|| OsRng.next_u64());
+
+        let mut sm = seed;
+        let state = [
+            splitmix64(&mut sm),
+            splitmix64(&mut sm),
+            splitmix64(&mut sm),
+            splitmix64(&mut sm),
+        ];
+
+        Self { state, seed }
+    }
+
+    /// Returns the seed this generator was created from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+/// One step of SplitMix64, used to expand a 64-bit seed into the xoshiro256++ state.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl RngCore for Gen {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[0].wrapping_add(s[3]).rotate_left(23).wrapping_add(s[0]);
+
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+
+        result
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            let bytes = self.next_u64().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+/// Unwraps the generator passed to a constructor, along with the seed to report in estimates.
+fn seeded(gen: Option<Gen>) -> (Gen, Option<u64>) {
+    let gen = gen.unwrap_or_else(|| Gen::new(None));
+    let seed = Some(gen.seed());
+    (gen, seed)
+}
+
+/// Default accuracy used when an estimator is built without explicit parameters, e.g. through
+/// [`FromIterator`].
+pub const DEFAULT_EPS: f64 = 0.1;
+
+/// Default failure probability used when an estimator is built without explicit parameters, e.g.
+/// through [`FromIterator`].
+pub const DEFAULT_DELTA: f64 = 0.005;
+
+/// Length of the first epoch of an estimator created with [`CvmEstimator::unbounded`].
+const INITIAL_EPOCH_LEN: usize = 1 << 10;
+
+/// Checks that `eps` and `delta` are usable accuracy parameters.
+fn validate(eps: f64, delta: f64) -> Result<(), DistinctError> {
+    if !(eps > 0.0 && eps < 1.0) {
+        return Err(DistinctError::InvalidEpsilon(eps));
+    }
+    if !(delta > 0.0 && delta < 1.0) {
+        return Err(DistinctError::InvalidDelta(delta));
+    }
+
+    Ok(())
+}
+
+/// Computes the sample set threshold from the paper for a stream of length `m`.
+fn threshold(eps: f64, delta: f64, m: usize) -> Result<usize, DistinctError> {
+    let m = m.max(1) as f64;
+    let thresh = (12.0 / eps.powf(2.0) * f64::log2(8.0 * m / delta)).ceil();
+
+    if !thresh.is_finite() || thresh >= usize::MAX as f64 {
+        return Err(DistinctError::ThresholdOverflow);
+    }
+
+    Ok(thresh as usize)
+}
+
+/// How the sample set threshold evolves while the stream is being consumed.
+#[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Schedule {
+    /// The stream length is known up front, so the threshold never changes.
+    Fixed { stream_len: usize },
+    /// The stream length is unknown. The stream is split into epochs of doubling length and the
+    /// threshold is recomputed at the start of every epoch.
+    Doubling { epoch: u32, epoch_end: usize },
+}
+
+/// A stateful F0-Estimator which can be fed one element at a time and queried at any point. This
+/// is the same algorithm as [`find_n_distinct`], except the sample set `chi` lives between calls
+/// so the stream does not need to be materialized up front. The estimator stores whatever `T` it
+/// is given, so feeding it references (`CvmEstimator<&T>`) keeps the original borrow-only
+/// behavior. If the length of the stream is not known ahead of time, use
+/// [`CvmEstimator::unbounded`] instead of [`CvmEstimator::new`].
+///
+/// The sample set is stored in `S`. The default, a plain `Vec<T>`, only needs `T: Eq` but every
+/// insertion scans the whole sample. When `T: Hash + Eq`, prefer [`CvmEstimator::new_hashed`] or
+/// [`CvmEstimator::unbounded_hashed`], which index the sample by hash and make every insertion
+/// `O(1)` instead of `O(thresh)`. Their hasher has fixed, public keys, so a stream crafted to
+/// collide degrades them back to scanning the sample: for untrusted input use
+/// [`CvmEstimator::new_keyed`] or [`CvmEstimator::unbounded_keyed`], whose sample is randomly keyed.
+///
+/// Randomness comes from `R`, a [`Gen`] by default. The `*_with_rng` constructors accept any
+/// [`RngCore`], including a `&mut` reference to a generator the caller wants to keep using
+/// afterwards.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{CvmEstimator, Gen};
+/// let mut estimator = CvmEstimator::new(0.1, 0.005, 13, Some(Gen::new(None)));
+/// for item in [1, 10, 20, 10, 10, 30, 20, 10, 20, 20, 1, 1, 1] {
+///     estimator.insert(item);
+/// }
+/// assert_eq!(estimator.estimate(), 4);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "S: serde::Serialize, R: serde::Serialize",
+        deserialize = "S: serde::Deserialize<'de>, R: serde::Deserialize<'de>"
+    ))
+)]
+pub struct CvmEstimator<T, S = Vec<T>, R = Gen> {
+    gen: R,
+    seed: Option<u64>,
+    eps: f64,
+    delta: f64,
+    p: f64,
+    chi: S,
+    thresh: usize,
+    processed: usize,
+    schedule: Schedule,
+    failed: bool,
+    _item: PhantomData<T>,
+}
+
+impl<T> CvmEstimator<T>
+where
+    T: Eq,
+{
+    /// Creates an empty estimator for a stream of (at most) `stream_len` elements. The accuracy
+    /// parameters match those of [`find_n_distinct`].
+    ///
+    /// # Panics
+    /// Panics if `eps` or `delta` are invalid, see [`CvmEstimator::try_new`].
+    pub fn new(eps: f64, delta: f64, stream_len: usize, gen: Option<Gen>) -> Self {
+        Self::try_new(eps, delta, stream_len, gen).expect("invalid estimator parameters")
+    }
+
+    /// Fallible version of [`CvmEstimator::new`]. Fails if `eps` or `delta` are NaN or outside of
+    /// `(0, 1)`, or if the resulting threshold does not fit in a `usize`.
+    pub fn try_new(
+        eps: f64,
+        delta: f64,
+        stream_len: usize,
+        gen: Option<Gen>,
+    ) -> Result<Self, DistinctError> {
+        let (gen, seed) = seeded(gen);
+        Self::fixed(eps, delta, stream_len, gen, seed)
+    }
+
+    /// Creates an empty estimator for a stream whose length is not known ahead of time, such as a
+    /// socket, a file or an arbitrary iterator.
+    ///
+    /// The stream is consumed in epochs of doubling length: the `j`-th epoch ends after
+    /// `m_j = 1024 * 2^j` elements and uses the threshold the paper prescribes for a stream of
+    /// length `m_j` with failure probability `delta / 2^(j + 1)`. Because those failure
+    /// probabilities sum to at most `delta`, the estimate is still within a factor of `(1 ± eps)`
+    /// of the true count with probability at least `1 - delta`, whatever the final length of the
+    /// stream. The threshold only ever grows, by `24 / eps^2` per epoch, so the sample set stays
+    /// `O(log(m / delta) / eps^2)` just like in the fixed-length case.
+    ///
+    /// # Panics
+    /// Panics if `eps` or `delta` are invalid, see [`CvmEstimator::try_unbounded`].
+    pub fn unbounded(eps: f64, delta: f64, gen: Option<Gen>) -> Self {
+        Self::try_unbounded(eps, delta, gen).expect("invalid estimator parameters")
+    }
+
+    /// Fallible version of [`CvmEstimator::unbounded`].
+    pub fn try_unbounded(eps: f64, delta: f64, gen: Option<Gen>) -> Result<Self, DistinctError> {
+        let (gen, seed) = seeded(gen);
+        Self::doubling(eps, delta, gen, seed)
+    }
+}
+
+impl<T, R> CvmEstimator<T, Vec<T>, R>
+where
+    T: Eq,
+    R: RngCore,
+{
+    /// Creates an empty estimator driven by `rng`, for a stream of at most `stream_len` elements,
+    /// or for a stream of unknown length if `stream_len` is `None`. Pass `&mut rng` to keep using
+    /// the generator once the estimator is done with it.
+    ///
+    /// # Examples
+    /// ```rust
+    /// use distinction::{CvmEstimator, Gen};
+    /// use rand::RngCore;
+    /// let mut rng = Gen::new(Some(42));
+    /// let mut estimator = CvmEstimator::try_with_rng(0.1, 0.005, None, &mut rng).unwrap();
+    /// estimator.extend([1, 2, 2, 3]);
+    /// assert_eq!(estimator.estimate(), 3);
+    ///
+    /// drop(estimator);
+    /// let _ = rng.next_u64();
+    /// ```
+    pub fn try_with_rng(
+        eps: f64,
+        delta: f64,
+        stream_len: Option<usize>,
+        rng: R,
+    ) -> Result<Self, DistinctError> {
+        Self::for_len(eps, delta, stream_len, rng, None)
+    }
+}
+
+impl<T> CvmEstimator<T, HashSample<T>>
+where
+    T: Hash + Eq,
+{
+    /// Same as [`CvmEstimator::new`], but the sample set is indexed by hash.
+    pub fn new_hashed(eps: f64, delta: f64, stream_len: usize, gen: Option<Gen>) -> Self {
+        Self::try_new_hashed(eps, delta, stream_len, gen).expect("invalid estimator parameters")
+    }
+
+    /// Same as [`CvmEstimator::try_new`], but the sample set is indexed by hash.
+    pub fn try_new_hashed(
+        eps: f64,
+        delta: f64,
+        stream_len: usize,
+        gen: Option<Gen>,
+    ) -> Result<Self, DistinctError> {
+        let (gen, seed) = seeded(gen);
+        Self::fixed(eps, delta, stream_len, gen, seed)
+    }
+
+    /// Same as [`CvmEstimator::unbounded`], but the sample set is indexed by hash.
+    pub fn unbounded_hashed(eps: f64, delta: f64, gen: Option<Gen>) -> Self {
+        Self::try_unbounded_hashed(eps, delta, gen).expect("invalid estimator parameters")
+    }
+
+    /// Same as [`CvmEstimator::try_unbounded`], but the sample set is indexed by hash.
+    pub fn try_unbounded_hashed(
+        eps: f64,
+        delta: f64,
+        gen: Option<Gen>,
+    ) -> Result<Self, DistinctError> {
+        let (gen, seed) = seeded(gen);
+        Self::doubling(eps, delta, gen, seed)
+    }
+}
+
+impl<T> CvmEstimator<T, HashSample<T, RandomState>>
+where
+    T: Hash + Eq,
+{
+    /// Same as [`CvmEstimator::new_hashed`], but the sample set is hashed under random keys, so the
+    /// elements of the stream cannot be chosen to collide. Use this when the stream comes from an
+    /// untrusted source. The order elements are halved in then differs from one run to the next,
+    /// so a seeded [`Gen`] no longer makes the estimate reproducible.
+    pub fn new_keyed(eps: f64, delta: f64, stream_len: usize, gen: Option<Gen>) -> Self {
+        Self::try_new_keyed(eps, delta, stream_len, gen).expect("invalid estimator parameters")
+    }
+
+    /// Same as [`CvmEstimator::try_new_hashed`], but the sample set is hashed under random keys.
+    pub fn try_new_keyed(
+        eps: f64,
+        delta: f64,
+        stream_len: usize,
+        gen: Option<Gen>,
+    ) -> Result<Self, DistinctError> {
+        let (gen, seed) = seeded(gen);
+        Self::fixed(eps, delta, stream_len, gen, seed)
+    }
+
+    /// Same as [`CvmEstimator::unbounded_hashed`], but the sample set is hashed under random keys.
+    pub fn unbounded_keyed(eps: f64, delta: f64, gen: Option<Gen>) -> Self {
+        Self::try_unbounded_keyed(eps, delta, gen).expect("invalid estimator parameters")
+    }
+
+    /// Same as [`CvmEstimator::try_unbounded_hashed`], but the sample set is hashed under random
+    /// keys.
+    pub fn try_unbounded_keyed(
+        eps: f64,
+        delta: f64,
+        gen: Option<Gen>,
+    ) -> Result<Self, DistinctError> {
+        let (gen, seed) = seeded(gen);
+        Self::doubling(eps, delta, gen, seed)
+    }
+}
+
+impl<T, R> CvmEstimator<T, HashSample<T>, R>
+where
+    T: Hash + Eq,
+    R: RngCore,
+{
+    /// Same as [`CvmEstimator::try_with_rng`], but the sample set is indexed by hash.
+    pub fn try_with_rng_hashed(
+        eps: f64,
+        delta: f64,
+        stream_len: Option<usize>,
+        rng: R,
+    ) -> Result<Self, DistinctError> {
+        Self::for_len(eps, delta, stream_len, rng, None)
+    }
+}
+
+impl<T, H, R> CvmEstimator<T, HashSample<T, H>, R>
+where
+    T: Hash + Eq,
+    H: BuildHasher + Default,
+    R: RngCore,
+{
+    /// Feeds a single element of the stream into the estimator given only a borrowed form of it,
+    /// e.g. a `&str` for a `CvmEstimator<String, _>`. The element is only converted to an owned
+    /// `T` if it ends up in the sample.
+    pub fn insert_ref<Q>(&mut self, item: &Q)
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ToOwned<Owned = T> + ?Sized,
+    {
+        if self.start_step() {
+            self.chi.remove(item);
+            self.finish_step(|| item.to_owned());
+        }
+    }
+
+    /// Returns `true` if `item` is currently in the sample set.
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.chi.contains(item)
+    }
+}
+
+impl<T, S, R> CvmEstimator<T, S, R>
+where
+    S: SampleSet<T>,
+    R: RngCore,
+{
+    /// Creates an estimator sized for an iterator: with a fixed threshold when its length is known
+    /// exactly, in unbounded mode otherwise.
+    fn for_size_hint(
+        size_hint: (usize, Option<usize>),
+        eps: f64,
+        delta: f64,
+        rng: R,
+        seed: Option<u64>,
+    ) -> Result<Self, DistinctError> {
+        let stream_len = match size_hint {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        };
+        Self::for_len(eps, delta, stream_len, rng, seed)
+    }
+
+    /// Creates an estimator for a stream of `stream_len` elements, or of unknown length.
+    fn for_len(
+        eps: f64,
+        delta: f64,
+        stream_len: Option<usize>,
+        rng: R,
+        seed: Option<u64>,
+    ) -> Result<Self, DistinctError> {
+        match stream_len {
+            Some(stream_len) => Self::fixed(eps, delta, stream_len, rng, seed),
+            None => Self::doubling(eps, delta, rng, seed),
+        }
+    }
+
+    fn fixed(
+        eps: f64,
+        delta: f64,
+        stream_len: usize,
+        rng: R,
+        seed: Option<u64>,
+    ) -> Result<Self, DistinctError> {
+        validate(eps, delta)?;
+        let thresh = threshold(eps, delta, stream_len)?;
+
+        log::info!(
+            "Initializing; p = {} m = {} thresh = {}",
+            1.0,
+            stream_len,
+            thresh
+        );
+
+        let schedule = Schedule::Fixed { stream_len };
+        Ok(Self::with_schedule(eps, delta, thresh, schedule, rng, seed))
+    }
+
+    fn doubling(eps: f64, delta: f64, rng: R, seed: Option<u64>) -> Result<Self, DistinctError> {
+        validate(eps, delta)?;
+        let thresh = threshold(eps, delta / 2.0, INITIAL_EPOCH_LEN)?;
+
+        log::info!("Initializing; p = {} m = unknown thresh = {}", 1.0, thresh);
+
+        let schedule = Schedule::Doubling {
+            epoch: 0,
+            epoch_end: INITIAL_EPOCH_LEN,
         };
+        Ok(Self::with_schedule(eps, delta, thresh, schedule, rng, seed))
+    }
+
+    fn with_schedule(
+        eps: f64,
+        delta: f64,
+        thresh: usize,
+        schedule: Schedule,
+        rng: R,
+        seed: Option<u64>,
+    ) -> Self {
+        Self {
+            gen: rng,
+            seed,
+            eps,
+            delta,
+            p: 1.0,
+            chi: S::default(),
+            thresh,
+            processed: 0,
+            schedule,
+            failed: false,
+            _item: PhantomData,
+        }
+    }
+
+    /// Forgets the stream seen so far, going back to the first epoch of the schedule.
+    fn restart(&mut self) {
+        self.chi.clear();
+        self.p = 1.0;
+        self.processed = 0;
+        self.failed = false;
+
+        match &mut self.schedule {
+            Schedule::Fixed { stream_len } => {
+                self.thresh = threshold(self.eps, self.delta, *stream_len).unwrap_or(usize::MAX);
+            }
+            Schedule::Doubling { epoch, epoch_end } => {
+                *epoch = 0;
+                *epoch_end = INITIAL_EPOCH_LEN;
+                self.thresh =
+                    threshold(self.eps, self.delta / 2.0, INITIAL_EPOCH_LEN).unwrap_or(usize::MAX);
+            }
+        }
+    }
+
+    /// Moves to the next epoch if the stream has outgrown the current one.
+    fn advance_schedule(&mut self) {
+        if let Schedule::Doubling { epoch, epoch_end } = &mut self.schedule {
+            while self.processed > *epoch_end {
+                *epoch += 1;
+                *epoch_end = epoch_end.saturating_mul(2);
+
+                let delta_j = self.delta / 2f64.powi(*epoch as i32 + 1);
+                self.thresh = threshold(self.eps, delta_j, *epoch_end).unwrap_or(usize::MAX);
+
+                log::info!("Starting epoch {}; thresh = {}", epoch, self.thresh);
+            }
+        }
+    }
+
+    /// Feeds a single element of the stream into the estimator.
+    pub fn insert(&mut self, item: T) {
+        if self.start_step() {
+            // If a_i exists in \chi, remove it.
+            self.chi.remove(&item);
+            self.finish_step(|| item);
+        }
+    }
+
+    /// Starts one step of the algorithm, returning `false` if the element should be ignored.
+    fn start_step(&mut self) -> bool {
+        // Once the algorithm has given up there is nothing meaningful left to track.
+        if self.failed {
+            return false;
+        }
 
-        Self { rng }
+        self.processed += 1;
+        self.advance_schedule();
+        true
     }
 
-    /// Generates a random number from the optionally provided seed.
-    fn gen<T>(&mut self) -> T
+    /// Finishes a step of the algorithm once the current element has been removed from the sample
+    /// set. `item` produces the element if it needs to be added back.
+    fn finish_step<F>(&mut self, item: F)
     where
-        Standard: Distribution<T>,
+        F: FnOnce() -> T,
     {
-        self.rng.gen()
+        // With probability p, \chi \leftarrow \chi \union \{a_i\}
+        if self.gen.gen::<f64>() < self.p {
+            // Add the element to \chi
+            self.chi.insert(item());
+        }
+
+        if self.chi.len() == self.thresh {
+            // Throw away each element of \chi with probability 1/2
+            self.chi.halve(&mut self.gen);
+
+            self.p /= 2.0;
+
+            if self.chi.len() == self.thresh {
+                log::warn!("Exiting due to small threshold after removal of elements.");
+                self.failed = true;
+                self.chi.clear();
+            }
+        }
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far, or 0 if the
+    /// sample set could not be shrunk below the threshold. Use [`CvmEstimator::try_estimate`] to
+    /// tell the two apart.
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
     }
 
-    /// Generates random values in a provided range. Only enabled during testing.
-    #[cfg(test)]
-    fn gen_range<T, R>(&mut self, range: R) -> T
+    /// Returns the current estimate of the number of distinct elements seen so far, or
+    /// [`DistinctError::ThresholdExceeded`] if the sample set could not be shrunk below the
+    /// threshold.
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        if self.failed {
+            return Err(DistinctError::ThresholdExceeded);
+        }
+
+        Ok(Estimate::cvm(
+            self.eps,
+            self.delta,
+            self.p,
+            self.chi.len(),
+            self.thresh,
+            self.processed,
+            self.seed,
+        ))
+    }
+
+    /// Folds the state of `other` into this estimator, so that it estimates the number of distinct
+    /// elements across both streams. This lets shards of a stream be processed independently, e.g.
+    /// on different workers, and combined afterwards. Both estimators should have been created with
+    /// the same `eps` and `delta`.
+    ///
+    /// The sampling probabilities are aligned first by subsampling whichever sample was kept with
+    /// the larger `p`, the two samples are then unioned, and the result is halved until it is back
+    /// under the threshold for the combined stream length.
+    ///
+    /// The shards must hold disjoint sets of elements, e.g. because the stream was partitioned by
+    /// key. Each estimator samples independently, so an element seen by both is kept if either
+    /// sample holds it, with up to twice the probability of any other: shards which overlap are
+    /// overcounted, by up to a factor of 2 when they hold the same elements. To count streams that
+    /// may overlap, partition them by hash instead, as
+    /// [`ConcurrentCvmSketch`] does, or use a sketch whose merge is
+    /// exact, such as [`HyperLogLog`].
+    ///
+    /// # Examples
+    /// ```rust
+    /// use distinction::{CvmEstimator, Gen};
+    /// let mut left = CvmEstimator::new_hashed(0.1, 0.005, 4, Some(Gen::new(None)));
+    /// left.extend([1, 2, 3, 3]);
+    /// let mut right = CvmEstimator::new_hashed(0.1, 0.005, 4, Some(Gen::new(None)));
+    /// right.extend([3, 4, 5, 5]);
+    ///
+    /// left += right;
+    /// assert_eq!(left.estimate(), 5);
+    /// ```
+    pub fn merge(&mut self, mut other: Self)
     where
-        T: SampleUniform,
-        R: SampleRange<T>,
+        T: Hash + Eq,
     {
-        self.rng.gen_range(range)
+        if other.failed {
+            self.failed = true;
+        }
+        if self.failed {
+            self.chi.clear();
+            return;
+        }
+
+        // Bring both samples down to the smaller of the two sampling probabilities. Both are powers
+        // of 1/2, so this is a whole number of halvings.
+        while self.p > other.p {
+            self.chi.halve(&mut self.gen);
+            self.p /= 2.0;
+        }
+        while other.p > self.p {
+            other.chi.halve(&mut self.gen);
+            other.p /= 2.0;
+        }
+
+        self.processed += other.processed;
+        self.schedule = match (self.schedule, other.schedule) {
+            (
+                Schedule::Fixed { stream_len },
+                Schedule::Fixed {
+                    stream_len: other_len,
+                },
+            ) => {
+                let stream_len = stream_len.saturating_add(other_len);
+                self.thresh = threshold(self.eps, self.delta, stream_len).unwrap_or(usize::MAX);
+                Schedule::Fixed { stream_len }
+            }
+            (schedule @ Schedule::Doubling { .. }, Schedule::Fixed { .. })
+            | (Schedule::Fixed { .. }, schedule @ Schedule::Doubling { .. }) => schedule,
+            (
+                Schedule::Doubling { epoch, epoch_end },
+                Schedule::Doubling {
+                    epoch: other_epoch,
+                    epoch_end: other_epoch_end,
+                },
+            ) => Schedule::Doubling {
+                epoch: epoch.max(other_epoch),
+                epoch_end: epoch_end.max(other_epoch_end),
+            },
+        };
+        self.thresh = self.thresh.max(other.thresh);
+        self.advance_schedule();
+
+        // Look up the elements of `other` in a set of our own, as `contains` is linear for a `Vec`
+        // sample.
+        let ours: Vec<T> = mem::take(&mut self.chi).into_iter().collect();
+        let theirs: Vec<T> = {
+            let seen: HashSet<&T> = ours.iter().collect();
+            other
+                .chi
+                .into_iter()
+                .filter(|item| !seen.contains(item))
+                .collect()
+        };
+        for item in ours.into_iter().chain(theirs) {
+            self.chi.insert(item);
+        }
+
+        while self.chi.len() >= self.thresh {
+            self.chi.halve(&mut self.gen);
+            self.p /= 2.0;
+        }
+
+        log::info!(
+            "Merged; p = {} |chi| = {} thresh = {}",
+            self.p,
+            self.chi.len(),
+            self.thresh
+        );
+    }
+}
+
+impl<T, S, R> AddAssign for CvmEstimator<T, S, R>
+where
+    T: Hash + Eq,
+    S: SampleSet<T>,
+    R: RngCore,
+{
+    fn add_assign(&mut self, other: Self) {
+        self.merge(other);
+    }
+}
+
+impl<T, S, R> DistinctCounter<T> for CvmEstimator<T, S, R>
+where
+    S: SampleSet<T>,
+    R: RngCore,
+{
+    fn insert(&mut self, item: T) {
+        self.insert(item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.chi.heap_bytes()
+    }
+
+    fn reset(&mut self) {
+        self.restart();
+        self.chi = S::default();
+    }
+
+    fn clear(&mut self) {
+        self.restart();
+    }
+}
+
+impl<T, S, R> Extend<T> for CvmEstimator<T, S, R>
+where
+    S: SampleSet<T>,
+    R: RngCore,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(item);
+        }
+    }
+}
+
+impl<T, S> FromIterator<T> for CvmEstimator<T, S>
+where
+    S: SampleSet<T>,
+{
+    /// Builds an estimator with [`DEFAULT_EPS`] and [`DEFAULT_DELTA`]. The threshold is sized from
+    /// the iterator's length when it is known exactly, otherwise the estimator runs in
+    /// [unbounded](CvmEstimator::unbounded) mode.
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let (gen, seed) = seeded(None);
+        let mut estimator =
+            Self::for_size_hint(iter.size_hint(), DEFAULT_EPS, DEFAULT_DELTA, gen, seed)
+                .expect("default parameters are valid");
+        estimator.extend(iter);
+        estimator
     }
 }
 
-/// Returns the number of distinct entries in an arbitrary stream. Specifically, this function does
-/// not take ownership over any of your data, it operates exclusively on references to it and, therefore,
-/// your data does not even need to implement clone. The algorithm is based on the work of
+/// Returns the number of distinct entries in an arbitrary stream. The stream can be anything which
+/// implements [`IntoIterator`]: passing a reference (`&stream`, a slice, `stream.iter()`) means the
+/// function does not take ownership over any of your data, it operates exclusively on references to
+/// it and, therefore, your data does not even need to implement clone. If the length of the
+/// iterator is not known exactly the estimator runs in [unbounded](CvmEstimator::unbounded) mode.
+/// The algorithm is based on the work of
 /// [Chakraborty, Vinodchandran, and Meel](https://arxiv.org/pdf/2301.10191) for a simple, sample-based
 /// algorithm for finding the number of distinct elements in a stream.
 ///
@@ -56,59 +838,137 @@ impl Gen {
 /// let delta = 0.005;
 /// let n_distinct = find_n_distinct(&stream, eps, delta, Some(gen));
 /// assert_eq!(n_distinct, 4);
+///
+/// let words = "the quick brown fox jumps over the lazy dog";
+/// assert_eq!(find_n_distinct(words.split(' '), eps, delta, None), 8);
 /// ```
-pub fn find_n_distinct<T>(stream: &Vec<T>, eps: f64, delta: f64, gen: Option<Gen>) -> usize
+///
+/// # Panics
+/// Panics if `eps` or `delta` are NaN or outside of `(0, 1)`. Use [`try_find_n_distinct`] to get
+/// an error instead, and to tell an empty stream apart from a failed run.
+pub fn find_n_distinct<I>(stream: I, eps: f64, delta: f64, gen: Option<Gen>) -> usize
 where
-    T: Eq + PartialEq,
+    I: IntoIterator,
+    I::Item: Eq + PartialEq,
 {
+    // Logging may already have been set up, either by the caller or by an earlier call.
     #[cfg(feature = "use_logging")]
     #[cfg(not(test))]
-    env_logger::init();
-
-    if stream.is_empty() {
-        return 0;
-    }
-
-    // Get a random number generator for probability checks.
-    let mut gen = gen.unwrap_or(Gen::new(None));
+    let _ = env_logger::try_init();
 
-    let m = stream.len();
-    let mut p = 1.0;
-    let mut chi: Vec<&T> = Vec::new();
-    let thresh: usize = (12.0 / eps.powf(2.0) * f64::log2((8 * m) as f64 / delta)).ceil() as usize;
+    let stream = stream.into_iter();
+    let (gen, seed) = seeded(gen);
+    let mut estimator: CvmEstimator<I::Item> =
+        CvmEstimator::for_size_hint(stream.size_hint(), eps, delta, gen, seed)
+            .expect("invalid estimator parameters");
+    estimator.extend(stream);
 
-    log::info!("Initializing; p = {} m = {} thresh = {}", p, m, thresh);
-
-    for i in 0..m {
-        let ai = &stream[i];
-
-        // If a_i exists in \chi, remove it.
-        if let Some(pos_i) = chi.iter().position(|x| *x == ai) {
-            chi.swap_remove(pos_i);
-        }
-
-        // With probability p, \chi \leftarrow \chi \union \{a_i\}
-        if gen.gen::<f64>() < p {
-            // Add the element to \chi
-            chi.push(ai);
-        }
+    log::info!("Finished calculating; p = {}", estimator.p);
 
-        if chi.len() == thresh {
-            // Throw away each element of \chi with probability 1/2
-            chi.retain(|_| gen.gen::<f64>() >= 0.5);
+    estimator.estimate()
+}
 
-            p /= 2.0;
+/// Fallible version of [`find_n_distinct`]. Rather than logging a warning and returning 0 when the
+/// sample set is still full after halving, this returns [`DistinctError::ThresholdExceeded`], and
+/// invalid parameters are reported instead of producing a meaningless threshold.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{DistinctError, Gen, try_find_n_distinct};
+/// let stream = vec![1, 10, 20, 10, 10, 30, 20, 10, 20, 20, 1, 1, 1];
+/// let estimate = try_find_n_distinct(&stream, 0.1, 0.005, Some(Gen::new(None))).unwrap();
+/// assert_eq!(estimate.count(), 4);
+///
+/// let err = try_find_n_distinct(&stream, 1.5, 0.005, None).unwrap_err();
+/// assert_eq!(err, DistinctError::InvalidEpsilon(1.5));
+/// ```
+pub fn try_find_n_distinct<I>(
+    stream: I,
+    eps: f64,
+    delta: f64,
+    gen: Option<Gen>,
+) -> Result<Estimate, DistinctError>
+where
+    I: IntoIterator,
+    I::Item: Eq,
+{
+    let (gen, seed) = seeded(gen);
+    try_find_n_distinct_seeded(stream, eps, delta, gen, seed)
+}
 
-            if chi.len() == thresh {
-                log::warn!("Exiting due to small threshold after removal of elements.");
-                return 0;
-            }
-        }
-    }
+/// Same as [`try_find_n_distinct`], but the sampling is driven by the caller's generator, which can
+/// be used again once the function returns. See [`Gen`] for which generators give estimates that
+/// are reproducible across platforms.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{Gen, try_find_n_distinct_with_rng};
+/// let mut rng = Gen::new(Some(42));
+/// let first = try_find_n_distinct_with_rng(&[1, 2, 2], 0.1, 0.005, &mut rng).unwrap();
+/// let second = try_find_n_distinct_with_rng(&[3, 3, 3], 0.1, 0.005, &mut rng).unwrap();
+/// assert_eq!((first.count(), second.count()), (2, 1));
+/// ```
+pub fn try_find_n_distinct_with_rng<I, R>(
+    stream: I,
+    eps: f64,
+    delta: f64,
+    rng: &mut R,
+) -> Result<Estimate, DistinctError>
+where
+    I: IntoIterator,
+    I::Item: Eq,
+    R: RngCore + ?Sized,
+{
+    try_find_n_distinct_seeded(stream, eps, delta, rng, None)
+}
 
-    log::info!("Finished calculating; p = {}", p);
+fn try_find_n_distinct_seeded<I, R>(
+    stream: I,
+    eps: f64,
+    delta: f64,
+    rng: R,
+    seed: Option<u64>,
+) -> Result<Estimate, DistinctError>
+where
+    I: IntoIterator,
+    I::Item: Eq,
+    R: RngCore,
+{
+    let stream = stream.into_iter();
+    let mut estimator: CvmEstimator<I::Item, Vec<I::Item>, R> =
+        CvmEstimator::for_size_hint(stream.size_hint(), eps, delta, rng, seed)?;
+    estimator.extend(stream);
+    estimator.try_estimate()
+}
 
-    return (chi.len() as f64 / p) as usize;
+/// Same as [`find_n_distinct`], but for elements which implement [`Hash`]. The sample set is
+/// indexed by hash, so each element of the stream is processed in constant time rather than in
+/// time proportional to the threshold, which makes small values of `eps` practical on long
+/// streams.
+///
+/// The sample is hashed under fixed, public keys, so a stream crafted to collide makes every element
+/// cost a scan of the sample again. For untrusted input, feed a [`CvmEstimator::new_keyed`] or
+/// [`CvmEstimator::unbounded_keyed`] instead.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{Gen, find_n_distinct_hashed};
+/// let stream = vec![1, 10, 20, 10, 10, 30, 20, 10, 20, 20, 1, 1, 1];
+/// let n_distinct = find_n_distinct_hashed(&stream, 0.01, 0.005, Some(Gen::new(None)));
+/// assert_eq!(n_distinct, 4);
+/// ```
+pub fn find_n_distinct_hashed<I>(stream: I, eps: f64, delta: f64, gen: Option<Gen>) -> usize
+where
+    I: IntoIterator,
+    I::Item: Hash + Eq,
+{
+    let stream = stream.into_iter();
+    let (gen, seed) = seeded(gen);
+    let mut estimator: CvmEstimator<I::Item, HashSample<I::Item>> =
+        CvmEstimator::for_size_hint(stream.size_hint(), eps, delta, gen, seed)
+            .expect("invalid estimator parameters");
+    estimator.extend(stream);
+    estimator.estimate()
 }
 
 #[cfg(test)]
@@ -145,4 +1005,380 @@ mod tests {
             find_n_distinct(&stream, eps, delta, None) == ground_truth_unique_naive(stream)
         }
     }
+
+    #[test]
+    fn estimator_matches_find_n_distinct() {
+        let mut gen = Gen::new(Some(7));
+        let stream: Vec<i32> = (0..10000).map(|_| gen.gen_range(0..500)).collect();
+
+        let mut estimator = CvmEstimator::new(0.1, 0.005, stream.len(), Some(Gen::new(Some(1))));
+        estimator.extend(&stream);
+
+        assert_eq!(
+            estimator.estimate(),
+            find_n_distinct(&stream, 0.1, 0.005, Some(Gen::new(Some(1))))
+        );
+    }
+
+    quickcheck! {
+        fn qc_prop_estimator_from_iter(stream: Vec<i32>) -> bool {
+            let estimator: CvmEstimator<i32> = stream.iter().copied().collect();
+            estimator.estimate() == ground_truth_unique_naive(stream)
+        }
+    }
+
+    #[test]
+    fn unbounded_threshold_grows_with_stream() {
+        let mut estimator = CvmEstimator::unbounded(0.5, 0.005, Some(Gen::new(Some(3))));
+        let initial = estimator.thresh;
+
+        estimator.extend(0..4 * INITIAL_EPOCH_LEN);
+
+        assert!(matches!(
+            estimator.schedule,
+            Schedule::Doubling { epoch: 2, .. }
+        ));
+        assert_eq!(estimator.thresh, initial + 2 * (24.0 / 0.25) as usize);
+    }
+
+    quickcheck! {
+        fn qc_prop_unbounded_unknown_length(stream: Vec<i32>) -> bool {
+            // `filter` hides the length of the stream from the estimator.
+            let estimator: CvmEstimator<&i32> = stream.iter().filter(|_| true).collect();
+            estimator.estimate() == ground_truth_unique_naive(stream)
+        }
+    }
+
+    #[test]
+    fn hashed_matches_ground_truth_below_threshold() {
+        let mut gen = Gen::new(Some(11));
+        let stream: Vec<i32> = (0..100000).map(|_| gen.gen_range(0..20000)).collect();
+
+        assert_eq!(
+            ground_truth_unique_naive(stream.clone()),
+            find_n_distinct_hashed(&stream, 0.1, 0.005, Some(gen))
+        );
+    }
+
+    #[test]
+    fn hashed_estimate_within_eps() {
+        let eps = 0.1;
+        let stream: Vec<u64> = (0..200000).map(|i| i % 150000).collect();
+        let estimate = find_n_distinct_hashed(&stream, eps, 0.005, Some(Gen::new(Some(5))));
+
+        assert!((estimate as f64 - 150000.0).abs() <= eps * 150000.0);
+    }
+
+    #[test]
+    fn hashed_estimate_is_pinned_by_seed() {
+        // Which elements survive a halving depends on the order of the sample, and so on its
+        // hasher. Pinning the estimate catches a hasher whose output changes between releases.
+        let stream: Vec<u64> = (0..200000).map(|i| i % 150000).collect();
+        let estimate = find_n_distinct_hashed(&stream, 0.1, 0.005, Some(Gen::new(Some(5))));
+        assert_eq!(estimate, 151152);
+    }
+
+    #[test]
+    fn overlapping_shards_are_overcounted() {
+        let eps = 0.1;
+        let delta = 0.005;
+        // The same keys twice over, split by position, so that both shards see every key.
+        let stream: Vec<u64> = (0..100000).chain(0..100000).collect();
+        let (left, right) = stream.split_at(100000);
+
+        let mut merged = CvmEstimator::new_hashed(eps, delta, left.len(), Some(Gen::new(Some(1))));
+        merged.extend(left.iter().copied());
+        let mut other = CvmEstimator::new_hashed(eps, delta, right.len(), Some(Gen::new(Some(2))));
+        other.extend(right.iter().copied());
+        merged += other;
+
+        assert!(merged.p < 1.0);
+        assert!(merged.chi.len() < merged.thresh);
+        // Keys in both shards are kept with up to twice the probability of a single sample, as
+        // documented on `merge`.
+        let estimate = merged.estimate() as f64;
+        assert!(estimate > (1.0 + eps) * 100000.0, "{}", estimate);
+        assert!(estimate <= 2.0 * (1.0 + eps) * 100000.0, "{}", estimate);
+    }
+
+    #[test]
+    fn merged_estimate_matches_concatenated_stream() {
+        let eps = 0.1;
+        let delta = 0.005;
+        // Shard by key, the way a distributed job would.
+        let stream: Vec<u64> = (0..300000).map(|i| (i * 7919) % 200000).collect();
+        let (left, right): (Vec<u64>, Vec<u64>) = stream.iter().partition(|x| *x % 2 == 0);
+
+        let mut merged = CvmEstimator::new_hashed(eps, delta, left.len(), Some(Gen::new(Some(1))));
+        merged.extend(left.iter().copied());
+        let mut other = CvmEstimator::new_hashed(eps, delta, right.len(), Some(Gen::new(Some(2))));
+        other.extend(right.iter().copied());
+        merged += other;
+
+        let mut concatenated =
+            CvmEstimator::new_hashed(eps, delta, stream.len(), Some(Gen::new(Some(3))));
+        concatenated.extend(stream.iter().copied());
+
+        assert!(merged.p < 1.0);
+        assert!(merged.chi.len() < merged.thresh);
+        let difference = merged.estimate().abs_diff(concatenated.estimate());
+        assert!(difference as f64 <= eps * 200000.0);
+    }
+
+    quickcheck! {
+        fn qc_prop_merge_below_threshold(left: Vec<i32>, right: Vec<i32>) -> bool {
+            let mut merged: CvmEstimator<i32> = left.iter().copied().collect();
+            merged.merge(right.iter().copied().collect());
+            merged.estimate() == ground_truth_unique_naive([left, right].concat())
+        }
+    }
+
+    #[test]
+    fn invalid_parameters_are_reported() {
+        let stream = [1, 2, 3];
+
+        assert!(matches!(
+            try_find_n_distinct(stream, f64::NAN, 0.005, None),
+            Err(DistinctError::InvalidEpsilon(eps)) if eps.is_nan()
+        ));
+        for eps in [0.0, -0.1, 1.0] {
+            assert_eq!(
+                try_find_n_distinct(stream, eps, 0.005, None),
+                Err(DistinctError::InvalidEpsilon(eps))
+            );
+        }
+        for delta in [0.0, 1.0, 2.0] {
+            assert_eq!(
+                try_find_n_distinct(stream, 0.1, delta, None),
+                Err(DistinctError::InvalidDelta(delta))
+            );
+        }
+        assert_eq!(
+            try_find_n_distinct(stream, 1e-160, 0.005, None),
+            Err(DistinctError::ThresholdOverflow)
+        );
+    }
+
+    /// A generator whose every draw is the largest value, so that halving a `Vec` sample keeps
+    /// every element and the sample can never be shrunk below the threshold.
+    struct Saturated;
+
+    impl RngCore for Saturated {
+        fn next_u32(&mut self) -> u32 {
+            u32::MAX
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            u64::MAX
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            dest.fill(u8::MAX);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn threshold_failure_is_an_error() {
+        let stream: Vec<i32> = (0..1000).collect();
+        let mut estimator = CvmEstimator::try_with_rng(0.5, 0.5, Some(stream.len()), Saturated)
+            .expect("valid estimator parameters");
+        estimator.extend(stream.iter().copied());
+
+        assert_eq!(estimator.estimate(), 0);
+        assert_eq!(
+            estimator.try_estimate(),
+            Err(DistinctError::ThresholdExceeded)
+        );
+        // The failed run is told apart from an empty stream.
+        assert_eq!(
+            try_find_n_distinct_with_rng(&stream, 0.5, 0.5, &mut Saturated),
+            Err(DistinctError::ThresholdExceeded)
+        );
+        assert_eq!(
+            try_find_n_distinct(&[] as &[i32], 0.1, 0.005, None).map(|estimate| estimate.value),
+            Ok(0.0)
+        );
+    }
+
+    #[test]
+    fn interval_holds_true_count_at_eps_edges() {
+        // Estimates as far from the true count as the guarantee allows, on either side.
+        let (eps, f0) = (0.5, 1000.0);
+        let low = Estimate::cvm(eps, 0.01, 0.5, 250, 2000, 5000, None);
+        let high = Estimate::cvm(eps, 0.01, 0.5, 750, 2000, 5000, None);
+        assert_eq!(
+            (low.value, high.value),
+            ((1.0 - eps) * f0, (1.0 + eps) * f0)
+        );
+        for estimate in [&low, &high] {
+            assert!(estimate.lower <= f0 && f0 <= estimate.upper, "{}", estimate);
+        }
+        assert_eq!((low.upper, high.lower), (f0, f0));
+
+        // Partitions at opposite edges.
+        let total = Estimate::partitioned_cvm(&[low, high], 0.01, None);
+        assert!(
+            total.lower <= 2.0 * f0 && 2.0 * f0 <= total.upper,
+            "{}",
+            total
+        );
+    }
+
+    #[test]
+    fn estimate_reports_diagnostics() {
+        let stream: Vec<u64> = (0..100000).collect();
+        let run = |gen: Gen| {
+            let mut estimator = CvmEstimator::new_hashed(0.5, 0.01, stream.len(), Some(gen));
+            estimator.extend(&stream);
+            estimator.try_estimate().unwrap()
+        };
+
+        let gen = Gen::new(None);
+        let seed = gen.seed();
+        let estimate = run(gen);
+        let Diagnostics::Cvm {
+            p,
+            sample_len,
+            thresh,
+            rounds,
+            seed: reported_seed,
+        } = estimate.diagnostics
+        else {
+            panic!("expected CVM diagnostics");
+        };
+
+        assert_eq!(estimate.processed, stream.len());
+        assert_eq!(estimate.value, sample_len as f64 / p);
+        assert_eq!(p, 0.5f64.powi(rounds as i32));
+        assert!(rounds > 0 && sample_len < thresh);
+        assert!(estimate.lower < estimate.value && estimate.value < estimate.upper);
+        assert_eq!(estimate.confidence, 0.99);
+
+        // The reported seed is enough to reproduce the run.
+        assert_eq!(reported_seed, Some(seed));
+        assert_eq!(run(Gen::new(reported_seed)), estimate);
+    }
+
+    #[test]
+    fn insert_ref_only_clones_sampled_elements() {
+        let mut estimator: CvmEstimator<String, HashSample<String>> =
+            CvmEstimator::unbounded_hashed(0.1, 0.005, None);
+        for word in "a b a c b a".split(' ') {
+            estimator.insert_ref(word);
+        }
+
+        assert!(estimator.contains("a") && estimator.contains("c"));
+        assert!(!estimator.contains("d"));
+        assert_eq!(estimator.estimate(), 3);
+    }
+
+    #[test]
+    fn keyed_estimator_counts_like_the_hashed_one() {
+        let stream: Vec<u32> = (0..100_000).map(|i| i % 3_000).collect();
+        let mut keyed = CvmEstimator::new_keyed(0.1, 0.005, stream.len(), None);
+        keyed.extend(stream.iter().copied());
+        let estimate = keyed.try_estimate().unwrap();
+        assert!(estimate.lower <= 3_000.0 && 3_000.0 <= estimate.upper);
+
+        let mut estimator =
+            CvmEstimator::<String, HashSample<String, RandomState>>::unbounded_keyed(
+                0.1, 0.005, None,
+            );
+        for word in "a b a c b a".split(' ') {
+            estimator.insert_ref(word);
+        }
+        assert!(estimator.contains("a") && !estimator.contains("d"));
+        assert_eq!(estimator.estimate(), 3);
+    }
+
+    quickcheck! {
+        fn qc_prop_owned_and_borrowed_inputs_agree(stream: Vec<i32>, seed: u64) -> bool {
+            let by_ref = find_n_distinct(&stream, 0.1, 0.005, Some(Gen::new(Some(seed))));
+            let by_value = find_n_distinct(stream.clone(), 0.1, 0.005, Some(Gen::new(Some(seed))));
+            let chained = find_n_distinct(stream.iter().map(|x| x / 2), 0.1, 0.005, None);
+            let halved: Vec<i32> = stream.iter().map(|x| x / 2).collect();
+            by_ref == by_value
+                && by_value == ground_truth_unique_naive(stream)
+                && chained == ground_truth_unique_naive(halved)
+        }
+    }
+
+    #[test]
+    fn gen_matches_xoshiro256plusplus() {
+        // First outputs of the reference implementation from the state [1, 2, 3, 4].
+        let mut gen = Gen {
+            state: [1, 2, 3, 4],
+            seed: 0,
+        };
+        assert_eq!(gen.next_u64(), 41943041);
+        assert_eq!(gen.next_u64(), 58720359);
+        assert_eq!(gen.next_u64(), 3588806011781223);
+    }
+
+    #[test]
+    fn caller_rng_is_reusable() {
+        let stream: Vec<u64> = (0..50000).collect();
+        let mut rng = Gen::new(Some(9));
+
+        let first = try_find_n_distinct_with_rng(&stream, 0.5, 0.01, &mut rng).unwrap();
+        let mut estimator =
+            CvmEstimator::try_with_rng_hashed(0.5, 0.01, Some(stream.len()), &mut rng).unwrap();
+        estimator.extend(&stream);
+        let second = estimator.try_estimate().unwrap();
+
+        assert!(matches!(
+            first.diagnostics,
+            Diagnostics::Cvm { seed: None, .. }
+        ));
+        assert!((first.value - 50000.0).abs() <= 0.5 * 50000.0);
+        assert!((second.value - 50000.0).abs() <= 0.5 * 50000.0);
+        // Both runs drew from the same generator, so they sampled differently.
+        assert_ne!(first.value, second.value);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_keeps_generator_state() {
+        let mut estimator = CvmEstimator::<u64>::new(0.5, 0.01, 40000, Some(Gen::new(Some(4))));
+        estimator.extend(0..20000);
+        assert!(estimator.p < 1.0);
+
+        let json = serde_json::to_string(&estimator).unwrap();
+        let mut restored: CvmEstimator<u64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.try_estimate(), estimator.try_estimate());
+
+        // The sample keeps its order and the generator its state, so both halve identically.
+        estimator.extend(10000..40000);
+        restored.extend(10000..40000);
+        let estimate = estimator.try_estimate().unwrap();
+        assert_eq!(restored.try_estimate(), Ok(estimate));
+
+        let json = serde_json::to_string(&estimate).unwrap();
+        assert_eq!(serde_json::from_str::<Estimate>(&json).unwrap(), estimate);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_sketches() {
+        fn round_trip<T>(sketch: T) -> bool
+        where
+            T: serde::Serialize + serde::de::DeserializeOwned + PartialEq,
+        {
+            let json = serde_json::to_string(&sketch).unwrap();
+            serde_json::from_str::<T>(&json).unwrap() == sketch
+        }
+
+        assert!(round_trip((0..100_000).collect::<HyperLogLog>()));
+        assert!(round_trip((0..100).collect::<HyperLogLogPlusPlus>()));
+        assert!(round_trip((0..100_000).collect::<HyperLogLogPlusPlus>()));
+        assert!(round_trip((0..100_000).collect::<KmvSketch>()));
+        let theta: ThetaSketch = (0..100_000).collect();
+        assert!(round_trip(theta.compact()));
+        assert!(round_trip(theta));
+    }
 }
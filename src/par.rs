@@ -0,0 +1,299 @@
+//! Estimating the number of distinct elements on rayon's thread pool.
+use std::{hash::Hash, sync::Mutex};
+
+use rand::RngCore;
+use rayon::prelude::*;
+
+use crate::{
+    hash::{self, hash64},
+    seeded, splitmix64, validate, CvmEstimator, DistinctError, Estimate, Gen, HashSample,
+};
+
+/// Number of elements a worker collects for a partition before locking its estimator.
+const BATCH_LEN: usize = 256;
+
+/// Same as [`find_n_distinct_hashed`](crate::find_n_distinct_hashed), but the slice is processed
+/// on rayon's thread pool.
+///
+/// Splitting a stream into contiguous chunks and merging their estimators would overcount: an
+/// element present in several chunks has a chance to be sampled in each of them. Instead the
+/// elements are partitioned by hash, so that every distinct element belongs to exactly one of
+/// [`rayon::current_num_threads`] partitions, and every partition is counted by a
+/// [`CvmEstimator`] of its own which the workers feed in batches. Each partition runs with failure
+/// probability `delta / partitions`, so by the union bound the sum of their estimates is within a
+/// factor of `(1 ± eps)` of the true count with probability `1 - delta`, as with
+/// [`find_n_distinct`](crate::find_n_distinct).
+///
+/// The sample set threshold depends on `eps` and only logarithmically on the number of elements,
+/// so every estimator may hold about as many elements as a single one counting the whole slice,
+/// and together they use up to `partitions` times as much memory. Use
+/// [`try_par_find_n_distinct_with_partitions`] to pick the number of partitions rather than follow
+/// the size of the thread pool.
+///
+/// The generators of the partitions are drawn from `gen`, but the workers feed the estimators in
+/// whatever order they are scheduled, so the same seed may give different estimates from one run
+/// to the next.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{par_find_n_distinct, Gen};
+/// let data: Vec<u32> = (0..1_000_000).map(|i| i % 1000).collect();
+/// assert_eq!(par_find_n_distinct(&data, 0.1, 0.005, Some(Gen::new(None))), 1000);
+/// ```
+///
+/// # Panics
+/// Panics if `eps` or `delta` are NaN or outside of `(0, 1)`. Use [`try_par_find_n_distinct`] to
+/// get an error instead.
+pub fn par_find_n_distinct<T>(data: &[T], eps: f64, delta: f64, gen: Option<Gen>) -> usize
+where
+    T: Hash + Eq + Sync,
+{
+    validate(eps, delta).expect("invalid estimator parameters");
+    try_par_find_n_distinct(data, eps, delta, gen).map_or(0, |estimate| estimate.count())
+}
+
+/// Fallible version of [`par_find_n_distinct`].
+pub fn try_par_find_n_distinct<T>(
+    data: &[T],
+    eps: f64,
+    delta: f64,
+    gen: Option<Gen>,
+) -> Result<Estimate, DistinctError>
+where
+    T: Hash + Eq + Sync,
+{
+    par_estimate(
+        data.par_iter(),
+        eps,
+        delta,
+        rayon::current_num_threads(),
+        gen,
+    )
+}
+
+/// Same as [`try_par_find_n_distinct`], but the elements are split into `partitions` partitions
+/// (at least one) rather than one per thread of the pool. Memory grows with the number of
+/// partitions, and parallelism is limited by it, as the workers share the estimators.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{try_par_find_n_distinct_with_partitions, Diagnostics, Gen};
+/// let data: Vec<u32> = (0..1_000_000).map(|i| i % 1000).collect();
+/// let estimate =
+///     try_par_find_n_distinct_with_partitions(&data, 0.1, 0.005, 2, Some(Gen::new(None)))
+///         .unwrap();
+/// assert_eq!(estimate.count(), 1000);
+/// assert!(matches!(estimate.diagnostics, Diagnostics::PartitionedCvm { partitions: 2, .. }));
+/// ```
+pub fn try_par_find_n_distinct_with_partitions<T>(
+    data: &[T],
+    eps: f64,
+    delta: f64,
+    partitions: usize,
+    gen: Option<Gen>,
+) -> Result<Estimate, DistinctError>
+where
+    T: Hash + Eq + Sync,
+{
+    par_estimate(data.par_iter(), eps, delta, partitions, gen)
+}
+
+/// Extension trait adding [`par_approx_distinct`](ParApproxDistinct::par_approx_distinct) to every
+/// rayon [`ParallelIterator`] over hashable elements.
+///
+/// # Examples
+/// ```rust
+/// use distinction::ParApproxDistinct;
+/// use rayon::prelude::*;
+/// let estimate = (0..1_000_000u32)
+///     .into_par_iter()
+///     .map(|i| i % 1000)
+///     .par_approx_distinct(0.1, 0.005)
+///     .unwrap();
+/// assert_eq!(estimate.count(), 1000);
+/// ```
+pub trait ParApproxDistinct: ParallelIterator {
+    /// Consumes the iterator and estimates the number of distinct elements it yielded, counting
+    /// partitions of the elements in parallel as [`par_find_n_distinct`] does. The estimators are
+    /// sized from the iterator's length when rayon knows it, otherwise they run in
+    /// [unbounded](CvmEstimator::unbounded) mode.
+    fn par_approx_distinct(self, eps: f64, delta: f64) -> Result<Estimate, DistinctError>;
+
+    /// Same as [`par_approx_distinct`](ParApproxDistinct::par_approx_distinct), but the elements
+    /// are split into `partitions` partitions (at least one). See
+    /// [`try_par_find_n_distinct_with_partitions`].
+    fn par_approx_distinct_with_partitions(
+        self,
+        eps: f64,
+        delta: f64,
+        partitions: usize,
+    ) -> Result<Estimate, DistinctError>;
+}
+
+impl<I> ParApproxDistinct for I
+where
+    I: ParallelIterator,
+    I::Item: Hash + Eq,
+{
+    fn par_approx_distinct(self, eps: f64, delta: f64) -> Result<Estimate, DistinctError> {
+        par_estimate(self, eps, delta, rayon::current_num_threads(), None)
+    }
+
+    fn par_approx_distinct_with_partitions(
+        self,
+        eps: f64,
+        delta: f64,
+        partitions: usize,
+    ) -> Result<Estimate, DistinctError> {
+        par_estimate(self, eps, delta, partitions, None)
+    }
+}
+
+/// Picks the partition of `item`. The samples of the partitions hash their elements with the same
+/// SipHash as [`hash64`], and their tables tell elements apart by the top bits of that hash, which
+/// would be the same for every element of a partition picked by them. The hash is remixed first.
+fn partition_of<T>(item: &T, partitions: usize) -> usize
+where
+    T: Hash + ?Sized,
+{
+    let mut state = hash64(item);
+    hash::partition(splitmix64(&mut state), partitions)
+}
+
+fn par_estimate<I>(
+    iter: I,
+    eps: f64,
+    delta: f64,
+    partitions: usize,
+    gen: Option<Gen>,
+) -> Result<Estimate, DistinctError>
+where
+    I: ParallelIterator,
+    I::Item: Hash + Eq,
+{
+    validate(eps, delta)?;
+    let partitions = partitions.max(1);
+    let (mut gen, seed) = seeded(gen);
+    let stream_len = iter.opt_len();
+    let estimators = (0..partitions)
+        .map(|_| {
+            let gen = Gen::new(Some(gen.next_u64()));
+            CvmEstimator::<I::Item, HashSample<I::Item>>::for_len(
+                eps,
+                delta / partitions as f64,
+                stream_len,
+                gen,
+                None,
+            )
+            .map(Mutex::new)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let flush = |partition: usize, batch: &mut Vec<I::Item>| {
+        let mut estimator = estimators[partition]
+            .lock()
+            .expect("a worker panicked while holding an estimator");
+        estimator.extend(batch.drain(..));
+    };
+    iter.fold(
+        || (0..partitions).map(|_| Vec::new()).collect::<Vec<_>>(),
+        |mut batches, item| {
+            let partition = partition_of(&item, partitions);
+            batches[partition].push(item);
+            if batches[partition].len() == BATCH_LEN {
+                flush(partition, &mut batches[partition]);
+            }
+            batches
+        },
+    )
+    .for_each(|mut batches| {
+        for (partition, batch) in batches.iter_mut().enumerate() {
+            flush(partition, batch);
+        }
+    });
+
+    let estimates = estimators
+        .into_iter()
+        .map(|estimator| {
+            estimator
+                .into_inner()
+                .expect("a worker panicked while holding an estimator")
+                .try_estimate()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Estimate::partitioned_cvm(&estimates, delta, seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Diagnostics;
+    use std::collections::HashSet;
+
+    #[test]
+    fn repeated_elements_are_not_overcounted() {
+        // Every element appears in every chunk a worker could be given.
+        let data: Vec<u64> = (0..2_000_000).map(|i| i % 100_000).collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let estimate = pool
+            .install(|| try_par_find_n_distinct(&data, 0.2, 0.01, Some(Gen::new(Some(7)))))
+            .unwrap();
+
+        assert!(
+            estimate.lower <= 100_000.0 && 100_000.0 <= estimate.upper,
+            "{}",
+            estimate
+        );
+        assert_eq!(estimate.processed, data.len());
+        assert_eq!(estimate.confidence, 0.99);
+        let Diagnostics::PartitionedCvm {
+            partitions, seed, ..
+        } = estimate.diagnostics
+        else {
+            panic!("unexpected diagnostics {:?}", estimate.diagnostics);
+        };
+        assert_eq!(partitions, 4);
+        assert_eq!(seed, Some(7));
+    }
+
+    #[test]
+    fn partitions_do_not_share_top_hash_bits() {
+        // The 7 top bits of the hash are the tag the sample's hash table tells elements apart by.
+        let tags: HashSet<u64> = (0..100_000u64)
+            .filter(|i| partition_of(i, 128) == 0)
+            .map(|i| hash64(&i) >> 57)
+            .collect();
+        assert_eq!(tags.len(), 128);
+    }
+
+    #[test]
+    fn parallel_iterators_of_unknown_length() {
+        let estimate = (0..300_000u32)
+            .into_par_iter()
+            .filter(|i| i % 3 == 0)
+            .map(|i| i % 5000)
+            .par_approx_distinct(0.1, 0.005)
+            .unwrap();
+        assert_eq!(estimate.count(), 5000);
+        assert_eq!(estimate.processed, 100_000);
+
+        let estimate = (0..300_000u32)
+            .into_par_iter()
+            .map(|i| i % 5000)
+            .par_approx_distinct_with_partitions(0.1, 0.005, 0)
+            .unwrap();
+        assert_eq!(estimate.count(), 5000);
+        assert!(matches!(
+            estimate.diagnostics,
+            Diagnostics::PartitionedCvm { partitions: 1, .. }
+        ));
+
+        assert_eq!(
+            (0..10).into_par_iter().par_approx_distinct(1.5, 0.005),
+            Err(DistinctError::InvalidEpsilon(1.5))
+        );
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-column cardinality reports, shared by the readers of structured inputs.
+use std::fmt::{self, Write};
+
+use crate::{DistinctCounter, DistinctError, Estimate};
+
+/// One counter per column (or key) of an input, filled in a single pass over it.
+#[derive(Debug, Clone)]
+pub struct ColumnCounters<C> {
+    /// The number of records read, not counting a header.
+    pub rows: usize,
+    /// The name of every column and its counter, in the order they were asked for.
+    pub columns: Vec<(String, C)>,
+}
+
+impl<C> ColumnCounters<C> {
+    /// Builds the report of the estimates of every counter.
+    pub fn report(&self) -> Result<Report, DistinctError>
+    where
+        C: for<'a> DistinctCounter<&'a [u8]>,
+    {
+        let columns = self
+            .columns
+            .iter()
+            .map(|(name, counter)| {
+                Ok(ColumnReport {
+                    name: name.clone(),
+                    estimate: DistinctCounter::estimate(counter)?,
+                    exact: None,
+                })
+            })
+            .collect::<Result<_, DistinctError>>()?;
+
+        Ok(Report {
+            rows: self.rows,
+            columns,
+        })
+    }
+}
+
+/// The number of distinct values of every column of an input.
+///
+/// Its [`Display`](fmt::Display) implementation renders a table with a line per column, and
+/// [`to_json`](Report::to_json) a JSON document for further processing.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// The number of records read, not counting a header.
+    pub rows: usize,
+    /// A line per column, in the order they were asked for.
+    pub columns: Vec<ColumnReport>,
+}
+
+/// The number of distinct values of a single column.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ColumnReport {
+    /// The name of the column, or of the columns of a composite key joined with `+`.
+    pub name: String,
+    /// The estimated number of distinct values.
+    pub estimate: Estimate,
+    /// The exact number of distinct values, when it was also computed to check the estimate.
+    pub exact: Option<usize>,
+}
+
+impl Report {
+    /// Renders the report as a single line of JSON:
+    ///
+    /// ```json
+    /// {"rows":3,"columns":[{"name":"id","estimate":3,"lower":3,"upper":3,"confidence":1}]}
+    /// ```
+    ///
+    /// Columns also carry an `exact` count when one was computed.
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"rows\":{},\"columns\":[", self.rows);
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"name\":");
+            json_string(&mut json, &column.name);
+            let estimate = &column.estimate;
+            let _ = write!(json, ",\"estimate\":{}", estimate.count());
+            for (key, value) in [
+                ("lower", estimate.lower.floor()),
+                ("upper", estimate.upper.ceil()),
+                ("confidence", estimate.confidence),
+            ] {
+                // JSON has no infinities, which an upper bound can be.
+                if value.is_finite() {
+                    let _ = write!(json, ",\"{}\":{}", key, value);
+                } else {
+                    let _ = write!(json, ",\"{}\":null", key);
+                }
+            }
+            if let Some(exact) = column.exact {
+                let _ = write!(json, ",\"exact\":{}", exact);
+            }
+            json.push('}');
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let exact = self.columns.iter().any(|column| column.exact.is_some());
+        let width = self
+            .columns
+            .iter()
+            .map(|column| column.name.chars().count())
+            .chain(["column".len()])
+            .max()
+            .unwrap_or_default();
+
+        write!(
+            f,
+            "{:<width$}  {:>12}  {:>12}  {:>12}",
+            "column", "distinct", "lower", "upper"
+        )?;
+        if exact {
+            write!(f, "  {:>12}  {:>8}", "exact", "error")?;
+        }
+        for column in &self.columns {
+            let estimate = &column.estimate;
+            write!(
+                f,
+                "\n{:<width$}  {:>12}  {:>12.0}  {:>12.0}",
+                column.name,
+                estimate.count(),
+                estimate.lower.floor(),
+                estimate.upper.ceil()
+            )?;
+            if let Some(count) = column.exact {
+                let error = (estimate.value - count as f64) / (count as f64).max(1.0);
+                write!(f, "  {:>12}  {:>+7.2}%", count, error * 100.0)?;
+            } else if exact {
+                write!(f, "  {:>12}  {:>8}", "-", "-")?;
+            }
+        }
+        write!(f, "\n{} rows", self.rows)
+    }
+}
+
+/// Appends `s` to `json` as a JSON string.
+fn json_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExactCounter;
+
+    #[test]
+    fn renders_tables_and_json() {
+        let mut ids = ExactCounter::<Vec<u8>>::new();
+        ids.insert_batch([&b"a"[..], b"b", b"c"]);
+        let mut names = ExactCounter::<Vec<u8>>::new();
+        names.insert_batch([&b"x"[..], b"x", b"z"]);
+        let counters = ColumnCounters {
+            rows: 3,
+            columns: vec![("id".to_owned(), ids), ("full name".to_owned(), names)],
+        };
+        let mut report = counters.report().unwrap();
+
+        assert_eq!(
+            report.to_string(),
+            "column         distinct         lower         upper\n\
+             id                    3             3             3\n\
+             full name             2             2             2\n\
+             3 rows"
+        );
+        assert_eq!(
+            report.to_json(),
+            r#"{"rows":3,"columns":[{"name":"id","estimate":3,"lower":3,"upper":3,"confidence":1},{"name":"full name","estimate":2,"lower":2,"upper":2,"confidence":1}]}"#
+        );
+
+        report.columns[0].exact = Some(4);
+        report.columns[1].name = "tab\there".to_owned();
+        assert_eq!(
+            report.to_string().lines().nth(1),
+            Some("id                   3             3             3             4   -25.00%")
+        );
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["columns"][0]["exact"], 4);
+        assert_eq!(json["columns"][1]["name"], "tab\there");
+    }
+}
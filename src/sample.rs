@@ -0,0 +1,200 @@
+//! Storage for the sample set `chi` kept by [`CvmEstimator`](crate::CvmEstimator).
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    hash::{BuildHasher, Hash},
+    mem,
+};
+
+use rand::{Rng, RngCore};
+
+use crate::hash::FixedState;
+
+/// The operations the F0-Estimator needs from its sample set.
+pub trait SampleSet<T>: Default + IntoIterator<Item = T> {
+    /// Returns the number of elements currently in the sample.
+    fn len(&self) -> usize;
+
+    /// Returns `true` if the sample holds no elements.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if `item` is in the sample.
+    fn contains(&self, item: &T) -> bool;
+
+    /// Removes `item` from the sample if it is present.
+    fn remove(&mut self, item: &T);
+
+    /// Adds `item` to the sample. The estimator always removes an element before re-inserting it,
+    /// so `item` is never already present.
+    fn insert(&mut self, item: T);
+
+    /// Throws away each element of the sample with probability 1/2.
+    fn halve<R: RngCore + ?Sized>(&mut self, rng: &mut R);
+
+    /// Removes every element from the sample.
+    fn clear(&mut self);
+
+    /// Returns the approximate number of bytes the sample has allocated on the heap.
+    fn heap_bytes(&self) -> usize {
+        self.len() * mem::size_of::<T>()
+    }
+}
+
+/// The original, unindexed sample set. Only requires `T: Eq`, but looking an element up is linear
+/// in the size of the sample.
+impl<T> SampleSet<T> for Vec<T>
+where
+    T: Eq,
+{
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        <[T]>::contains(self, item)
+    }
+
+    fn remove(&mut self, item: &T) {
+        if let Some(pos) = self.iter().position(|x| x == item) {
+            self.swap_remove(pos);
+        }
+    }
+
+    fn insert(&mut self, item: T) {
+        self.push(item);
+    }
+
+    fn halve<R: RngCore + ?Sized>(&mut self, rng: &mut R) {
+        self.retain(|_| rng.gen::<f64>() >= 0.5);
+    }
+
+    fn clear(&mut self) {
+        Vec::clear(self);
+    }
+
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * mem::size_of::<T>()
+    }
+}
+
+/// A sample set indexed by hash, giving constant time lookups, insertions and removals.
+///
+/// By default the hasher is [`FixedState`], SipHash-1-3 under the fixed keys of
+/// [`hash64`](crate::hash::hash64), rather than a per-process random state or std's
+/// `DefaultHasher`, whose algorithm may change between releases of Rust. The iteration order of the
+/// sample, and therefore which elements survive a halving for a given [`Gen`](crate::Gen) seed, is
+/// then the same from one run to the next.
+///
+/// The fixed keys are public, so whoever controls the stream can feed it elements which all
+/// collide, turning every insertion into a scan of the sample. When the elements come from an
+/// untrusted source, key the sample with `S` =
+/// [`RandomState`](std::collections::hash_map::RandomState) instead, e.g. with
+/// [`CvmEstimator::new_keyed`](crate::CvmEstimator::new_keyed), at the cost of reproducible
+/// estimates.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "T: serde::Serialize",
+        deserialize = "T: serde::Deserialize<'de> + Hash + Eq, S: BuildHasher + Default"
+    ))
+)]
+pub struct HashSample<T, S = FixedState> {
+    set: HashSet<T, S>,
+}
+
+impl<T, S> Default for HashSample<T, S>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        Self {
+            set: HashSet::default(),
+        }
+    }
+}
+
+impl<T, S> HashSample<T, S> {
+    /// Creates an empty sample whose elements are hashed by `hasher`.
+    pub fn with_hasher(hasher: S) -> Self {
+        Self {
+            set: HashSet::with_hasher(hasher),
+        }
+    }
+
+    /// Returns an iterator over the elements of the sample, in the order they would be halved in.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.set.iter()
+    }
+}
+
+impl<T, S> HashSample<T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Returns `true` if the sample holds an element equal to `item`.
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.set.contains(item)
+    }
+
+    /// Removes the element equal to `item` from the sample if it is present.
+    pub fn remove<Q>(&mut self, item: &Q)
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.set.remove(item);
+    }
+}
+
+impl<T, S> SampleSet<T> for HashSample<T, S>
+where
+    T: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    fn contains(&self, item: &T) -> bool {
+        self.set.contains(item)
+    }
+
+    fn remove(&mut self, item: &T) {
+        self.set.remove(item);
+    }
+
+    fn insert(&mut self, item: T) {
+        self.set.insert(item);
+    }
+
+    fn halve<R: RngCore + ?Sized>(&mut self, rng: &mut R) {
+        self.set.retain(|_| rng.gen::<f64>() >= 0.5);
+    }
+
+    fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    fn heap_bytes(&self) -> usize {
+        // Each bucket of the table holds an element and a control byte.
+        self.set.capacity() * (mem::size_of::<T>() + 1)
+    }
+}
+
+impl<T, S> IntoIterator for HashSample<T, S> {
+    type Item = T;
+    type IntoIter = std::collections::hash_set::IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.set.into_iter()
+    }
+}
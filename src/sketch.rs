@@ -0,0 +1,535 @@
+//! An owned variant of [`CvmEstimator`] which does not borrow the stream, and a variant of it
+//! which many threads can feed at once.
+use std::{
+    hash::Hash,
+    marker::PhantomData,
+    mem,
+    ops::AddAssign,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use rand::RngCore;
+
+use crate::{
+    codec::{self, DecodeError, Kind},
+    hash::{self, hash64},
+    seeded, validate, CvmEstimator, DistinctCounter, DistinctError, Estimate, Gen, HashSample,
+    SampleSet, Schedule,
+};
+
+/// An F0-Estimator which keeps 64-bit hashes of the sampled elements rather than the elements
+/// themselves.
+///
+/// Unlike a [`CvmEstimator<&T>`](CvmEstimator), the sketch does not hold on to the stream, so it is
+/// `'static + Send + Sync` and can be stored in long-lived structures, moved across threads, or kept
+/// around after the data it was built from has been dropped. Its memory use is bounded by the
+/// threshold regardless of how large the elements are.
+///
+/// Elements are identified by their [`hash64`](crate::hash::hash64), so two different elements with
+/// the same hash are counted once. With 64-bit hashes this is vanishingly unlikely below billions of
+/// distinct elements.
+///
+/// Both `hash64` and the table the sampled hashes are kept in use fixed, public keys, so that
+/// sketches built in different processes can be merged and seeded runs reproduced. Elements from an
+/// untrusted source can therefore be chosen to collide, making insertions linear in the size of the
+/// sample; prefer a [`CvmEstimator::new_keyed`] for such streams.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{CvmSketch, Gen};
+/// let mut sketch = CvmSketch::unbounded(0.1, 0.005, Some(Gen::new(None)));
+/// for line in ["a", "b", "a", "c"] {
+///     sketch.insert(&line.to_string());
+/// }
+/// assert_eq!(sketch.estimate(), 3);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CvmSketch {
+    estimator: CvmEstimator<u64, HashSample<u64>>,
+}
+
+impl CvmSketch {
+    /// Creates an empty sketch for a stream of (at most) `stream_len` elements. See
+    /// [`CvmEstimator::new`].
+    pub fn new(eps: f64, delta: f64, stream_len: usize, gen: Option<Gen>) -> Self {
+        Self {
+            estimator: CvmEstimator::new_hashed(eps, delta, stream_len, gen),
+        }
+    }
+
+    /// Fallible version of [`CvmSketch::new`]. See [`CvmEstimator::try_new`].
+    pub fn try_new(
+        eps: f64,
+        delta: f64,
+        stream_len: usize,
+        gen: Option<Gen>,
+    ) -> Result<Self, DistinctError> {
+        Ok(Self {
+            estimator: CvmEstimator::try_new_hashed(eps, delta, stream_len, gen)?,
+        })
+    }
+
+    /// Creates an empty sketch for a stream of unknown length. See [`CvmEstimator::unbounded`].
+    pub fn unbounded(eps: f64, delta: f64, gen: Option<Gen>) -> Self {
+        Self {
+            estimator: CvmEstimator::unbounded_hashed(eps, delta, gen),
+        }
+    }
+
+    /// Fallible version of [`CvmSketch::unbounded`]. See [`CvmEstimator::try_unbounded`].
+    pub fn try_unbounded(eps: f64, delta: f64, gen: Option<Gen>) -> Result<Self, DistinctError> {
+        Ok(Self {
+            estimator: CvmEstimator::try_unbounded_hashed(eps, delta, gen)?,
+        })
+    }
+
+    /// Feeds a single element of the stream into the sketch. Only its hash is kept.
+    pub fn insert<Q>(&mut self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item));
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`](crate::hash::hash64).
+    pub fn insert_hash(&mut self, hash: u64) {
+        self.estimator.insert(hash);
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far. See
+    /// [`CvmEstimator::estimate`].
+    pub fn estimate(&self) -> usize {
+        self.estimator.estimate()
+    }
+
+    /// Fallible version of [`CvmSketch::estimate`]. See [`CvmEstimator::try_estimate`].
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        self.estimator.try_estimate()
+    }
+
+    /// Folds the state of `other` into this sketch. The two sketches must have been fed disjoint
+    /// sets of elements, as those seen by both are overcounted. See [`CvmEstimator::merge`].
+    pub fn merge(&mut self, other: Self) {
+        self.estimator.merge(other.estimator);
+    }
+
+    /// Serializes the sketch, including the state of its generator, in the format described in
+    /// [`codec`](crate::codec). The sampled hashes, sampling probability, threshold schedule and
+    /// generator state are all kept, so a sketch read back with [`CvmSketch::from_bytes`] carries
+    /// on with the same guarantees. The sample is re-indexed when it is read back, so which hashes
+    /// a later halving keeps may differ from the original sketch.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let estimator = &self.estimator;
+        let mut hashes: Vec<u64> = estimator.chi.iter().copied().collect();
+        hashes.sort_unstable();
+
+        codec::encode(Kind::Cvm, |writer| {
+            writer.f64(estimator.eps);
+            writer.f64(estimator.delta);
+            writer.f64(estimator.p);
+            writer.usize(estimator.thresh);
+            writer.usize(estimator.processed);
+            match estimator.schedule {
+                Schedule::Fixed { stream_len } => {
+                    writer.u8(0);
+                    writer.usize(stream_len);
+                }
+                Schedule::Doubling { epoch, epoch_end } => {
+                    writer.u8(1);
+                    writer.u32(epoch);
+                    writer.usize(epoch_end);
+                }
+            }
+            writer.u8(estimator.failed as u8);
+            writer.u8(estimator.seed.is_some() as u8);
+            writer.u64(estimator.seed.unwrap_or(0));
+            for word in estimator.gen.state {
+                writer.u64(word);
+            }
+            writer.u64(estimator.gen.seed);
+            writer.usize(hashes.len());
+            for hash in hashes {
+                writer.u64(hash);
+            }
+        })
+    }
+
+    /// Reads back a sketch written by [`CvmSketch::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        codec::decode(bytes, Kind::Cvm, |reader, _| {
+            let eps = reader.f64()?;
+            let delta = reader.f64()?;
+            validate(eps, delta).map_err(|_| DecodeError::Invalid("eps or delta out of range"))?;
+
+            let p = reader.f64()?;
+            if !(p > 0.0 && p <= 1.0) {
+                return Err(DecodeError::Invalid("sampling probability out of range"));
+            }
+            let thresh = reader.usize()?;
+            let processed = reader.usize()?;
+            let schedule = match reader.u8()? {
+                0 => Schedule::Fixed {
+                    stream_len: reader.usize()?,
+                },
+                1 => Schedule::Doubling {
+                    epoch: reader.u32()?,
+                    epoch_end: reader.usize()?,
+                },
+                _ => return Err(DecodeError::Invalid("unknown threshold schedule")),
+            };
+            let failed = reader.u8()? != 0;
+            let has_seed = reader.u8()? != 0;
+            let seed = Some(reader.u64()?).filter(|_| has_seed);
+
+            let mut state = [0; 4];
+            for word in &mut state {
+                *word = reader.u64()?;
+            }
+            if state == [0; 4] {
+                return Err(DecodeError::Invalid("generator state is all zeros"));
+            }
+            let gen = Gen {
+                state,
+                seed: reader.u64()?,
+            };
+
+            let hashes = reader.sorted_u64s()?;
+            if hashes.len() >= thresh.max(1) {
+                return Err(DecodeError::Invalid("sample is larger than its threshold"));
+            }
+            let mut chi = HashSample::default();
+            for hash in hashes {
+                chi.insert(hash);
+            }
+
+            Ok(Self {
+                estimator: CvmEstimator {
+                    gen,
+                    seed,
+                    eps,
+                    delta,
+                    p,
+                    chi,
+                    thresh,
+                    processed,
+                    schedule,
+                    failed,
+                    _item: PhantomData,
+                },
+            })
+        })
+    }
+}
+
+impl AddAssign for CvmSketch {
+    fn add_assign(&mut self, other: Self) {
+        self.merge(other);
+    }
+}
+
+impl<Q> DistinctCounter<Q> for CvmSketch
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        self.insert(&item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        DistinctCounter::memory_bytes(&self.estimator)
+    }
+
+    fn reset(&mut self) {
+        DistinctCounter::reset(&mut self.estimator);
+    }
+
+    fn clear(&mut self) {
+        DistinctCounter::clear(&mut self.estimator);
+    }
+}
+
+impl<Q> Extend<Q> for CvmSketch
+where
+    Q: Hash,
+{
+    fn extend<I: IntoIterator<Item = Q>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<Q> FromIterator<Q> for CvmSketch
+where
+    Q: Hash,
+{
+    /// Builds a sketch with [`DEFAULT_EPS`](crate::DEFAULT_EPS) and
+    /// [`DEFAULT_DELTA`](crate::DEFAULT_DELTA). See [`CvmEstimator::from_iter`].
+    fn from_iter<I: IntoIterator<Item = Q>>(iter: I) -> Self {
+        Self {
+            estimator: iter.into_iter().map(|item| hash64(&item)).collect(),
+        }
+    }
+}
+
+/// A [`CvmSketch`] which is fed through a shared reference, for counting a stream ingested by many
+/// threads into one sketch.
+///
+/// The sketch is split into stripes, each a [`CvmSketch`] behind a lock of its own. Elements are
+/// assigned to a stripe by hash, so every distinct element is counted by exactly one of them and
+/// threads inserting different elements rarely wait on each other. Each stripe runs with failure
+/// probability `delta / stripes`, so by the union bound the sum of their estimates is within a
+/// factor of `(1 ± eps)` of the true count with probability `1 - delta`, as with a single
+/// [`CvmSketch`]. Unlike merging sketches fed overlapping parts of the stream, which overcounts,
+/// this holds however the elements are spread over the threads. The stripes together use about
+/// `stripes` times the memory of a single sketch, so a few times the number of inserting threads
+/// is plenty.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{ConcurrentCvmSketch, Gen};
+/// use std::thread;
+///
+/// let sketch = ConcurrentCvmSketch::new(0.1, 0.005, 8, Some(Gen::new(None)));
+/// thread::scope(|scope| {
+///     for thread in 0..4 {
+///         let sketch = &sketch;
+///         scope.spawn(move || {
+///             for i in 0..1000 {
+///                 sketch.insert(&(i % (10 * (thread + 1))));
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(sketch.estimate(), 40);
+/// ```
+pub struct ConcurrentCvmSketch {
+    stripes: Box<[Mutex<CvmSketch>]>,
+    delta: f64,
+    seed: Option<u64>,
+}
+
+impl ConcurrentCvmSketch {
+    /// Creates an empty sketch for a stream of unknown length, split into `stripes` stripes (at
+    /// least one). The generators of the stripes are drawn from `gen`.
+    ///
+    /// # Panics
+    /// Panics if `eps` or `delta` are NaN or outside of `(0, 1)`.
+    pub fn new(eps: f64, delta: f64, stripes: usize, gen: Option<Gen>) -> Self {
+        Self::try_new(eps, delta, stripes, gen).expect("invalid estimator parameters")
+    }
+
+    /// Fallible version of [`ConcurrentCvmSketch::new`].
+    pub fn try_new(
+        eps: f64,
+        delta: f64,
+        stripes: usize,
+        gen: Option<Gen>,
+    ) -> Result<Self, DistinctError> {
+        validate(eps, delta)?;
+        let stripes = stripes.max(1);
+        let (mut gen, seed) = seeded(gen);
+        let stripes = (0..stripes)
+            .map(|_| {
+                let gen = Gen::new(Some(gen.next_u64()));
+                CvmSketch::try_unbounded(eps, delta / stripes as f64, Some(gen)).map(Mutex::new)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            stripes,
+            delta,
+            seed,
+        })
+    }
+
+    /// Returns the number of stripes the sketch is split into.
+    pub fn stripes(&self) -> usize {
+        self.stripes.len()
+    }
+
+    /// Feeds a single element of the stream into the sketch. Only its hash is kept.
+    pub fn insert<Q>(&self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item));
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`](crate::hash::hash64).
+    pub fn insert_hash(&self, hash: u64) {
+        self.lock(hash::partition(hash, self.stripes.len()))
+            .insert_hash(hash);
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far. See
+    /// [`CvmEstimator::estimate`].
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
+    }
+
+    /// Fallible version of [`ConcurrentCvmSketch::estimate`]. The stripes are read one after the
+    /// other, so an estimate taken while other threads keep inserting reflects some of the
+    /// concurrent insertions but not necessarily all of them.
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        let estimates = (0..self.stripes.len())
+            .map(|stripe| self.lock(stripe).try_estimate())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Estimate::partitioned_cvm(&estimates, self.delta, self.seed))
+    }
+
+    /// Returns the stripes fed so far, once no other thread can insert into them any more.
+    pub fn into_stripes(self) -> Vec<CvmSketch> {
+        Vec::from(self.stripes)
+            .into_iter()
+            .map(|stripe| stripe.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect()
+    }
+
+    /// Locks a stripe. A thread which panicked while holding the lock cannot have left the sketch
+    /// half updated in a way that matters for the estimate, so poisoning is ignored.
+    fn lock(&self, stripe: usize) -> MutexGuard<'_, CvmSketch> {
+        self.stripes[stripe]
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<Q> DistinctCounter<Q> for ConcurrentCvmSketch
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        ConcurrentCvmSketch::insert(self, &item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        let stripes: usize = (0..self.stripes.len())
+            .map(|stripe| DistinctCounter::<Q>::memory_bytes(&*self.lock(stripe)))
+            .sum();
+        mem::size_of::<Self>() + stripes
+    }
+
+    fn reset(&mut self) {
+        for stripe in self.stripes.iter_mut() {
+            let stripe = stripe.get_mut().unwrap_or_else(PoisonError::into_inner);
+            DistinctCounter::<Q>::reset(stripe);
+        }
+    }
+
+    fn clear(&mut self) {
+        for stripe in self.stripes.iter_mut() {
+            let stripe = stripe.get_mut().unwrap_or_else(PoisonError::into_inner);
+            DistinctCounter::<Q>::clear(stripe);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quickcheck::quickcheck;
+
+    fn assert_owned<T: Send + Sync + 'static>(_: &T) {}
+
+    #[test]
+    fn sketch_outlives_stream() {
+        let mut sketch = CvmSketch::unbounded(0.1, 0.005, Some(Gen::new(Some(1))));
+        {
+            let stream: Vec<String> = (0..5000).map(|i| format!("user-{}", i % 1000)).collect();
+            sketch.extend(&stream);
+        }
+
+        assert_owned(&sketch);
+        let handle = std::thread::spawn(move || sketch.estimate());
+        assert_eq!(handle.join().unwrap(), 1000);
+    }
+
+    #[test]
+    fn borrowed_and_owned_inputs_hash_alike() {
+        let mut owned = CvmSketch::new(0.1, 0.005, 2, Some(Gen::new(Some(1))));
+        owned.insert(&String::from("a"));
+        owned.insert("a");
+
+        assert_eq!(owned.estimate(), 1);
+    }
+
+    #[test]
+    fn concurrent_sketch_loses_no_updates() {
+        let sketch = ConcurrentCvmSketch::new(0.1, 0.01, 4, Some(Gen::new(Some(3))));
+        // Below every stripe's threshold the count is exact, so a lost update would show.
+        std::thread::scope(|scope| {
+            for thread in 0..8u64 {
+                let sketch = &sketch;
+                scope.spawn(move || {
+                    for i in 0..10_000 {
+                        sketch.insert(&(thread * 100 + i % 200));
+                    }
+                });
+            }
+        });
+
+        let estimate = sketch.try_estimate().unwrap();
+        assert_eq!(estimate.count(), 900);
+        assert_eq!(estimate.processed, 80_000);
+        assert_eq!(estimate.confidence, 0.99);
+    }
+
+    #[test]
+    fn concurrent_estimate_within_bounds_under_contention() {
+        let sketch = ConcurrentCvmSketch::new(0.1, 0.01, 4, Some(Gen::new(Some(5))));
+        // Overlapping ranges, so that every thread races with its neighbours on the same elements.
+        std::thread::scope(|scope| {
+            for thread in 0..8u64 {
+                let sketch = &sketch;
+                scope.spawn(move || {
+                    for i in thread * 50_000..thread * 50_000 + 150_000 {
+                        sketch.insert(&i);
+                    }
+                });
+            }
+        });
+
+        let distinct = 7.0 * 50_000.0 + 150_000.0;
+        let estimate = sketch.try_estimate().unwrap();
+        assert!(
+            estimate.lower <= distinct && distinct <= estimate.upper,
+            "{}",
+            estimate
+        );
+        assert!((estimate.value - distinct).abs() <= 0.1 * distinct);
+        assert_eq!(estimate.processed, 8 * 150_000);
+        let processed: usize = sketch
+            .into_stripes()
+            .iter()
+            .map(|stripe| stripe.try_estimate().unwrap().processed)
+            .sum();
+        assert_eq!(processed, 8 * 150_000);
+    }
+
+    quickcheck! {
+        fn qc_prop_bytes_round_trip(stream: Vec<u16>, rest: Vec<u16>, seed: u64) -> bool {
+            let mut sketch = CvmSketch::unbounded(0.5, 0.1, Some(Gen::new(Some(seed))));
+            sketch.extend(&stream);
+
+            let bytes = sketch.to_bytes();
+            let mut restored = CvmSketch::from_bytes(&bytes).unwrap();
+            if restored.to_bytes() != bytes || restored.try_estimate() != sketch.try_estimate() {
+                return false;
+            }
+
+            // Below the threshold nothing is halved, so both keep counting exactly.
+            sketch.extend(&rest);
+            restored.extend(&rest);
+            restored.try_estimate() == sketch.try_estimate()
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! The Theta sketch framework of Dasgupta, Lang, Rhodes and Thaler, as popularised by Apache
+//! DataSketches.
+//!
+//! A theta sketch keeps the hashes of the distinct elements it has seen which fall below a
+//! threshold `theta`, so that the retained hashes are a uniform sample of the stream at rate
+//! `theta`. Unlike a [`KmvSketch`](crate::KmvSketch), the threshold is explicit, which makes the
+//! sketches closed under set operations: the [`ThetaUnion`], [`ThetaIntersection`] and [`a_not_b`]
+//! of sketches are sketches again, and can be combined further into arbitrary set expressions.
+//!
+//! Hashes are the top 63 bits of [`hash64`], and `theta` is stored as a hash threshold in
+//! `1..=`[`MAX_THETA`].
+use std::{collections::HashSet, hash::Hash, mem};
+
+use crate::{
+    codec::{self, DecodeError, Kind, Reader, Writer},
+    datasketches,
+    hash::hash64,
+    DistinctCounter, DistinctError, Estimate,
+};
+
+mod set_ops;
+
+pub use set_ops::{a_not_b, ThetaIntersection, ThetaUnion};
+
+/// Smallest supported base 2 logarithm of the nominal number of entries.
+pub const MIN_LG_K: u8 = 4;
+
+/// Largest supported base 2 logarithm of the nominal number of entries.
+pub const MAX_LG_K: u8 = 26;
+
+/// Base 2 logarithm of the nominal number of entries used when a sketch is built without explicit
+/// parameters, e.g. through [`FromIterator`]. Gives a standard error of about 1.6%.
+pub const DEFAULT_LG_K: u8 = 12;
+
+/// The threshold of a sketch which has retained every hash, i.e. sampling rate `1`.
+pub const MAX_THETA: u64 = i64::MAX as u64;
+
+/// Number of standard deviations of the bounds reported by `try_estimate`.
+const CONFIDENCE_STD_DEVS: f64 = 1.96;
+
+/// An updatable theta sketch, using the QuickSelect algorithm.
+///
+/// The sketch retains between `k = 2^lg_k` and `2k` hashes. Whenever it holds more, `theta` is
+/// lowered to the `k + 1`-th smallest retained hash with a quickselect and the hashes at or above
+/// it are dropped. The estimate `retained / theta` has a relative standard error of about
+/// `1 / sqrt(k)`.
+///
+/// Call [`compact`](ThetaSketch::compact) to get the read-only form used by the set operations.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{theta, ThetaSketch, ThetaUnion};
+/// let mut mobile = ThetaSketch::new(12);
+/// let mut desktop = ThetaSketch::new(12);
+/// mobile.extend(0..60_000);
+/// desktop.extend(40_000..100_000);
+/// let (mobile, desktop) = (mobile.compact(), desktop.compact());
+///
+/// let mut union = ThetaUnion::new(12);
+/// union.update(&mobile);
+/// union.update(&desktop);
+/// let all = union.result().estimate();
+/// assert!(all.abs_diff(100_000) < 5_000);
+///
+/// let mobile_only = theta::a_not_b(&mobile, &desktop).estimate();
+/// assert!(mobile_only.abs_diff(40_000) < 5_000);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThetaSketch {
+    lg_k: u8,
+    theta: u64,
+    hashes: HashSet<u64>,
+    processed: usize,
+}
+
+impl ThetaSketch {
+    /// Creates an empty sketch with a nominal `2^lg_k` entries.
+    ///
+    /// # Panics
+    /// Panics if `lg_k` is outside of [`MIN_LG_K`]`..=`[`MAX_LG_K`].
+    pub fn new(lg_k: u8) -> Self {
+        Self::try_new(lg_k).expect("invalid theta sketch size")
+    }
+
+    /// Fallible version of [`ThetaSketch::new`].
+    pub fn try_new(lg_k: u8) -> Result<Self, DistinctError> {
+        if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
+            return Err(DistinctError::InvalidPrecision(lg_k));
+        }
+
+        Ok(Self {
+            lg_k,
+            theta: MAX_THETA,
+            hashes: HashSet::new(),
+            processed: 0,
+        })
+    }
+
+    /// Returns the base 2 logarithm of the nominal number of entries.
+    pub fn lg_k(&self) -> u8 {
+        self.lg_k
+    }
+
+    /// Returns the sampling rate of the sketch, in `(0, 1]`.
+    pub fn theta(&self) -> f64 {
+        fraction(self.theta)
+    }
+
+    /// Feeds a single element of the stream into the sketch.
+    pub fn insert<Q>(&mut self, item: &Q)
+    where
+        Q: Hash + ?Sized,
+    {
+        self.insert_hash(hash64(item));
+    }
+
+    /// Feeds an element which has already been hashed with [`hash64`].
+    pub fn insert_hash(&mut self, hash: u64) {
+        self.processed += 1;
+        self.retain_hash(hash >> 1);
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far.
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
+    }
+
+    /// Returns the current estimate of the number of distinct elements seen so far, with bounds
+    /// at 95% confidence. A theta sketch always produces an estimate; the `Result` matches
+    /// [`CvmEstimator::try_estimate`].
+    ///
+    /// [`CvmEstimator::try_estimate`]: crate::CvmEstimator::try_estimate
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        Ok(estimate(self.hashes.len(), self.theta, self.processed))
+    }
+
+    /// Returns the bounds of the estimate at `std_devs` standard deviations.
+    pub fn bounds(&self, std_devs: f64) -> (f64, f64) {
+        bounds(self.hashes.len(), self.theta, std_devs)
+    }
+
+    /// Returns the read-only form of the sketch, which the set operations take.
+    pub fn compact(&self) -> CompactThetaSketch {
+        let mut hashes: Vec<u64> = self.hashes.iter().copied().collect();
+        hashes.sort_unstable();
+
+        CompactThetaSketch {
+            theta: self.theta,
+            hashes,
+            processed: self.processed,
+        }
+    }
+
+    /// Serializes the sketch in the format described in [`codec`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut hashes: Vec<u64> = self.hashes.iter().copied().collect();
+        hashes.sort_unstable();
+
+        codec::encode(Kind::Theta, |writer| {
+            writer.u8(self.lg_k);
+            write_retained(writer, self.theta, &hashes, self.processed);
+        })
+    }
+
+    /// Reads back a sketch written by [`ThetaSketch::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        codec::decode(bytes, Kind::Theta, |reader, _| {
+            let lg_k = reader.u8()?;
+            if !(MIN_LG_K..=MAX_LG_K).contains(&lg_k) {
+                return Err(DecodeError::Invalid("sketch size out of range"));
+            }
+            let (theta, hashes, processed) = read_retained(reader)?;
+            if hashes.len() > 2 << lg_k {
+                return Err(DecodeError::Invalid("more hashes than the sketch retains"));
+            }
+
+            Ok(Self {
+                lg_k,
+                theta,
+                hashes: hashes.into_iter().collect(),
+                processed,
+            })
+        })
+    }
+
+    /// Retains a 63-bit hash if it is below `theta`, lowering `theta` if the sketch grows too
+    /// large. Hashes of zero are ignored.
+    fn retain_hash(&mut self, hash: u64) {
+        if hash == 0 || hash >= self.theta {
+            return;
+        }
+
+        self.hashes.insert(hash);
+        if self.hashes.len() > 2 << self.lg_k {
+            self.rebuild();
+        }
+    }
+
+    /// Lowers `theta` to the `k + 1`-th smallest retained hash, keeping the `k` below it.
+    fn rebuild(&mut self) {
+        let k = 1 << self.lg_k;
+        let mut hashes: Vec<u64> = self.hashes.drain().collect();
+        let (_, &mut theta, _) = hashes.select_nth_unstable(k);
+
+        self.theta = theta;
+        self.hashes.extend(&hashes[..k]);
+    }
+}
+
+impl<Q> DistinctCounter<Q> for ThetaSketch
+where
+    Q: Hash,
+{
+    fn insert(&mut self, item: Q) {
+        self.insert(&item);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.try_estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>() + self.hashes.capacity() * mem::size_of::<u64>()
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.lg_k);
+    }
+
+    fn clear(&mut self) {
+        self.theta = MAX_THETA;
+        self.hashes.clear();
+        self.processed = 0;
+    }
+}
+
+impl<Q> Extend<Q> for ThetaSketch
+where
+    Q: Hash,
+{
+    fn extend<I: IntoIterator<Item = Q>>(&mut self, iter: I) {
+        for item in iter {
+            self.insert(&item);
+        }
+    }
+}
+
+impl<Q> FromIterator<Q> for ThetaSketch
+where
+    Q: Hash,
+{
+    /// Builds a sketch with [`DEFAULT_LG_K`].
+    fn from_iter<I: IntoIterator<Item = Q>>(iter: I) -> Self {
+        let mut sketch = Self::new(DEFAULT_LG_K);
+        sketch.extend(iter);
+        sketch
+    }
+}
+
+/// The read-only form of a theta sketch: its threshold and the sorted hashes below it.
+///
+/// Produced by [`ThetaSketch::compact`] and by the set operations, and consumed by the set
+/// operations, so that set expressions can be nested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompactThetaSketch {
+    theta: u64,
+    hashes: Vec<u64>,
+    processed: usize,
+}
+
+impl CompactThetaSketch {
+    /// Returns the sampling rate of the sketch, in `(0, 1]`.
+    pub fn theta(&self) -> f64 {
+        fraction(self.theta)
+    }
+
+    /// Returns the retained 63-bit hashes in increasing order.
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Returns whether the sketch retains no hashes.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Returns the estimate of the number of distinct elements.
+    pub fn estimate(&self) -> usize {
+        self.try_estimate().map_or(0, |estimate| estimate.count())
+    }
+
+    /// Returns the estimate of the number of distinct elements, with bounds at 95% confidence.
+    pub fn try_estimate(&self) -> Result<Estimate, DistinctError> {
+        Ok(estimate(self.hashes.len(), self.theta, self.processed))
+    }
+
+    /// Returns the bounds of the estimate at `std_devs` standard deviations.
+    pub fn bounds(&self, std_devs: f64) -> (f64, f64) {
+        bounds(self.hashes.len(), self.theta, std_devs)
+    }
+
+    /// Serializes the sketch in the format described in [`codec`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        codec::encode(Kind::CompactTheta, |writer| {
+            write_retained(writer, self.theta, &self.hashes, self.processed);
+        })
+    }
+
+    /// Reads back a sketch written by [`CompactThetaSketch::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        codec::decode(bytes, Kind::CompactTheta, |reader, _| {
+            let (theta, hashes, processed) = read_retained(reader)?;
+            Ok(Self {
+                theta,
+                hashes,
+                processed,
+            })
+        })
+    }
+
+    /// Serializes the sketch as an Apache DataSketches compact theta sketch, see
+    /// [`datasketches`].
+    pub fn to_datasketches(&self) -> Vec<u8> {
+        datasketches::write_compact_theta(self.theta, &self.hashes)
+    }
+
+    /// Reads an Apache DataSketches compact theta sketch, see [`datasketches`].
+    pub fn from_datasketches(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (theta, hashes) = datasketches::read_compact_theta(bytes)?;
+        Ok(Self {
+            theta,
+            hashes,
+            processed: 0,
+        })
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        self.hashes.binary_search(&hash).is_ok()
+    }
+}
+
+/// Writes the part of the payload shared by both kinds of theta sketch.
+fn write_retained(writer: &mut Writer, theta: u64, sorted_hashes: &[u64], processed: usize) {
+    writer.u64(theta);
+    writer.usize(processed);
+    writer.usize(sorted_hashes.len());
+    for &hash in sorted_hashes {
+        writer.u64(hash);
+    }
+}
+
+/// Reads the part of the payload written by [`write_retained`], checking that every hash could
+/// have been retained below `theta`.
+fn read_retained(reader: &mut Reader) -> Result<(u64, Vec<u64>, usize), DecodeError> {
+    let theta = reader.u64()?;
+    let processed = reader.usize()?;
+    let hashes = reader.sorted_u64s()?;
+
+    if theta == 0 || theta > MAX_THETA {
+        return Err(DecodeError::Invalid("theta out of range"));
+    }
+    if hashes.first() == Some(&0) || hashes.last().is_some_and(|&hash| hash >= theta) {
+        return Err(DecodeError::Invalid("hash outside of (0, theta)"));
+    }
+    Ok((theta, hashes, processed))
+}
+
+/// Scales a hash threshold to the sampling rate it stands for.
+fn fraction(theta: u64) -> f64 {
+    theta as f64 / MAX_THETA as f64
+}
+
+fn estimate(retained: usize, theta: u64, processed: usize) -> Estimate {
+    Estimate::theta(
+        retained,
+        fraction(theta),
+        bounds(retained, theta, CONFIDENCE_STD_DEVS),
+        processed,
+    )
+}
+
+/// Bounds on the number of distinct elements when `retained` hashes were kept at a threshold of
+/// `theta`. The number retained is binomial with rate `theta`, so the estimate `retained / theta`
+/// has a standard deviation of `sqrt(retained * (1 - theta)) / theta`. The lower bound is never
+/// below `retained`, each of which stands for a distinct element, and the upper bound accounts for
+/// at least one unseen element so that it stays meaningful when nothing was retained.
+fn bounds(retained: usize, theta: u64, std_devs: f64) -> (f64, f64) {
+    let count = retained as f64;
+    if theta == MAX_THETA {
+        return (count, count);
+    }
+
+    let theta = fraction(theta);
+    let value = count / theta;
+    let std_dev = (count.max(1.0) * (1.0 - theta)).sqrt() / theta;
+
+    (
+        (value - std_devs * std_dev).max(count),
+        value + std_devs * std_dev,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Diagnostics;
+    use quickcheck::quickcheck;
+
+    #[test]
+    fn exact_until_k_hashes() {
+        let sketch: ThetaSketch = (0..1000).chain(0..1000).collect();
+        let estimate = sketch.try_estimate().unwrap();
+
+        assert_eq!(sketch.theta(), 1.0);
+        assert_eq!(
+            (estimate.value, estimate.lower, estimate.upper),
+            (1000.0, 1000.0, 1000.0)
+        );
+        assert_eq!(estimate.processed, 2000);
+        assert_eq!(
+            ThetaSketch::try_new(3),
+            Err(DistinctError::InvalidPrecision(3))
+        );
+    }
+
+    #[test]
+    fn estimate_within_bounds() {
+        for n in [10_000u64, 100_000, 1_000_000] {
+            let sketch: ThetaSketch = (0..n).collect();
+            let estimate = sketch.try_estimate().unwrap();
+            let (lower, upper) = sketch.bounds(3.0);
+
+            assert!(sketch.theta() < 1.0);
+            assert!(lower <= n as f64 && n as f64 <= upper);
+            assert!(lower <= estimate.lower && estimate.upper <= upper);
+            assert!((estimate.value - n as f64).abs() <= 3.0 * n as f64 / 64.0);
+
+            let Diagnostics::Theta { retained, .. } = estimate.diagnostics else {
+                panic!("not a theta estimate: {:?}", estimate);
+            };
+            assert!((1 << DEFAULT_LG_K..=2 << DEFAULT_LG_K).contains(&retained));
+            assert_eq!(sketch.compact().try_estimate(), Ok(estimate));
+        }
+    }
+
+    quickcheck! {
+        fn qc_prop_bytes_round_trip(stream: Vec<u32>, lg_k: u8) -> bool {
+            let mut sketch = ThetaSketch::new(MIN_LG_K + lg_k % 4);
+            sketch.extend(stream);
+            let compact = sketch.compact();
+
+            ThetaSketch::from_bytes(&sketch.to_bytes()) == Ok(sketch)
+                && CompactThetaSketch::from_bytes(&compact.to_bytes()) == Ok(compact)
+        }
+    }
+}
@@ -0,0 +1,184 @@
+//! Set operations over theta sketches.
+use super::{CompactThetaSketch, ThetaSketch, MAX_THETA};
+use crate::DistinctError;
+
+/// Computes the union of any number of theta sketches.
+///
+/// The union keeps an internal [`ThetaSketch`] of nominal size `2^lg_k`. Feeding it a sketch
+/// lowers the union's threshold to the sketch's, and inserts the sketch's hashes below it. The
+/// result is a sketch of the union of all the streams, and can be fed into further set operations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThetaUnion {
+    gadget: ThetaSketch,
+    theta: u64,
+}
+
+impl ThetaUnion {
+    /// Creates an empty union with a nominal `2^lg_k` entries.
+    ///
+    /// # Panics
+    /// Panics if `lg_k` is outside of [`MIN_LG_K`](super::MIN_LG_K)`..=`
+    /// [`MAX_LG_K`](super::MAX_LG_K).
+    pub fn new(lg_k: u8) -> Self {
+        Self::try_new(lg_k).expect("invalid theta sketch size")
+    }
+
+    /// Fallible version of [`ThetaUnion::new`].
+    pub fn try_new(lg_k: u8) -> Result<Self, DistinctError> {
+        Ok(Self {
+            gadget: ThetaSketch::try_new(lg_k)?,
+            theta: MAX_THETA,
+        })
+    }
+
+    /// Adds the stream of `sketch` to the union.
+    pub fn update(&mut self, sketch: &CompactThetaSketch) {
+        self.theta = self.theta.min(sketch.theta);
+        for &hash in sketch.hashes.iter().take_while(|&&hash| hash < self.theta) {
+            self.gadget.retain_hash(hash);
+        }
+        self.gadget.processed += sketch.processed;
+    }
+
+    /// Returns a sketch of the union of the streams added so far, retaining at most `2^lg_k`
+    /// hashes.
+    pub fn result(&self) -> CompactThetaSketch {
+        let mut result = self.gadget.compact();
+        result.theta = result.theta.min(self.theta);
+        result.hashes.retain(|&hash| hash < result.theta);
+
+        let k = 1 << self.gadget.lg_k;
+        if result.hashes.len() > k {
+            result.theta = result.hashes[k];
+            result.hashes.truncate(k);
+        }
+
+        result
+    }
+}
+
+/// Computes the intersection of any number of theta sketches.
+///
+/// The intersection keeps the hashes common to every sketch fed to it, below the smallest of their
+/// thresholds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThetaIntersection {
+    result: Option<CompactThetaSketch>,
+}
+
+impl ThetaIntersection {
+    /// Creates an intersection which has not been fed any sketch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Intersects the streams seen so far with the stream of `sketch`.
+    pub fn update(&mut self, sketch: &CompactThetaSketch) {
+        let Some(result) = &mut self.result else {
+            self.result = Some(sketch.clone());
+            return;
+        };
+
+        result.theta = result.theta.min(sketch.theta);
+        let theta = result.theta;
+        result
+            .hashes
+            .retain(|&hash| hash < theta && sketch.contains(hash));
+        result.processed += sketch.processed;
+    }
+
+    /// Returns a sketch of the intersection of the streams added so far, or `None` if no sketch
+    /// has been added, since the intersection of no sets is not defined.
+    pub fn result(&self) -> Option<CompactThetaSketch> {
+        self.result.clone()
+    }
+}
+
+/// Returns a sketch of the elements of `a`'s stream which do not appear in `b`'s.
+pub fn a_not_b(a: &CompactThetaSketch, b: &CompactThetaSketch) -> CompactThetaSketch {
+    let theta = a.theta.min(b.theta);
+
+    CompactThetaSketch {
+        theta,
+        hashes: a
+            .hashes
+            .iter()
+            .copied()
+            .take_while(|&hash| hash < theta)
+            .filter(|&hash| !b.contains(hash))
+            .collect(),
+        processed: a.processed + b.processed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sketch(range: std::ops::Range<u64>) -> CompactThetaSketch {
+        range.collect::<ThetaSketch>().compact()
+    }
+
+    fn assert_within_bounds(sketch: &CompactThetaSketch, expected: u64) {
+        let (lower, upper) = sketch.bounds(3.0);
+        assert!(
+            lower <= expected as f64 && expected as f64 <= upper,
+            "{} not in [{}, {}]",
+            expected,
+            lower,
+            upper
+        );
+    }
+
+    #[test]
+    fn set_expressions() {
+        let a = sketch(0..60_000);
+        let b = sketch(40_000..100_000);
+        let c = sketch(50_000..200_000);
+
+        let mut union = ThetaUnion::new(12);
+        union.update(&a);
+        union.update(&b);
+        let a_or_b = union.result();
+        assert!(a_or_b.hashes().len() <= 1 << 12);
+        assert_within_bounds(&a_or_b, 100_000);
+
+        let mut intersection = ThetaIntersection::new();
+        assert_eq!(intersection.result(), None);
+        intersection.update(&a);
+        intersection.update(&b);
+        let a_and_b = intersection.result().unwrap();
+        assert_within_bounds(&a_and_b, 20_000);
+
+        assert_within_bounds(&a_not_b(&a, &b), 40_000);
+        assert_within_bounds(&a_not_b(&b, &a), 40_000);
+
+        // (a | b) & c and (a | b) - c
+        intersection = ThetaIntersection::new();
+        intersection.update(&a_or_b);
+        intersection.update(&c);
+        assert_within_bounds(&intersection.result().unwrap(), 50_000);
+        assert_within_bounds(&a_not_b(&a_or_b, &c), 50_000);
+    }
+
+    #[test]
+    fn exact_sketches_give_exact_results() {
+        let a = sketch(0..60);
+        let b = sketch(40..100);
+
+        let mut union = ThetaUnion::new(8);
+        union.update(&a);
+        union.update(&b);
+        assert_eq!(union.result().estimate(), 100);
+
+        let mut intersection = ThetaIntersection::new();
+        intersection.update(&a);
+        intersection.update(&b);
+        assert_eq!(intersection.result().unwrap().estimate(), 20);
+
+        assert_eq!(a_not_b(&a, &b).estimate(), 40);
+        assert!(a_not_b(&a, &a).is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+//! Distinct counts over tumbling and hopping windows, rolled up from a counter per time bucket.
+use std::{collections::BTreeMap, mem, ops::Range};
+
+use crate::{DistinctCounter, DistinctError, Estimate, Mergeable};
+
+/// Keeps a [`Mergeable`] counter per bucket of time, and answers queries over any range of buckets
+/// by merging their counters, without going back to the elements.
+///
+/// Elements are inserted with a timestamp, in whatever unit suits the caller, and land in the
+/// bucket of `width` time units that holds it. Counters of minutes can then be rolled up into
+/// hours and days with [`windows`](WindowedCounter::windows), or merged over an arbitrary
+/// [`range`](WindowedCounter::range). Since merging a [`HyperLogLog`](crate::HyperLogLog) or
+/// [`KmvSketch`](crate::KmvSketch) gives exactly the sketch of the merged stream, a rollup has the
+/// accuracy of a single sketch however many buckets it spans, and an element seen in several
+/// buckets is counted once.
+///
+/// Buckets are kept while they hold any of the last `retention` time units, up to the latest
+/// timestamp inserted or passed to [`evict`](WindowedCounter::evict). Elements stamped in a bucket
+/// which is no longer kept are ignored.
+///
+/// # Examples
+/// ```rust
+/// use distinction::{KmvSketch, WindowedCounter};
+/// // Minute buckets kept for a day, with timestamps in seconds. KMV sketches count exactly up to
+/// // their number of hashes, which keeps the example deterministic.
+/// let mut counter = WindowedCounter::new(KmvSketch::new(1024), 60, 24 * 3600);
+/// for second in 0..7200 {
+///     counter.insert(format!("user-{}", second / 10), second);
+/// }
+/// // 720 users over two hours, 6 of them a minute, 360 an hour.
+/// assert_eq!(counter.range(0..7200).unwrap().estimate(), 720);
+/// assert_eq!(counter.range(600..660).unwrap().estimate(), 6);
+/// let hours = counter.windows(3600, 3600).unwrap();
+/// assert_eq!(hours.len(), 2);
+/// assert_eq!(hours[1].0, 3600);
+/// assert_eq!(hours[1].1.estimate(), 360);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowedCounter<C> {
+    empty: C,
+    width: u64,
+    retention: u64,
+    /// The counter of every bucket which has been inserted into, by start of the bucket.
+    buckets: BTreeMap<u64, C>,
+    latest: u64,
+}
+
+impl<C> WindowedCounter<C>
+where
+    C: Clone,
+{
+    /// Creates a counter with buckets `width` time units wide, kept for `retention` time units.
+    /// Every bucket starts as a clone of `empty`.
+    ///
+    /// # Panics
+    /// Panics if `width` is 0.
+    pub fn new(empty: C, width: u64, retention: u64) -> Self {
+        assert!(width > 0, "buckets must be at least one time unit wide");
+
+        Self {
+            empty,
+            width,
+            retention,
+            buckets: BTreeMap::new(),
+            latest: 0,
+        }
+    }
+
+    /// Returns the width of the buckets.
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+
+    /// Returns how long buckets are kept after the latest timestamp.
+    pub fn retention(&self) -> u64 {
+        self.retention
+    }
+
+    /// Feeds a single element of the stream, stamped with `ts`, into the counter of its bucket.
+    pub fn insert<Q>(&mut self, item: Q, ts: u64)
+    where
+        C: DistinctCounter<Q>,
+    {
+        self.evict(ts);
+        let start = ts - ts % self.width;
+        if self.expired(start) {
+            return;
+        }
+        let empty = &self.empty;
+        self.buckets
+            .entry(start)
+            .or_insert_with(|| empty.clone())
+            .insert(item);
+    }
+
+    /// Drops the buckets which have fallen out of retention at time `now`, e.g. while no elements
+    /// arrive. Has no effect if a later timestamp has already been inserted.
+    pub fn evict(&mut self, now: u64) {
+        self.latest = self.latest.max(now);
+        while let Some((&start, _)) = self.buckets.first_key_value() {
+            if !self.expired(start) {
+                break;
+            }
+            self.buckets.pop_first();
+        }
+    }
+
+    /// Returns the start of every bucket kept, along with its counter, oldest first.
+    pub fn buckets(&self) -> impl Iterator<Item = (u64, &C)> {
+        self.buckets
+            .iter()
+            .map(|(&start, counter)| (start, counter))
+    }
+
+    /// Merges the counters of every bucket overlapping `range` into one, which counts the
+    /// distinct elements stamped within the range rounded out to bucket boundaries.
+    pub fn range(&self, range: Range<u64>) -> Result<C, DistinctError>
+    where
+        C: Mergeable,
+    {
+        let mut merged = self.empty.clone();
+        if range.start < range.end {
+            let first = range.start - range.start % self.width;
+            for counter in self
+                .buckets
+                .range(first..range.end)
+                .map(|(_, counter)| counter)
+            {
+                merged.merge(counter)?;
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Rolls the buckets up into windows `size` time units wide, starting every `hop` time units
+    /// from time 0, and returns the start of every window holding at least one bucket along with
+    /// its merged counter, oldest first. Windows are tumbling when `hop` equals `size` and hopping
+    /// when it is smaller. Both are meant to be multiples of the bucket width, as windows are
+    /// rounded out to bucket boundaries.
+    ///
+    /// # Panics
+    /// Panics if `size` or `hop` is 0.
+    pub fn windows(&self, size: u64, hop: u64) -> Result<Vec<(u64, C)>, DistinctError>
+    where
+        C: Mergeable,
+    {
+        assert!(
+            size > 0 && hop > 0,
+            "windows must be at least one time unit wide"
+        );
+        let (Some((&first, _)), Some((&last, _))) = (
+            self.buckets.first_key_value(),
+            self.buckets.last_key_value(),
+        ) else {
+            return Ok(Vec::new());
+        };
+
+        // The first window ending after the oldest bucket starts, the last one starting before
+        // the newest bucket ends.
+        let first_window = first.checked_sub(size).map_or(0, |before| before / hop + 1);
+        let last_window = last.saturating_add(self.width - 1) / hop;
+        let mut windows = Vec::new();
+        for window in first_window..=last_window {
+            let start = window * hop;
+            let range = start..start.saturating_add(size);
+            let first_bucket = range.start - range.start % self.width;
+            if self.buckets.range(first_bucket..range.end).next().is_some() {
+                windows.push((start, self.range(range)?));
+            }
+        }
+        Ok(windows)
+    }
+
+    /// Whether the bucket starting at `start` is entirely older than the retention period.
+    fn expired(&self, start: u64) -> bool {
+        match self.latest.checked_sub(self.retention) {
+            Some(cutoff) => start.saturating_add(self.width - 1) <= cutoff,
+            None => false,
+        }
+    }
+}
+
+/// Elements are fed along with their timestamp, and estimates are over every bucket kept.
+impl<Q, C> DistinctCounter<(Q, u64)> for WindowedCounter<C>
+where
+    C: DistinctCounter<Q> + Mergeable + Clone,
+{
+    fn insert(&mut self, (item, ts): (Q, u64)) {
+        self.insert(item, ts);
+    }
+
+    fn estimate(&self) -> Result<Estimate, DistinctError> {
+        self.range(0..u64::MAX)?.estimate()
+    }
+
+    fn memory_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + self
+                .buckets
+                .values()
+                .map(|counter| mem::size_of::<u64>() + counter.memory_bytes())
+                .sum::<usize>()
+    }
+
+    /// The buckets are dropped either way, so this is the same as
+    /// [`clear`](DistinctCounter::clear).
+    fn reset(&mut self) {
+        DistinctCounter::<(Q, u64)>::clear(self);
+    }
+
+    fn clear(&mut self) {
+        self.buckets.clear();
+        self.latest = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ExactCounter, HyperLogLog};
+
+    #[test]
+    fn rollups_match_sketches_of_their_elements() {
+        let mut counter = WindowedCounter::new(HyperLogLog::new(12), 60, u64::MAX);
+        let stream: Vec<(u64, u64)> = (0..3 * 3600).map(|ts| (ts * 7 % 5000, ts)).collect();
+        for &(item, ts) in &stream {
+            counter.insert(item, ts);
+        }
+        let sketch = |range: Range<u64>| {
+            let mut hll = HyperLogLog::new(12);
+            for (item, _) in stream.iter().filter(|(_, ts)| range.contains(ts)) {
+                hll.insert(item);
+            }
+            hll
+        };
+
+        let hours = counter.windows(3600, 3600).unwrap();
+        let starts: Vec<u64> = hours.iter().map(|(start, _)| *start).collect();
+        assert_eq!(starts, [0, 3600, 7200]);
+        for (start, hll) in hours {
+            assert_eq!(hll, sketch(start..start + 3600));
+        }
+        // Ranges are rounded out to whole minutes.
+        assert_eq!(counter.range(90..5430).unwrap(), sketch(60..5460));
+        assert_eq!(counter.range(100..100).unwrap(), HyperLogLog::new(12));
+
+        // Half-hour windows every ten minutes, including the ones only partly covered.
+        let hopping = counter.windows(1800, 600).unwrap();
+        assert_eq!(hopping.first().map(|(start, _)| *start), Some(0));
+        assert_eq!(hopping.last().map(|(start, _)| *start), Some(10_200));
+        for (start, hll) in hopping {
+            assert_eq!(hll, sketch(start..start + 1800));
+        }
+    }
+
+    #[test]
+    fn old_buckets_are_evicted() {
+        let mut counter = WindowedCounter::new(ExactCounter::new(), 10, 100);
+        for ts in 0..1000 {
+            counter.insert(ts % 50, ts);
+        }
+        // Buckets holding any of the last 100 time units, i.e. after 899.
+        let starts: Vec<u64> = counter.buckets().map(|(start, _)| start).collect();
+        assert_eq!(starts, (900..1000).step_by(10).collect::<Vec<_>>());
+        assert_eq!(counter.range(0..1000).unwrap().estimate(), 50);
+
+        // Too old to be kept.
+        counter.insert(1000, 850);
+        assert_eq!(counter.range(0..1000).unwrap().estimate(), 50);
+
+        counter.evict(1045);
+        assert_eq!(counter.buckets().next().map(|(start, _)| start), Some(940));
+        let estimate = DistinctCounter::<(u64, u64)>::estimate(&counter).unwrap();
+        assert_eq!((estimate.count(), estimate.processed), (50, 60));
+    }
+}